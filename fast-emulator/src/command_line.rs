@@ -0,0 +1,73 @@
+use fast_protocol::RawResponse;
+
+/// Split an incoming command (without the trailing `\r`) into prefix, optional address and payload, e.g. `SL:A,1,2,14`
+/// or `RS@48:0FF0000`. Commands share their shape with responses, so the same `RawResponse` struct is used.
+pub(crate) fn parse_command(line: &str) -> Option<RawResponse> {
+  let line = line.trim();
+  if line.is_empty() {
+    return None;
+  }
+
+  let mut parts = line.splitn(2, ':');
+  let cmd = parts.next().unwrap_or("");
+  let payload = parts.next().unwrap_or("").to_string();
+
+  let mut cmd_parts = cmd.splitn(2, '@');
+  let prefix = cmd_parts.next().unwrap_or("").to_uppercase();
+  let address = cmd_parts.next().map(|s| s.to_string());
+
+  Some(RawResponse {
+    prefix,
+    address,
+    payload,
+  })
+}
+
+/// Parse a comma separated list of hex fields. Empty fields are read as 0, e.g. `DL:A,,,0`
+pub(crate) fn hex_fields(payload: &str) -> Option<Vec<u32>> {
+  payload
+    .split(',')
+    .map(|field| {
+      let field = field.trim();
+      if field.is_empty() {
+        Some(0)
+      } else {
+        u32::from_str_radix(field, 16).ok()
+      }
+    })
+    .collect()
+}
+
+/// Parse an expansion address such as `48` (board only) or `480` (board + breakout)
+pub(crate) fn expansion_address(address: &str) -> Option<(u8, Option<u8>)> {
+  if address.len() < 2 {
+    return None;
+  }
+
+  let board = u8::from_str_radix(&address[..2], 16).ok()?;
+  let breakout = match &address[2..] {
+    "" => None,
+    b => Some(u8::from_str_radix(b, 16).ok()?),
+  };
+  Some((board, breakout))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_addressed_command() {
+    let cmd = parse_command("RS@480:0FF0000").unwrap();
+    assert_eq!(cmd.prefix, "RS");
+    assert_eq!(cmd.address, Some("480".to_string()));
+    assert_eq!(cmd.payload, "0FF0000");
+    assert_eq!(expansion_address("480"), Some((0x48, Some(0))));
+  }
+
+  #[test]
+  fn test_hex_fields() {
+    assert_eq!(hex_fields("A,,,0"), Some(vec![10, 0, 0, 0]));
+    assert_eq!(hex_fields("A,Z"), None);
+  }
+}
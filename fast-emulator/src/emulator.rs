@@ -0,0 +1,334 @@
+use std::sync::{Arc, Mutex};
+
+use fast_protocol::RawResponse;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::command_line::parse_command;
+use crate::exp::ExpNet;
+use crate::io_net::IoNet;
+use crate::*;
+
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Identity reported in response to `ID:`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardIdentity {
+  pub processor: String,
+  pub product_number: String,
+  pub firmware_version: String,
+}
+
+impl BoardIdentity {
+  pub fn new(processor: &str, product_number: &str, firmware_version: &str) -> Self {
+    Self {
+      processor: processor.to_string(),
+      product_number: product_number.to_string(),
+      firmware_version: firmware_version.to_string(),
+    }
+  }
+}
+
+/// In-process emulation of a FAST Neuron's IO NET and EXP ports.
+///
+/// Usage:
+/// ```ignore
+/// let (emulator, io_net, exp) = NeuronEmulator::new()
+///   .with_io_board("FP-I/O-3208", 32, 8)
+///   .spawn();
+/// let machine = MachineBuilder::boot_with_transports(BootConfig::default(), io_net, exp, io_network, vec![]).await?;
+/// emulator.close_switch(5);
+/// ```
+pub struct NeuronEmulator {
+  identity: BoardIdentity,
+  io_boards: Vec<EmulatedIoBoard>,
+  expansion_boards: Vec<EmulatedExpansionBoard>,
+}
+
+impl NeuronEmulator {
+  /// A Neuron with its built-in expansion processor and no IO boards
+  pub fn new() -> Self {
+    Self {
+      identity: BoardIdentity::new("NET", "FP-CPU-2000", "2.13"),
+      io_boards: Vec::new(),
+      expansion_boards: vec![EmulatedExpansionBoard::neuron()],
+    }
+  }
+
+  pub fn with_firmware(mut self, firmware_version: &str) -> Self {
+    self.identity.firmware_version = firmware_version.to_string();
+    self
+  }
+
+  /// Add an IO board to the end of the IO NET loop
+  pub fn with_io_board(
+    mut self,
+    product_number: &str,
+    switch_count: usize,
+    driver_count: usize,
+  ) -> Self {
+    self.io_boards.push(EmulatedIoBoard {
+      identity: BoardIdentity::new("IO", product_number, "1.05"),
+      switch_count,
+      driver_count,
+    });
+    self
+  }
+
+  pub fn with_expansion_board(mut self, board: EmulatedExpansionBoard) -> Self {
+    self.expansion_boards.push(board);
+    self
+  }
+
+  /// Start serving the emulated ports. Returns a handle to control the emulated hardware along with the IO NET and
+  /// EXP transports to connect to. Must be called from within a tokio runtime.
+  pub fn spawn(self) -> (EmulatorHandle, DuplexStream, DuplexStream) {
+    let state = Arc::new(Mutex::new(EmulatorState {
+      io_net: IoNet::new(self.identity, self.io_boards),
+      exp: ExpNet::new(self.expansion_boards),
+    }));
+
+    let (io_events_sender, io_events_receiver) = mpsc::unbounded_channel();
+    let (io_client, io_server) = tokio::io::duplex(PIPE_CAPACITY);
+    let (exp_client, exp_server) = tokio::io::duplex(PIPE_CAPACITY);
+
+    tokio::spawn(serve(
+      "IO NET",
+      io_server,
+      state.clone(),
+      |state, cmd, now| state.io_net.handle(cmd, now),
+      Some(io_events_receiver),
+    ));
+    tokio::spawn(serve(
+      "EXP",
+      exp_server,
+      state.clone(),
+      |state, cmd, _now| state.exp.handle(cmd),
+      None,
+    ));
+
+    let handle = EmulatorHandle {
+      state,
+      io_events: io_events_sender,
+    };
+    (handle, io_client, exp_client)
+  }
+}
+
+impl Default for NeuronEmulator {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+pub(crate) struct EmulatorState {
+  io_net: IoNet,
+  exp: ExpNet,
+}
+
+type CommandHandler = fn(&mut EmulatorState, &RawResponse, Instant) -> Option<String>;
+
+async fn serve(
+  name: &'static str,
+  stream: DuplexStream,
+  state: Arc<Mutex<EmulatorState>>,
+  handler: CommandHandler,
+  mut events: Option<mpsc::UnboundedReceiver<String>>,
+) {
+  let (reader, mut writer) = tokio::io::split(stream);
+  let mut reader = BufReader::new(reader);
+  let mut buffer = Vec::new();
+
+  loop {
+    let next_event = async {
+      match events.as_mut() {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+      }
+    };
+
+    let output = tokio::select! {
+      read = reader.read_until(b'\r', &mut buffer) => {
+        match read {
+          Ok(0) | Err(_) => break,
+          Ok(_) => {}
+        }
+
+        let line = String::from_utf8_lossy(&buffer).to_string();
+        buffer.clear();
+        let Some(cmd) = parse_command(&line) else {
+          continue;
+        };
+
+        log::trace!("Emulated {} received {}", name, line.trim());
+        let response = handler(&mut state.lock().unwrap(), &cmd, Instant::now());
+        match response {
+          Some(response) => response,
+          None => continue,
+        }
+      }
+
+      Some(event) = next_event => event,
+    };
+
+    if writer.write_all(output.as_bytes()).await.is_err() {
+      break;
+    }
+  }
+
+  log::debug!("Emulated {} port closed", name);
+}
+
+/// Controls and inspects the emulated hardware while it is running
+#[derive(Clone)]
+pub struct EmulatorHandle {
+  state: Arc<Mutex<EmulatorState>>,
+  io_events: mpsc::UnboundedSender<String>,
+}
+
+impl EmulatorHandle {
+  /// Physically close or open a switch. Emits a switch event on IO NET according to the switch's configuration and
+  /// fires any drivers that follow the switch.
+  pub fn set_switch(&self, switch_id: usize, closed: bool) {
+    let event = self
+      .state
+      .lock()
+      .unwrap()
+      .io_net
+      .set_switch(switch_id, closed, Instant::now());
+
+    if let Some(event) = event {
+      let _ = self.io_events.send(event);
+    }
+  }
+
+  pub fn close_switch(&self, switch_id: usize) {
+    self.set_switch(switch_id, true);
+  }
+
+  pub fn open_switch(&self, switch_id: usize) {
+    self.set_switch(switch_id, false);
+  }
+
+  /// Physical state of the switch
+  pub fn is_switch_closed(&self, switch_id: usize) -> Option<bool> {
+    self.switch(switch_id).map(|s| s.closed)
+  }
+
+  pub fn switch(&self, switch_id: usize) -> Option<EmulatedSwitch> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .io_net
+      .switches
+      .get(switch_id)
+      .cloned()
+  }
+
+  pub fn driver(&self, driver_id: usize) -> Option<EmulatedDriver> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .io_net
+      .drivers
+      .get(driver_id)
+      .cloned()
+  }
+
+  /// Platform set by `CH:`, if the mainboard has been configured
+  pub fn platform(&self) -> Option<u16> {
+    self.state.lock().unwrap().io_net.platform
+  }
+
+  pub fn watchdog(&self) -> WatchdogState {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .io_net
+      .watchdog_state(Instant::now())
+  }
+
+  /// Current color of an LED as a hex string, e.g. `FF0000`. `None` if the LED has not been set since the last reset.
+  pub fn led(&self, address: u8, breakout: Option<u8>, index: u16) -> Option<String> {
+    self.state.lock().unwrap().exp.led(address, breakout, index)
+  }
+
+  pub fn led_port(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedLedPort> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .exp
+      .led_port(address, breakout, port)
+  }
+
+  pub fn servo(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedServo> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .exp
+      .servo(address, breakout, port)
+  }
+
+  pub fn stepper(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedStepper> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .exp
+      .stepper(address, breakout, port)
+  }
+
+  pub fn motor(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedMotor> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .exp
+      .motor(address, breakout, port)
+  }
+
+  /// Number of times the expansion board has received `BR:`
+  pub fn expansion_board_resets(&self, address: u8) -> u32 {
+    self.state.lock().unwrap().exp.resets(address)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::io::AsyncReadExt;
+
+  async fn read_line(stream: &mut DuplexStream) -> String {
+    let mut line = Vec::new();
+    loop {
+      let byte = stream.read_u8().await.unwrap();
+      if byte == b'\r' {
+        return String::from_utf8(line).unwrap();
+      }
+      line.push(byte);
+    }
+  }
+
+  #[tokio::test]
+  async fn test_serve_io_net() {
+    let (emulator, mut io_net, _exp) = NeuronEmulator::new()
+      .with_io_board("FP-I/O-3208", 32, 8)
+      .spawn();
+
+    io_net.write_all(b"\r\r\rID:\r").await.unwrap();
+    assert_eq!(read_line(&mut io_net).await, "ID:NET FP-CPU-2000 2.13");
+
+    io_net.write_all(b"CH:2000,1\r").await.unwrap();
+    assert_eq!(read_line(&mut io_net).await, "CH:P");
+    assert_eq!(emulator.platform(), Some(2000));
+
+    emulator.close_switch(3);
+    assert_eq!(read_line(&mut io_net).await, "-L:03");
+  }
+}
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use fast_protocol::RawResponse;
+
+use crate::BoardIdentity;
+use crate::command_line::{expansion_address, hex_fields};
+
+/// An expansion board on the emulated EXP bus. Breakout 0 is the board itself.
+#[derive(Debug, Clone)]
+pub struct EmulatedExpansionBoard {
+  pub address: u8,
+  pub identity: BoardIdentity,
+  pub breakouts: Vec<EmulatedBreakout>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmulatedBreakout {
+  pub led_ports: u8,
+  pub leds: u16,
+}
+
+impl EmulatedExpansionBoard {
+  /// A board with 4 local LED ports of 32 LEDs each and no external breakouts
+  pub fn new(address: u8, product_number: &str) -> Self {
+    Self {
+      address,
+      identity: BoardIdentity::new("EXP", product_number, "0.10"),
+      breakouts: vec![EmulatedBreakout {
+        led_ports: 4,
+        leds: 128,
+      }],
+    }
+  }
+
+  /// The expansion processor built into the Neuron
+  pub fn neuron() -> Self {
+    Self::new(0x48, "FP-EXP-2000")
+  }
+
+  pub fn with_breakout(mut self, led_ports: u8, leds: u16) -> Self {
+    self.breakouts.push(EmulatedBreakout { led_ports, leds });
+    self
+  }
+}
+
+/// LED port configuration as sent by `ER:`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatedLedPort {
+  pub led_type: u8,
+  pub start: u8,
+  pub count: u8,
+}
+
+/// Servo port state as configured by `MS:` and moved by `MP:`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmulatedServo {
+  pub min: u8,
+  pub max: u8,
+  pub home: u8,
+  /// Last position requested with `MP:`, limited to the configured travel
+  pub position: Option<u8>,
+  /// Time the last move was requested to take
+  pub move_time_ms: u32,
+}
+
+/// Stepper port state. Moves complete as soon as they are received, so `MQ:` never reports a stepper as moving.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmulatedStepper {
+  /// Steps from where the stepper was last homed with `MH:`
+  pub position: i32,
+  pub homes: u32,
+  pub stops: u32,
+}
+
+/// DC motor port state as last set by `MD:`. A speed of 0 is stopped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmulatedMotor {
+  pub reverse: bool,
+  pub speed: u8,
+  /// Time the motor was told to run for, 0 until stopped
+  pub time_ms: u32,
+}
+
+struct ExpBoardState {
+  definition: EmulatedExpansionBoard,
+  led_ports: HashMap<(u8, u8), EmulatedLedPort>,
+  servos: HashMap<(u8, u8), EmulatedServo>,
+  steppers: HashMap<(u8, u8), EmulatedStepper>,
+  motors: HashMap<(u8, u8), EmulatedMotor>,
+  leds: HashMap<(u8, u16), String>,
+  resets: u32,
+}
+
+pub(crate) struct ExpNet {
+  boards: Vec<ExpBoardState>,
+}
+
+impl ExpNet {
+  pub(crate) fn new(boards: Vec<EmulatedExpansionBoard>) -> Self {
+    Self {
+      boards: boards
+        .into_iter()
+        .map(|definition| ExpBoardState {
+          definition,
+          led_ports: HashMap::new(),
+          servos: HashMap::new(),
+          steppers: HashMap::new(),
+          motors: HashMap::new(),
+          leds: HashMap::new(),
+          resets: 0,
+        })
+        .collect(),
+    }
+  }
+
+  /// Handle an EXP command. Commands addressed to boards which are not present go unanswered, as on real hardware.
+  pub(crate) fn handle(&mut self, cmd: &RawResponse) -> Option<String> {
+    let (address, breakout) = cmd.address.as_deref().and_then(expansion_address)?;
+    let Some(board) = self
+      .boards
+      .iter_mut()
+      .find(|b| b.definition.address == address)
+    else {
+      log::debug!(
+        "Emulated EXP bus has no board at {:X}, ignoring {}",
+        address,
+        cmd.prefix
+      );
+      return None;
+    };
+    let breakout = breakout.unwrap_or(0);
+
+    let response = match cmd.prefix.as_str() {
+      "ID" => Some(format!(
+        "ID:{} {} {}",
+        board.definition.identity.processor,
+        board.definition.identity.product_number,
+        board.definition.identity.firmware_version
+      )),
+      "BR" => {
+        board.leds.clear();
+        board.servos.clear();
+        board.motors.clear();
+        board.resets += 1;
+        Some("BR:P".to_string())
+      }
+      "ER" => Some(board.configure_led_port(breakout, &cmd.payload)),
+      // LED updates are not acknowledged
+      "RS" => {
+        board.set_leds(breakout, &cmd.payload);
+        None
+      }
+      "IH" => Some(board.identify_breakout(breakout)),
+      "MS" => Some(board.configure_servo(breakout, &cmd.payload)),
+      // servo moves are not acknowledged
+      "MP" => {
+        board.move_servo(breakout, &cmd.payload);
+        None
+      }
+      "MH" => Some(board.home_stepper(breakout, &cmd.payload)),
+      "MX" => Some(board.stop_stepper(breakout, &cmd.payload)),
+      "MQ" => Some(board.stepper_status(breakout, &cmd.payload)),
+      // stepper moves are not acknowledged
+      "MA" | "MR" => {
+        board.move_stepper(breakout, &cmd.prefix, &cmd.payload);
+        None
+      }
+      "MD" => Some(board.drive_motor(breakout, &cmd.payload)),
+      _ => {
+        log::warn!("Emulated EXP bus received unknown command {}", cmd.prefix);
+        Some("XX:F".to_string())
+      }
+    };
+
+    response.map(|r| format!("{}\r", r))
+  }
+
+  pub(crate) fn led(&self, address: u8, breakout: Option<u8>, index: u16) -> Option<String> {
+    self
+      .board(address)?
+      .leds
+      .get(&(breakout.unwrap_or(0), index))
+      .cloned()
+  }
+
+  pub(crate) fn led_port(
+    &self,
+    address: u8,
+    breakout: Option<u8>,
+    port: u8,
+  ) -> Option<EmulatedLedPort> {
+    self
+      .board(address)?
+      .led_ports
+      .get(&(breakout.unwrap_or(0), port))
+      .cloned()
+  }
+
+  pub(crate) fn servo(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedServo> {
+    self
+      .board(address)?
+      .servos
+      .get(&(breakout.unwrap_or(0), port))
+      .cloned()
+  }
+
+  pub(crate) fn stepper(
+    &self,
+    address: u8,
+    breakout: Option<u8>,
+    port: u8,
+  ) -> Option<EmulatedStepper> {
+    self
+      .board(address)?
+      .steppers
+      .get(&(breakout.unwrap_or(0), port))
+      .cloned()
+  }
+
+  pub(crate) fn motor(&self, address: u8, breakout: Option<u8>, port: u8) -> Option<EmulatedMotor> {
+    self
+      .board(address)?
+      .motors
+      .get(&(breakout.unwrap_or(0), port))
+      .cloned()
+  }
+
+  pub(crate) fn resets(&self, address: u8) -> u32 {
+    self.board(address).map_or(0, |b| b.resets)
+  }
+
+  fn board(&self, address: u8) -> Option<&ExpBoardState> {
+    self.boards.iter().find(|b| b.definition.address == address)
+  }
+}
+
+impl ExpBoardState {
+  fn configure_led_port(&mut self, breakout: u8, payload: &str) -> String {
+    // port is hex, type/start/count are decimal
+    let fields: Vec<&str> = payload.split(',').collect();
+    let port = fields.first().and_then(|p| u8::from_str_radix(p, 16).ok());
+    let rest: Option<Vec<u8>> = fields[1..].iter().map(|f| f.parse::<u8>().ok()).collect();
+
+    match (port, rest.as_deref()) {
+      (Some(port), Some([led_type, start, count])) => {
+        self.led_ports.insert(
+          (breakout, port),
+          EmulatedLedPort {
+            led_type: *led_type,
+            start: *start,
+            count: *count,
+          },
+        );
+        "ER:P".to_string()
+      }
+      _ => "ER:F".to_string(),
+    }
+  }
+
+  fn configure_servo(&mut self, breakout: u8, payload: &str) -> String {
+    match hex_fields(payload).as_deref() {
+      Some([port, min, max, home]) if min <= max => {
+        self.servos.insert(
+          (breakout, *port as u8),
+          EmulatedServo {
+            min: *min as u8,
+            max: *max as u8,
+            home: *home as u8,
+            ..Default::default()
+          },
+        );
+        "MS:P".to_string()
+      }
+      _ => "MS:F".to_string(),
+    }
+  }
+
+  fn move_servo(&mut self, breakout: u8, payload: &str) {
+    let Some(&[port, position, time]) = hex_fields(payload).as_deref() else {
+      log::warn!("Emulated EXP bus received invalid servo move {}", payload);
+      return;
+    };
+    match self.servos.get_mut(&(breakout, port as u8)) {
+      Some(servo) => {
+        servo.position = Some((position as u8).clamp(servo.min, servo.max));
+        servo.move_time_ms = time;
+      }
+      None => log::warn!("Emulated servo port {} is not configured", port),
+    }
+  }
+
+  /// The stepper on a port, if the board is one which has steppers. Steppers need no configuration, so they exist
+  /// from the first command sent to them.
+  fn stepper_mut(&mut self, breakout: u8, port: u32) -> Option<&mut EmulatedStepper> {
+    if !self
+      .definition
+      .identity
+      .product_number
+      .starts_with("FP-EXP-0061")
+    {
+      return None;
+    }
+    Some(self.steppers.entry((breakout, port as u8)).or_default())
+  }
+
+  fn home_stepper(&mut self, breakout: u8, payload: &str) -> String {
+    let port = hex_fields(payload).and_then(|fields| fields.first().copied());
+    match port.and_then(|port| self.stepper_mut(breakout, port)) {
+      Some(stepper) => {
+        stepper.position = 0;
+        stepper.homes += 1;
+        "MH:P".to_string()
+      }
+      None => "MH:F".to_string(),
+    }
+  }
+
+  fn stop_stepper(&mut self, breakout: u8, payload: &str) -> String {
+    let port = hex_fields(payload).and_then(|fields| fields.first().copied());
+    match port.and_then(|port| self.stepper_mut(breakout, port)) {
+      Some(stepper) => {
+        stepper.stops += 1;
+        "MX:P".to_string()
+      }
+      None => "MX:F".to_string(),
+    }
+  }
+
+  fn stepper_status(&mut self, breakout: u8, payload: &str) -> String {
+    match hex_fields(payload).as_deref() {
+      Some(&[port]) => match self.stepper_mut(breakout, port) {
+        Some(stepper) => format!("MQ:{:X},{:X},0", port, stepper.position as u32),
+        None => "MQ:F".to_string(),
+      },
+      _ => "MQ:F".to_string(),
+    }
+  }
+
+  fn move_stepper(&mut self, breakout: u8, prefix: &str, payload: &str) {
+    let target = match (prefix, hex_fields(payload).as_deref()) {
+      ("MA", Some(&[port, position])) => Some((port, None, position as i32)),
+      ("MR", Some(&[port, direction, steps])) => {
+        let steps = steps as i32;
+        Some((port, Some(if direction == 1 { -steps } else { steps }), 0))
+      }
+      _ => None,
+    };
+    let Some((port, relative, position)) = target else {
+      log::warn!("Emulated EXP bus received invalid stepper move {}", payload);
+      return;
+    };
+
+    match self.stepper_mut(breakout, port) {
+      Some(stepper) => match relative {
+        Some(steps) => stepper.position = stepper.position.wrapping_add(steps),
+        None => stepper.position = position,
+      },
+      None => log::warn!("Emulated EXP board has no stepper on port {}", port),
+    }
+  }
+
+  fn drive_motor(&mut self, breakout: u8, payload: &str) -> String {
+    let product_number = &self.definition.identity.product_number;
+    if !product_number.starts_with("FP-EXP-0051") && !product_number.starts_with("FP-EXP-1313") {
+      return "MD:F".to_string();
+    }
+
+    match hex_fields(payload).as_deref() {
+      Some(&[port, direction, speed, time]) if direction <= 1 => {
+        self.motors.insert(
+          (breakout, port as u8),
+          EmulatedMotor {
+            reverse: direction == 1,
+            speed: speed as u8,
+            time_ms: time,
+          },
+        );
+        "MD:P".to_string()
+      }
+      _ => "MD:F".to_string(),
+    }
+  }
+
+  fn set_leds(&mut self, breakout: u8, payload: &str) {
+    // each entry is a hex LED index followed by a 6 character hex color, e.g. 1A00FF00
+    for entry in payload.split(',').filter(|e| e.len() > 6) {
+      let (index, color) = entry.split_at(entry.len() - 6);
+      match hex_fields(index) {
+        Some(index) => {
+          self
+            .leds
+            .insert((breakout, index[0] as u16), color.to_uppercase());
+        }
+        None => log::warn!("Emulated EXP bus received invalid LED entry {}", entry),
+      }
+    }
+  }
+
+  fn identify_breakout(&self, breakout: u8) -> String {
+    let address = format!("{:X}{:X}", self.definition.address, breakout);
+    match self.definition.breakouts.get(breakout as usize) {
+      Some(info) => format!(
+        "IH:{},A{},P{:02X},R{:02X}",
+        address,
+        if breakout == 0 { "L" } else { "" },
+        info.led_ports,
+        info.leds
+      ),
+      None => format!("IH:{},X", address),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::command_line::parse_command;
+
+  fn send(exp: &mut ExpNet, line: &str) -> Option<String> {
+    exp.handle(&parse_command(line).unwrap())
+  }
+
+  #[test]
+  fn test_set_leds() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::neuron()]);
+    assert_eq!(send(&mut exp, "ER@48:0,1,0,4"), Some("ER:P\r".to_string()));
+    assert_eq!(send(&mut exp, "RS@48:0FF0000,100FF00"), None);
+
+    assert_eq!(exp.led(0x48, None, 1), Some("00FF00".to_string()));
+    assert_eq!(send(&mut exp, "BR@48:"), Some("BR:P\r".to_string()));
+    assert_eq!(exp.led(0x48, None, 1), None);
+  }
+
+  #[test]
+  fn test_missing_board() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::neuron()]);
+    assert_eq!(send(&mut exp, "BR@B4:"), None);
+  }
+
+  #[test]
+  fn test_servo() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::new(0xB4, "FP-EXP-0071")]);
+    assert_eq!(send(&mut exp, "MP@B4:1,80,0"), None);
+    assert_eq!(exp.servo(0xB4, None, 1), None);
+
+    assert_eq!(
+      send(&mut exp, "MS@B4:1,10,F0,80"),
+      Some("MS:P\r".to_string())
+    );
+    assert_eq!(send(&mut exp, "MP@B4:1,FF,1F4"), None);
+    let servo = exp.servo(0xB4, None, 1).unwrap();
+    assert_eq!(servo.position, Some(0xF0));
+    assert_eq!(servo.move_time_ms, 500);
+  }
+
+  #[test]
+  fn test_stepper() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::new(0x90, "FP-EXP-0061")]);
+    assert_eq!(send(&mut exp, "MA@90:1,190"), None);
+    assert_eq!(exp.stepper(0x90, None, 1).unwrap().position, 400);
+
+    assert_eq!(send(&mut exp, "MR@90:1,1,1F4"), None);
+    assert_eq!(
+      send(&mut exp, "MQ@90:1"),
+      Some("MQ:1,FFFFFF9C,0\r".to_string())
+    );
+
+    assert_eq!(send(&mut exp, "MH@90:1"), Some("MH:P\r".to_string()));
+    assert_eq!(exp.stepper(0x90, None, 1).unwrap().position, 0);
+    assert_eq!(send(&mut exp, "MX@90:1"), Some("MX:P\r".to_string()));
+    assert_eq!(exp.stepper(0x90, None, 1).unwrap().stops, 1);
+  }
+
+  #[test]
+  fn test_stepper_missing() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::neuron()]);
+    assert_eq!(send(&mut exp, "MQ@48:0"), Some("MQ:F\r".to_string()));
+  }
+
+  #[test]
+  fn test_motor() {
+    let mut exp = ExpNet::new(vec![
+      EmulatedExpansionBoard::neuron(),
+      EmulatedExpansionBoard::new(0x30, "FP-EXP-1313"),
+    ]);
+    assert_eq!(send(&mut exp, "MD@48:0,0,FF,0"), Some("MD:F\r".to_string()));
+
+    assert_eq!(
+      send(&mut exp, "MD@30:0,1,C0,FA"),
+      Some("MD:P\r".to_string())
+    );
+    let motor = exp.motor(0x30, None, 0).unwrap();
+    assert!(motor.reverse);
+    assert_eq!(motor.speed, 0xC0);
+    assert_eq!(motor.time_ms, 250);
+
+    assert_eq!(send(&mut exp, "BR@30:"), Some("BR:P\r".to_string()));
+    assert_eq!(exp.motor(0x30, None, 0), None);
+  }
+
+  #[test]
+  fn test_identify_breakout() {
+    let mut exp = ExpNet::new(vec![EmulatedExpansionBoard::neuron().with_breakout(2, 64)]);
+    assert_eq!(
+      send(&mut exp, "IH@480:"),
+      Some("IH:480,AL,P04,R80\r".to_string())
+    );
+    assert_eq!(
+      send(&mut exp, "IH@481:"),
+      Some("IH:481,A,P02,R40\r".to_string())
+    );
+    assert_eq!(send(&mut exp, "IH@482:"), Some("IH:482,X\r".to_string()));
+  }
+}
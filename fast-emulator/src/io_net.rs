@@ -0,0 +1,442 @@
+use std::time::Duration;
+
+use fast_protocol::{DriverTrigger, RawResponse, SwitchReportingMode};
+use tokio::time::Instant;
+
+use crate::BoardIdentity;
+use crate::command_line::hex_fields;
+
+/// Driver modes which keep the driver on for as long as the trigger is active
+const HOLD_MODES: [u8; 4] = [0x18, 0x20, 0x5D, 0x5E];
+
+/// An IO board on the emulated IO NET loop
+#[derive(Debug, Clone)]
+pub struct EmulatedIoBoard {
+  pub identity: BoardIdentity,
+  pub switch_count: usize,
+  pub driver_count: usize,
+}
+
+/// State of a single switch as set by the test and configured by `SL:`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulatedSwitch {
+  /// Physical state of the switch, before any inversion is applied
+  pub closed: bool,
+  pub reporting: SwitchReportingMode,
+  pub debounce_close: Duration,
+  pub debounce_open: Duration,
+}
+
+impl Default for EmulatedSwitch {
+  fn default() -> Self {
+    Self {
+      closed: false,
+      reporting: SwitchReportingMode::ReportNormal,
+      debounce_close: Duration::from_millis(2),
+      debounce_open: Duration::from_millis(20),
+    }
+  }
+}
+
+/// State of a single driver as configured by `DL:` and triggered by `TL:` or its switch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmulatedDriver {
+  /// Driver mode as sent in `DL:`, e.g. `0x10` for pulse. `0` when disabled
+  pub mode: u8,
+  pub trigger: u8,
+  pub switch: Option<usize>,
+  /// Remaining mode specific parameters, as sent
+  pub params: Vec<u32>,
+  /// Whether the driver fires from its switch rather than only from `TL:` commands
+  pub switch_control: bool,
+  /// Number of times the driver has been fired
+  pub pulses: u32,
+  /// Whether the driver is currently held on
+  pub held: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogState {
+  Disabled,
+  Running { deadline: Instant },
+  Expired,
+}
+
+pub(crate) struct IoNet {
+  identity: BoardIdentity,
+  boards: Vec<EmulatedIoBoard>,
+  pub(crate) platform: Option<u16>,
+  pub(crate) switches: Vec<EmulatedSwitch>,
+  pub(crate) drivers: Vec<EmulatedDriver>,
+  watchdog: WatchdogState,
+}
+
+impl IoNet {
+  pub(crate) fn new(identity: BoardIdentity, boards: Vec<EmulatedIoBoard>) -> Self {
+    let switch_count = boards.iter().map(|b| b.switch_count).sum();
+    let driver_count = boards.iter().map(|b| b.driver_count).sum();
+
+    Self {
+      identity,
+      boards,
+      platform: None,
+      switches: vec![EmulatedSwitch::default(); switch_count],
+      drivers: vec![EmulatedDriver::default(); driver_count],
+      watchdog: WatchdogState::Disabled,
+    }
+  }
+
+  pub(crate) fn handle(&mut self, cmd: &RawResponse, now: Instant) -> Option<String> {
+    self.update_watchdog(now);
+
+    let response = match cmd.prefix.as_str() {
+      "ID" => self.identify(cmd.address.as_deref()),
+      "NN" => Some(self.node_info(&cmd.payload)),
+      "CH" => Some(self.configure_hardware(&cmd.payload)),
+      "SL" => Some(self.configure_switch(&cmd.payload)),
+      "DL" => Some(self.configure_driver(&cmd.payload)),
+      "TL" => Some(self.trigger_driver(&cmd.payload)),
+      "SA" => Some(self.report_switches()),
+      "WD" => Some(self.watchdog(&cmd.payload, now)),
+      _ => {
+        log::warn!("Emulated IO NET received unknown command {}", cmd.prefix);
+        Some("XX:F".to_string())
+      }
+    };
+
+    response.map(|r| format!("{}\r", r))
+  }
+
+  /// Change the physical state of a switch, returning the switch event to send, if any
+  pub(crate) fn set_switch(
+    &mut self,
+    switch_id: usize,
+    closed: bool,
+    now: Instant,
+  ) -> Option<String> {
+    self.update_watchdog(now);
+
+    let Some(switch) = self.switches.get_mut(switch_id) else {
+      log::warn!("Emulated switch {} does not exist", switch_id);
+      return None;
+    };
+
+    if switch.closed == closed {
+      return None;
+    }
+    switch.closed = closed;
+
+    let active = match switch.reporting {
+      SwitchReportingMode::ReportInverted => !closed,
+      _ => closed,
+    };
+    let reporting = switch.reporting;
+    self.run_switch_rules(switch_id, active);
+
+    if reporting == SwitchReportingMode::None {
+      return None;
+    }
+
+    let prefix = if active { "-L" } else { "/L" };
+    Some(format!("{}:{:02X}\r", prefix, switch_id))
+  }
+
+  pub(crate) fn watchdog_state(&mut self, now: Instant) -> WatchdogState {
+    self.update_watchdog(now);
+    self.watchdog
+  }
+
+  fn update_watchdog(&mut self, now: Instant) {
+    if let WatchdogState::Running { deadline } = self.watchdog
+      && now >= deadline
+    {
+      log::warn!("Emulated watchdog expired, high voltage is now off");
+      self.watchdog = WatchdogState::Expired;
+      for driver in self.drivers.iter_mut() {
+        driver.held = false;
+      }
+    }
+  }
+
+  fn drivers_powered(&self) -> bool {
+    matches!(self.watchdog, WatchdogState::Running { .. })
+  }
+
+  fn identify(&self, address: Option<&str>) -> Option<String> {
+    let identity = match address {
+      None => &self.identity,
+      Some(address) => {
+        let index = usize::from_str_radix(address, 16).ok()?;
+        &self.boards.get(index)?.identity
+      }
+    };
+
+    Some(format!(
+      "ID:{} {} {}",
+      identity.processor, identity.product_number, identity.firmware_version
+    ))
+  }
+
+  fn node_info(&self, payload: &str) -> String {
+    let board = u8::from_str_radix(payload, 16)
+      .ok()
+      .and_then(|node| self.boards.get(node as usize).map(|board| (node, board)));
+    match board {
+      Some((node, board)) => format!(
+        "NN:{:02X},{},{},{:02X},{:02X},00,00,00,00,00,00",
+        node,
+        board.identity.product_number,
+        board.identity.firmware_version,
+        board.driver_count,
+        board.switch_count
+      ),
+      None => "NN:F".to_string(),
+    }
+  }
+
+  fn configure_hardware(&mut self, payload: &str) -> String {
+    match payload.split(',').next().map(|p| p.parse::<u16>()) {
+      Some(Ok(platform)) => {
+        self.platform = Some(platform);
+        "CH:P".to_string()
+      }
+      _ => "CH:F".to_string(),
+    }
+  }
+
+  fn configure_switch(&mut self, payload: &str) -> String {
+    let Some(fields) = hex_fields(payload) else {
+      return "SL:F".to_string();
+    };
+
+    let reporting = match fields.get(1) {
+      Some(0) => SwitchReportingMode::None,
+      Some(1) => SwitchReportingMode::ReportNormal,
+      Some(2) => SwitchReportingMode::ReportInverted,
+      _ => return "SL:F".to_string(),
+    };
+
+    let Some(switch) = self.switches.get_mut(fields[0] as usize) else {
+      return "SL:F".to_string();
+    };
+
+    switch.reporting = reporting;
+    if let Some(close) = fields.get(2) {
+      switch.debounce_close = Duration::from_millis(*close as u64);
+    }
+    if let Some(open) = fields.get(3) {
+      switch.debounce_open = Duration::from_millis(*open as u64);
+    }
+    "SL:P".to_string()
+  }
+
+  fn configure_driver(&mut self, payload: &str) -> String {
+    let Some(fields) = hex_fields(payload) else {
+      return "DL:F".to_string();
+    };
+    if fields.len() < 4 {
+      return "DL:F".to_string();
+    }
+
+    let Some(driver) = self.drivers.get_mut(fields[0] as usize) else {
+      return "DL:F".to_string();
+    };
+
+    let trigger = fields[1] as u8;
+    let mode = fields[3] as u8;
+    driver.mode = mode;
+    driver.trigger = trigger;
+    driver.switch = (mode != 0).then_some(fields[2] as usize);
+    driver.params = fields[4..].to_vec();
+    driver.switch_control = mode != 0 && trigger & DriverTrigger::DISABLE_SWITCH.bits() == 0;
+    driver.held = false;
+    "DL:P".to_string()
+  }
+
+  fn trigger_driver(&mut self, payload: &str) -> String {
+    let Some(fields) = hex_fields(payload) else {
+      return "TL:F".to_string();
+    };
+    let driver_id = fields[0] as usize;
+    if driver_id >= self.drivers.len() || fields.len() < 2 {
+      return "TL:F".to_string();
+    }
+
+    let powered = self.drivers_powered();
+    let driver = &mut self.drivers[driver_id];
+    match fields[1] {
+      0 => driver.switch_control = true,
+      1 => fire(driver_id, driver, powered),
+      2 => driver.held = false,
+      3 => driver.held = powered,
+      _ => return "TL:F".to_string(),
+    }
+    "TL:P".to_string()
+  }
+
+  fn report_switches(&self) -> String {
+    let data = self
+      .switches
+      .chunks(8)
+      .map(|chunk| {
+        let byte = chunk.iter().enumerate().fold(0u8, |acc, (bit, switch)| {
+          acc | ((switch.closed as u8) << bit)
+        });
+        format!("{:02X}", byte)
+      })
+      .collect::<String>();
+
+    format!("SA:{:02X},{}", self.switches.len(), data)
+  }
+
+  fn watchdog(&mut self, payload: &str, now: Instant) -> String {
+    if payload.is_empty() {
+      return match self.watchdog {
+        WatchdogState::Disabled => "WD:00000000".to_string(),
+        WatchdogState::Expired => "WD:FFFFFFFF".to_string(),
+        WatchdogState::Running { deadline } => {
+          format!("WD:{:08X}", (deadline - now).as_millis())
+        }
+      };
+    }
+
+    match u64::from_str_radix(payload, 16) {
+      Ok(0) => {
+        self.watchdog = WatchdogState::Disabled;
+        "WD:P".to_string()
+      }
+      Ok(ms) => {
+        self.watchdog = WatchdogState::Running {
+          deadline: now + Duration::from_millis(ms),
+        };
+        "WD:P".to_string()
+      }
+      Err(_) => "WD:F".to_string(),
+    }
+  }
+
+  /// Fire drivers which are configured to follow the given switch
+  fn run_switch_rules(&mut self, switch_id: usize, active: bool) {
+    let powered = self.drivers_powered();
+    for (driver_id, driver) in self.drivers.iter_mut().enumerate() {
+      if !driver.switch_control || driver.switch != Some(switch_id) {
+        continue;
+      }
+
+      let inverted = driver.trigger & DriverTrigger::INVERT_SWITCH1.bits() != 0;
+      let triggered = active != inverted;
+      if HOLD_MODES.contains(&driver.mode) {
+        driver.held = triggered && powered;
+      } else if triggered {
+        fire(driver_id, driver, powered);
+      }
+    }
+  }
+}
+
+fn fire(driver_id: usize, driver: &mut EmulatedDriver, powered: bool) {
+  if powered {
+    driver.pulses += 1;
+  } else {
+    log::warn!(
+      "Emulated driver {} not fired, watchdog is not running",
+      driver_id
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::command_line::parse_command;
+
+  fn io_net() -> IoNet {
+    IoNet::new(
+      BoardIdentity::new("NET", "FP-CPU-2000", "2.13"),
+      vec![EmulatedIoBoard {
+        identity: BoardIdentity::new("IO", "FP-I/O-3208", "1.05"),
+        switch_count: 32,
+        driver_count: 8,
+      }],
+    )
+  }
+
+  fn send(io_net: &mut IoNet, line: &str, now: Instant) -> Option<String> {
+    io_net.handle(&parse_command(line).unwrap(), now)
+  }
+
+  #[test]
+  fn test_node_info() {
+    let mut io_net = io_net();
+    let now = Instant::now();
+
+    assert_eq!(
+      send(&mut io_net, "NN:00", now),
+      Some("NN:00,FP-I/O-3208,1.05,08,20,00,00,00,00,00,00\r".to_string())
+    );
+    assert_eq!(send(&mut io_net, "NN:01", now), Some("NN:F\r".to_string()));
+  }
+
+  #[test]
+  fn test_switch_report() {
+    let mut io_net = io_net();
+    let now = Instant::now();
+    io_net.set_switch(4, true, now);
+    io_net.set_switch(8, true, now);
+
+    assert_eq!(
+      send(&mut io_net, "SA:", now),
+      Some("SA:20,10010000\r".to_string())
+    );
+  }
+
+  #[test]
+  fn test_inverted_switch_event() {
+    let mut io_net = io_net();
+    let now = Instant::now();
+    assert_eq!(
+      send(&mut io_net, "SL:1A,2,2,14", now),
+      Some("SL:P\r".to_string())
+    );
+
+    assert_eq!(
+      io_net.set_switch(0x1A, true, now),
+      Some("/L:1A\r".to_string())
+    );
+    assert_eq!(
+      io_net.set_switch(0x1A, false, now),
+      Some("-L:1A\r".to_string())
+    );
+  }
+
+  #[test]
+  fn test_watchdog_expiry() {
+    let mut io_net = io_net();
+    let now = Instant::now();
+    send(&mut io_net, "DL:3,81,5,10,FA,FF,0,0,50", now);
+    send(&mut io_net, "WD:4E2", now);
+
+    send(&mut io_net, "TL:3,1", now);
+    assert_eq!(io_net.drivers[3].pulses, 1);
+
+    let later = now + Duration::from_millis(1300);
+    assert_eq!(
+      send(&mut io_net, "WD:", later),
+      Some("WD:FFFFFFFF\r".to_string())
+    );
+    send(&mut io_net, "TL:3,1", later);
+    assert_eq!(io_net.drivers[3].pulses, 1);
+  }
+
+  #[test]
+  fn test_switch_triggered_driver() {
+    let mut io_net = io_net();
+    let now = Instant::now();
+    send(&mut io_net, "WD:4E2", now);
+    send(&mut io_net, "DL:2,10,5,10,14,FF,0,0,50", now);
+
+    io_net.set_switch(5, true, now);
+    io_net.set_switch(5, false, now);
+    assert_eq!(io_net.drivers[2].pulses, 1);
+  }
+}
@@ -0,0 +1,11 @@
+mod command_line;
+mod emulator;
+mod exp;
+mod io_net;
+
+pub use emulator::*;
+pub use exp::{
+  EmulatedBreakout, EmulatedExpansionBoard, EmulatedLedPort, EmulatedMotor, EmulatedServo,
+  EmulatedStepper,
+};
+pub use io_net::{EmulatedDriver, EmulatedIoBoard, EmulatedSwitch, WatchdogState};
@@ -1,11 +1,17 @@
 use std::fmt::Display;
 
+use crate::RawResponse;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum FastResponseError {
   InvalidFormat,
   UnknownResponse,
   UnknownPrefix(String),
   Timeout,
+  /// The hardware answered the command with `F`
+  Failed,
+  /// The connection was lost before a response arrived
+  Disconnected,
 }
 
 impl Display for FastResponseError {
@@ -17,6 +23,68 @@ impl Display for FastResponseError {
         write!(f, "Unknown response prefix: {}", prefix)
       }
       FastResponseError::Timeout => write!(f, "Response timed out"),
+      FastResponseError::Failed => write!(f, "Command failed"),
+      FastResponseError::Disconnected => write!(f, "Connection lost"),
     }
   }
 }
+
+impl std::error::Error for FastResponseError {}
+
+/// A command which did not get a usable response, along with what was sent and received
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RequestError {
+  /// The command as sent, without the trailing `\r`, e.g. `BR@48:`
+  pub command: String,
+  /// The response, if one arrived
+  pub response: Option<RawResponse>,
+  pub error: FastResponseError,
+}
+
+impl RequestError {
+  pub fn new(command: &str, response: Option<RawResponse>, error: FastResponseError) -> Self {
+    Self {
+      command: command.trim_end_matches('\r').to_string(),
+      response,
+      error,
+    }
+  }
+
+  /// Command prefix, e.g. `BR` for `BR@48:`
+  pub fn prefix(&self) -> &str {
+    let header = self.command.split(':').next().unwrap_or("");
+    header.split('@').next().unwrap_or(header)
+  }
+}
+
+impl Display for RequestError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ({})", self.error, self.command)?;
+    if let Some(response) = &self.response {
+      write!(f, ", received {}", response)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for RequestError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request_error() {
+    let error = RequestError::new(
+      "BR@48:\r",
+      Some(RawResponse {
+        prefix: "BR".to_string(),
+        address: None,
+        payload: "F".to_string(),
+      }),
+      FastResponseError::Failed,
+    );
+    assert_eq!(error.prefix(), "BR");
+    assert_eq!(error.to_string(), "Command failed (BR@48:), received BR:F");
+  }
+}
@@ -30,6 +30,9 @@ impl FastCommand for IdentifyHardwareCommand {
   fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
     // Example: IH:480,AL,P04,R80
     let segments = raw.payload.split(',').collect::<Vec<&str>>();
+    if segments.len() < 2 {
+      return Err(FastResponseError::InvalidFormat);
+    }
 
     if segments[1] == "X" {
       return Ok(ExpansionBreakoutInfo::inactive(
@@ -146,6 +149,20 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_invalid() {
+    let cmd = IdentifyHardwareCommand::new(72, 0);
+    let raw_response = RawResponse {
+      prefix: "IH".to_string(),
+      payload: "F".to_string(),
+      ..Default::default()
+    };
+    assert_eq!(
+      cmd.parse(raw_response).err(),
+      Some(FastResponseError::InvalidFormat)
+    );
+  }
+
   #[test]
   fn test_active() {
     let cmd = IdentifyHardwareCommand::new(72, 0);
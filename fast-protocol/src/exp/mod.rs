@@ -2,10 +2,16 @@ mod board_reset;
 mod color;
 mod identify_hardware;
 mod leds;
+mod motors;
+mod servos;
+mod steppers;
 
 pub mod prelude {
   pub use crate::exp::board_reset::*;
   pub use crate::exp::color::*;
   pub use crate::exp::identify_hardware::*;
   pub use crate::exp::leds::*;
+  pub use crate::exp::motors::*;
+  pub use crate::exp::servos::*;
+  pub use crate::exp::steppers::*;
 }
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotorDirection {
+  Forward,
+  Reverse,
+}
+
+/// Run a DC motor in a direction at a PWM speed of 0-255. A speed of 0 stops the motor. The board stops the motor
+/// by itself once `time` passes, a time of zero runs until told otherwise.
+/// https://fastpinball.com/fast-serial-protocol/exp/md/
+pub struct DriveMotorCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+  direction: MotorDirection,
+  speed: u8,
+  time: Duration,
+}
+
+impl DriveMotorCommand {
+  pub fn new(
+    expansion_board: u8,
+    breakout: Option<u8>,
+    port: u8,
+    direction: MotorDirection,
+    speed: u8,
+    time: Duration,
+  ) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+      direction,
+      speed,
+      time,
+    }
+  }
+
+  pub fn stop(expansion_board: u8, breakout: Option<u8>, port: u8) -> Self {
+    Self::new(
+      expansion_board,
+      breakout,
+      port,
+      MotorDirection::Forward,
+      0,
+      Duration::ZERO,
+    )
+  }
+}
+
+impl FastCommand for DriveMotorCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "md"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    // direction is 0 for forward, 1 for reverse
+    format!(
+      "MD@{}:{:X},{},{:X},{:X}\r",
+      address,
+      self.port,
+      match self.direction {
+        MotorDirection::Forward => 0,
+        MotorDirection::Reverse => 1,
+      },
+      self.speed,
+      self.time.as_millis()
+    )
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    let command = DriveMotorCommand::new(
+      0x30,
+      None,
+      0,
+      MotorDirection::Reverse,
+      0xC0,
+      Duration::from_millis(250),
+    );
+    assert_eq!(command.to_string(), "MD@30:0,1,C0,FA\r");
+  }
+
+  #[test]
+  fn test_stop() {
+    assert_eq!(
+      DriveMotorCommand::stop(0x98, Some(1), 1).to_string(),
+      "MD@981:1,0,0,0\r"
+    );
+  }
+}
@@ -0,0 +1,3 @@
+mod drive_motor;
+
+pub use drive_motor::*;
@@ -0,0 +1,66 @@
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Set the travel limits and home position of a servo port. Positions are 0-255 across the servo's full range.
+/// https://fastpinball.com/fast-serial-protocol/exp/ms/
+pub struct ConfigureServoCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+  min: u8,
+  max: u8,
+  home: u8,
+}
+
+impl ConfigureServoCommand {
+  pub fn new(
+    expansion_board: u8,
+    breakout: Option<u8>,
+    port: u8,
+    min: u8,
+    max: u8,
+    home: u8,
+  ) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+      min,
+      max,
+      home,
+    }
+  }
+}
+
+impl FastCommand for ConfigureServoCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "ms"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!(
+      "MS@{}:{:X},{:X},{:X},{:X}\r",
+      address, self.port, self.min, self.max, self.home
+    )
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    let command = ConfigureServoCommand::new(0xB4, None, 2, 0x10, 0xF0, 0x80);
+    assert_eq!(command.to_string(), "MS@B4:2,10,F0,80\r");
+  }
+}
@@ -0,0 +1,5 @@
+mod configure_servo;
+mod move_servo;
+
+pub use configure_servo::*;
+pub use move_servo::*;
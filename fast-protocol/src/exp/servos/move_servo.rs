@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Move a servo to a position, taking the given time to get there. A time of zero moves as fast as the servo can.
+/// Like `RS:`, moves are not acknowledged.
+/// https://fastpinball.com/fast-serial-protocol/exp/mp/
+pub struct MoveServoCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+  position: u8,
+  time: Duration,
+}
+
+impl MoveServoCommand {
+  pub fn new(
+    expansion_board: u8,
+    breakout: Option<u8>,
+    port: u8,
+    position: u8,
+    time: Duration,
+  ) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+      position,
+      time,
+    }
+  }
+}
+
+impl FastCommand for MoveServoCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "mp"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!(
+      "MP@{}:{:X},{:X},{:X}\r",
+      address,
+      self.port,
+      self.position,
+      self.time.as_millis()
+    )
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    let command = MoveServoCommand::new(0xB4, None, 1, 0xC0, Duration::from_millis(500));
+    assert_eq!(command.to_string(), "MP@B4:1,C0,1F4\r");
+  }
+}
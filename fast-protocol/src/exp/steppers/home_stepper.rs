@@ -0,0 +1,50 @@
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Make the stepper's current location its home. Positions are counted in steps from home.
+/// https://fastpinball.com/fast-serial-protocol/exp/mh/
+pub struct HomeStepperCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+}
+
+impl HomeStepperCommand {
+  pub fn new(expansion_board: u8, breakout: Option<u8>, port: u8) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+    }
+  }
+}
+
+impl FastCommand for HomeStepperCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "mh"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!("MH@{}:{:X}\r", address, self.port)
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    let command = HomeStepperCommand::new(0x90, None, 1);
+    assert_eq!(command.to_string(), "MH@90:1\r");
+  }
+}
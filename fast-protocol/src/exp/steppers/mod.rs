@@ -0,0 +1,9 @@
+mod home_stepper;
+mod move_stepper;
+mod stepper_status;
+mod stop_stepper;
+
+pub use home_stepper::*;
+pub use move_stepper::*;
+pub use stepper_status::*;
+pub use stop_stepper::*;
@@ -0,0 +1,120 @@
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Move a stepper to a position, in steps from home. Positions are signed 32 bit values sent as hex, so positions
+/// behind home wrap around, e.g. -1 is `FFFFFFFF`. Like `MP:`, moves are not acknowledged, use `MQ:` to find out
+/// when the stepper arrives.
+/// https://fastpinball.com/fast-serial-protocol/exp/ma/
+pub struct MoveStepperCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+  position: i32,
+}
+
+impl MoveStepperCommand {
+  pub fn new(expansion_board: u8, breakout: Option<u8>, port: u8, position: i32) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+      position,
+    }
+  }
+}
+
+impl FastCommand for MoveStepperCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "ma"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!(
+      "MA@{}:{:X},{:X}\r",
+      address, self.port, self.position as u32
+    )
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+/// Move a stepper by a number of steps from wherever it is. Positive steps move forward, negative steps move in
+/// reverse. Moves are not acknowledged.
+/// https://fastpinball.com/fast-serial-protocol/exp/mr/
+pub struct MoveStepperRelativeCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+  steps: i32,
+}
+
+impl MoveStepperRelativeCommand {
+  pub fn new(expansion_board: u8, breakout: Option<u8>, port: u8, steps: i32) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+      steps,
+    }
+  }
+}
+
+impl FastCommand for MoveStepperRelativeCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "mr"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    // direction is 0 for forward, 1 for reverse
+    format!(
+      "MR@{}:{:X},{},{:X}\r",
+      address,
+      self.port,
+      if self.steps < 0 { 1 } else { 0 },
+      self.steps.unsigned_abs()
+    )
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    assert_eq!(
+      MoveStepperCommand::new(0x90, None, 0, 400).to_string(),
+      "MA@90:0,190\r"
+    );
+    assert_eq!(
+      MoveStepperCommand::new(0x90, None, 0, -1).to_string(),
+      "MA@90:0,FFFFFFFF\r"
+    );
+  }
+
+  #[test]
+  fn test_relative_request() {
+    assert_eq!(
+      MoveStepperRelativeCommand::new(0x90, None, 1, 200).to_string(),
+      "MR@90:1,0,C8\r"
+    );
+    assert_eq!(
+      MoveStepperRelativeCommand::new(0x90, None, 1, -200).to_string(),
+      "MR@90:1,1,C8\r"
+    );
+  }
+}
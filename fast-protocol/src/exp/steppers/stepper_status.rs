@@ -0,0 +1,116 @@
+use crate::common::expansion_addr;
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Query where a stepper is and whether it is still moving
+/// https://fastpinball.com/fast-serial-protocol/exp/mq/
+pub struct StepperStatusCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+}
+
+impl StepperStatusCommand {
+  pub fn new(expansion_board: u8, breakout: Option<u8>, port: u8) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+    }
+  }
+}
+
+impl FastCommand for StepperStatusCommand {
+  type Response = StepperStatusResponse;
+
+  fn prefix() -> &'static str {
+    "mq"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!("MQ@{}:{:X}\r", address, self.port)
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    if raw.payload.eq_ignore_ascii_case("f") {
+      return Ok(StepperStatusResponse::Failed);
+    }
+
+    // e.g. 1,190,0 -- port, position, moving
+    let parts: Vec<&str> = raw.payload.split(',').map(|part| part.trim()).collect();
+    if parts.len() < 3 {
+      return Err(FastResponseError::InvalidFormat);
+    }
+
+    let port = u8::from_str_radix(parts[0], 16).map_err(|_| FastResponseError::InvalidFormat)?;
+    let position =
+      u32::from_str_radix(parts[1], 16).map_err(|_| FastResponseError::InvalidFormat)? as i32;
+    let moving = match parts[2] {
+      "0" => false,
+      "1" => true,
+      _ => return Err(FastResponseError::InvalidFormat),
+    };
+
+    Ok(StepperStatusResponse::Status {
+      port,
+      position,
+      moving,
+    })
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepperStatusResponse {
+  Status {
+    port: u8,
+    /// Steps from home
+    position: i32,
+    moving: bool,
+  },
+  /// The port has no stepper
+  Failed,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    assert_eq!(
+      StepperStatusCommand::new(0x90, None, 1).to_string(),
+      "MQ@90:1\r"
+    );
+  }
+
+  #[test]
+  fn test_response() {
+    let result = StepperStatusCommand::new(0x90, None, 1).parse(RawResponse {
+      prefix: "MQ".to_string(),
+      payload: "1,FFFFFF38,1".to_string(),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      result,
+      Ok(StepperStatusResponse::Status {
+        port: 1,
+        position: -200,
+        moving: true,
+      })
+    );
+  }
+
+  #[test]
+  fn test_response_failed() {
+    let result = StepperStatusCommand::new(0x90, None, 3).parse(RawResponse {
+      prefix: "MQ".to_string(),
+      payload: "F".to_string(),
+      ..Default::default()
+    });
+
+    assert_eq!(result, Ok(StepperStatusResponse::Failed));
+  }
+}
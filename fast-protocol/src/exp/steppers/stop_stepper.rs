@@ -0,0 +1,50 @@
+use crate::common::{ProcessedResponse, expansion_addr};
+use crate::error::FastResponseError;
+use crate::fast_command::FastCommand;
+use crate::raw_response::RawResponse;
+
+/// Stop a stepper where it is, abandoning any move in progress
+/// https://fastpinball.com/fast-serial-protocol/exp/mx/
+pub struct StopStepperCommand {
+  expansion_board: u8,
+  breakout: Option<u8>,
+  port: u8,
+}
+
+impl StopStepperCommand {
+  pub fn new(expansion_board: u8, breakout: Option<u8>, port: u8) -> Self {
+    Self {
+      expansion_board,
+      breakout,
+      port,
+    }
+  }
+}
+
+impl FastCommand for StopStepperCommand {
+  type Response = ProcessedResponse;
+
+  fn prefix() -> &'static str {
+    "mx"
+  }
+
+  fn to_string(&self) -> String {
+    let address = expansion_addr(self.expansion_board, self.breakout);
+    format!("MX@{}:{:X}\r", address, self.port)
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    ProcessedResponse::parse(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_request() {
+    let command = StopStepperCommand::new(0x91, Some(2), 0);
+    assert_eq!(command.to_string(), "MX@912:0\r");
+  }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Display;
+
+/// A firmware version as reported by `ID:`, e.g. `2.13` or `01.05`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+  pub major: u16,
+  pub minor: u16,
+}
+
+impl FirmwareVersion {
+  pub const fn new(major: u16, minor: u16) -> Self {
+    Self { major, minor }
+  }
+
+  pub fn parse(version: &str) -> Option<Self> {
+    let (major, minor) = version
+      .trim()
+      .trim_start_matches(['v', 'V'])
+      .split_once('.')?;
+    Some(Self {
+      major: major.parse().ok()?,
+      minor: minor.parse().ok()?,
+    })
+  }
+}
+
+impl Display for FirmwareVersion {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}.{:02}", self.major, self.minor)
+  }
+}
+
+/// A feature which some firmware versions lack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+  /// A driver mode as sent in `DL:`, e.g. `0x75`
+  DriverMode(u8),
+  /// IO NET node enumeration with `NN:`
+  NodeInfo,
+  /// Breakout identification with `IH:`
+  IdentifyHardware,
+  /// LED port configuration with `ER:`
+  LedPorts,
+  /// Servo configuration and movement with `MS:` and `MP:`
+  Servos,
+  /// Stepper homing, movement and status with `MH:`, `MA:`, `MR:`, `MX:` and `MQ:`
+  Steppers,
+  /// DC motor control with `MD:`
+  Motors,
+}
+
+impl Display for Capability {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Capability::DriverMode(mode) => write!(f, "driver mode {:02X}", mode),
+      Capability::NodeInfo => write!(f, "node enumeration (NN)"),
+      Capability::IdentifyHardware => write!(f, "breakout identification (IH)"),
+      Capability::LedPorts => write!(f, "LED port configuration (ER)"),
+      Capability::Servos => write!(f, "servo control (MS/MP)"),
+      Capability::Steppers => write!(f, "stepper control (MH/MA/MR/MX/MQ)"),
+      Capability::Motors => write!(f, "DC motor control (MD)"),
+    }
+  }
+}
+
+/// Firmware known to work with a processor, as identified by the processor and product number reported by `ID:`
+#[derive(Debug, Clone)]
+pub struct FirmwareSupport {
+  pub processor: &'static str,
+  pub product_number: &'static str,
+  /// Oldest firmware which can run a machine at all
+  pub minimum: FirmwareVersion,
+  /// Newest firmware which has been run against this crate
+  pub known_good: FirmwareVersion,
+  /// Capabilities which need newer firmware than `minimum`, with the first version which supports them
+  pub capabilities: &'static [(Capability, FirmwareVersion)],
+}
+
+impl FirmwareSupport {
+  /// The first firmware version which supports the capability
+  pub fn required_for(&self, capability: Capability) -> FirmwareVersion {
+    self
+      .capabilities
+      .iter()
+      .find(|(c, _)| *c == capability)
+      .map_or(self.minimum, |(_, version)| *version)
+  }
+
+  pub fn supports(&self, version: FirmwareVersion, capability: Capability) -> bool {
+    version >= self.required_for(capability)
+  }
+}
+
+/// Known processors. Keep this up to date with the FAST firmware release notes as new versions are tested.
+pub const FIRMWARE_SUPPORT: &[FirmwareSupport] = &[
+  // Neuron IO NET processor
+  FirmwareSupport {
+    processor: "NET",
+    product_number: "FP-CPU-2000",
+    minimum: FirmwareVersion::new(2, 0),
+    known_good: FirmwareVersion::new(2, 13),
+    capabilities: &[
+      (Capability::DriverMode(0x75), FirmwareVersion::new(2, 13)),
+      (Capability::DriverMode(0x78), FirmwareVersion::new(2, 13)),
+      (Capability::NodeInfo, FirmwareVersion::new(2, 6)),
+    ],
+  },
+  // Neuron built-in expansion processor
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-2000",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // Standalone expansion boards. 0.10 is the only EXP firmware these have been run against, so every capability is
+  // listed from it until older releases are tested.
+  // DC motor board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-0051",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+      (Capability::Motors, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // Stepper board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-0061",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+      (Capability::Steppers, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // Servo board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-0071",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+      (Capability::Servos, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // LED board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-0081",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // LED board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-0091",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+    ],
+  },
+  // Shaker motor board
+  FirmwareSupport {
+    processor: "EXP",
+    product_number: "FP-EXP-1313",
+    minimum: FirmwareVersion::new(0, 10),
+    known_good: FirmwareVersion::new(0, 10),
+    capabilities: &[
+      (Capability::IdentifyHardware, FirmwareVersion::new(0, 10)),
+      (Capability::LedPorts, FirmwareVersion::new(0, 10)),
+      (Capability::Motors, FirmwareVersion::new(0, 10)),
+    ],
+  },
+];
+
+/// Compatibility information for a processor, if it's known
+pub fn firmware_support(processor: &str, product_number: &str) -> Option<&'static FirmwareSupport> {
+  FIRMWARE_SUPPORT
+    .iter()
+    .find(|s| s.processor == processor && s.product_number == product_number)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_version() {
+    assert_eq!(
+      FirmwareVersion::parse("02.13"),
+      Some(FirmwareVersion::new(2, 13))
+    );
+    assert_eq!(
+      FirmwareVersion::parse("0.10"),
+      Some(FirmwareVersion::new(0, 10))
+    );
+    assert_eq!(FirmwareVersion::parse("unknown"), None);
+    assert!(FirmwareVersion::new(2, 6) < FirmwareVersion::new(2, 13));
+    assert_eq!(FirmwareVersion::new(1, 5).to_string(), "1.05");
+  }
+
+  #[test]
+  fn test_capabilities() {
+    let neuron = firmware_support("NET", "FP-CPU-2000").unwrap();
+    let version = FirmwareVersion::new(2, 6);
+    assert!(neuron.supports(version, Capability::DriverMode(0x10)));
+    assert!(neuron.supports(version, Capability::NodeInfo));
+    assert!(!neuron.supports(version, Capability::DriverMode(0x75)));
+    assert!(firmware_support("NET", "FP-CPU-0000").is_none());
+
+    let servo_board = firmware_support("EXP", "FP-EXP-0071").unwrap();
+    assert!(servo_board.supports(FirmwareVersion::new(0, 10), Capability::Servos));
+    assert!(!servo_board.supports(FirmwareVersion::new(0, 9), Capability::Servos));
+  }
+}
@@ -3,15 +3,17 @@ mod error;
 mod event_response;
 pub mod exp;
 mod fast_command;
+mod firmware;
 pub mod net;
 mod raw_response;
 
 pub use crate::common::ProcessedResponse;
 pub use crate::exp::prelude::*;
 pub use crate::fast_command::*;
+pub use crate::firmware::*;
 pub use crate::net::prelude::*;
 pub use crate::raw_response::RawResponse;
-pub use error::FastResponseError;
+pub use error::{FastResponseError, RequestError};
 pub use event_response::*;
 
 pub enum FastAddress {
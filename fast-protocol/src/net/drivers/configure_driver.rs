@@ -3,18 +3,21 @@ use crate::net::prelude::*;
 use crate::*;
 
 /// Configure a driver in Fast IO boards (DL)
-pub struct ConfigureDriverCommand<'a> {
-  driver_id: &'a usize,
-  config: &'a DriverConfig,
+pub struct ConfigureDriverCommand {
+  driver_id: usize,
+  config: DriverConfig,
 }
 
-impl ConfigureDriverCommand<'_> {
-  pub fn new<'a>(driver_id: &'a usize, config: &'a DriverConfig) -> ConfigureDriverCommand<'a> {
-    ConfigureDriverCommand { driver_id, config }
+impl ConfigureDriverCommand {
+  pub fn new(driver_id: &usize, config: &DriverConfig) -> ConfigureDriverCommand {
+    ConfigureDriverCommand {
+      driver_id: *driver_id,
+      config: config.clone(),
+    }
   }
 }
 
-impl FastCommand for ConfigureDriverCommand<'_> {
+impl FastCommand for ConfigureDriverCommand {
   type Response = ProcessedResponse;
 
   fn prefix() -> &'static str {
@@ -23,7 +26,7 @@ impl FastCommand for ConfigureDriverCommand<'_> {
 
   fn to_string(&self) -> String {
     // https://fastpinball.com/fast-serial-protocol/net/dl/
-    match self.config {
+    match &self.config {
       DriverConfig::Disabled => format!("DL:{:X},,,0\r", self.driver_id),
       DriverConfig::Pulse {
         switch,
@@ -100,7 +103,7 @@ impl FastCommand for ConfigureDriverCommand<'_> {
         secondary_pwm_power,
         rest,
       } => format!(
-        "DL:{:X},{:X},{:X},75,{:X},{:X},{:X},{:X},{:X}\r",
+        "DL:{:X},{:X},{:X},20,{:X},{:X},{:X},{:X},{:X}\r",
         self.driver_id,
         DriverTriggerBuilder::new()
           .invert_switch1(invert_switch)
@@ -114,6 +117,76 @@ impl FastCommand for ConfigureDriverCommand<'_> {
         secondary_pwm_power,
         rest.as_millis()
       ),
+      // without a cancel switch mode 75 would cancel on switch 0, so it's a plain pulse instead
+      DriverConfig::PulseCancel {
+        switch,
+        invert_switch,
+        cancel_switch: None,
+        initial_pwm_length,
+        initial_pwm_power,
+        secondary_pwm_length,
+        secondary_pwm_power,
+        ..
+      } => format!(
+        "DL:{:X},{:X},{:X},10,{:X},{:X},{:X},{:X},0\r",
+        self.driver_id,
+        DriverTriggerBuilder::new()
+          .invert_switch1(invert_switch)
+          .disable_switch(true)
+          .bits(),
+        switch.unwrap_or(0),
+        initial_pwm_length.as_millis(),
+        initial_pwm_power,
+        secondary_pwm_length.as_millis(),
+        secondary_pwm_power
+      ),
+      DriverConfig::PulseCancel {
+        switch,
+        invert_switch,
+        cancel_switch: Some(cancel_switch),
+        invert_cancel_switch,
+        initial_pwm_length,
+        initial_pwm_power,
+        secondary_pwm_length,
+        secondary_pwm_power,
+      } => format!(
+        "DL:{:X},{:X},{:X},75,{:X},{:X},{:X},{:X},{:X}\r",
+        self.driver_id,
+        DriverTriggerBuilder::new()
+          .invert_switch1(invert_switch)
+          .invert_switch2(invert_cancel_switch)
+          .disable_switch(true)
+          .bits(),
+        switch.unwrap_or(0),
+        cancel_switch,
+        initial_pwm_length.as_millis(),
+        initial_pwm_power,
+        secondary_pwm_length.as_millis(),
+        secondary_pwm_power
+      ),
+      DriverConfig::PulseHoldExtension {
+        switch,
+        invert_switch,
+        initial_pwm_length,
+        initial_pwm_power,
+        secondary_pwm_power,
+        extension_length,
+        rest,
+      } => format!(
+        "DL:{:X},{:X},{:X},78,{:X},{:X},{:X},{:X},{:X}\r",
+        self.driver_id,
+        DriverTriggerBuilder::new()
+          .manual(switch.is_none())
+          .invert_switch1(invert_switch)
+          .disable_switch(switch.is_none())
+          .bits(),
+        switch.unwrap_or(0),
+        initial_pwm_length.as_millis(),
+        initial_pwm_power,
+        secondary_pwm_power,
+        extension_length.as_millis(),
+        rest.as_millis()
+      ),
       DriverConfig::DelayedPulse {
         switch,
         invert_switch,
@@ -236,4 +309,86 @@ mod tests {
     let request_str = ConfigureDriverCommand::new(&10, &config).to_string();
     assert_eq!(request_str, "DL:A,91,5,10,64,FF,32,7F,1F4\r");
   }
+
+  #[test]
+  fn test_pulse_hold_cancel_driver() {
+    let config = DriverConfig::PulseHoldCancel {
+      switch: Some(1),
+      invert_switch: Some(false),
+      off_switch: Some(2),
+      invert_off_switch: Some(false),
+      initial_pwm_length: Duration::from_millis(30),
+      secondary_pwm_length: Duration::from_millis(500),
+      secondary_pwm_power: Power::percent(10),
+      rest: Duration::from_millis(500),
+    };
+    let request_str = ConfigureDriverCommand::new(&0, &config).to_string();
+    assert_eq!(request_str, "DL:0,80,1,20,2,1E,1F4,19,1F4\r");
+    assert_eq!(config.mode(), 0x20);
+  }
+
+  #[test]
+  fn test_pulse_cancel_driver() {
+    let config = DriverConfig::PulseCancel {
+      switch: Some(0x12),
+      invert_switch: Some(false),
+      cancel_switch: Some(0x13),
+      invert_cancel_switch: Some(true),
+      initial_pwm_length: Duration::from_millis(40),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_length: Duration::from_millis(20),
+      secondary_pwm_power: Power::percent(50),
+    };
+    let request_str = ConfigureDriverCommand::new(&3, &config).to_string();
+    assert_eq!(request_str, "DL:3,A0,12,75,13,28,FF,14,7F\r");
+    assert_eq!(config.mode(), 0x75);
+  }
+
+  #[test]
+  fn test_pulse_cancel_without_cancel_switch() {
+    let config = DriverConfig::PulseCancel {
+      switch: Some(0x12),
+      invert_switch: Some(false),
+      cancel_switch: None,
+      invert_cancel_switch: Some(true),
+      initial_pwm_length: Duration::from_millis(40),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_length: Duration::from_millis(20),
+      secondary_pwm_power: Power::percent(50),
+    };
+    let request_str = ConfigureDriverCommand::new(&3, &config).to_string();
+    assert_eq!(request_str, "DL:3,80,12,10,28,FF,14,7F,0\r");
+    assert_eq!(config.mode(), 0x10);
+  }
+
+  #[test]
+  fn test_pulse_hold_extension_driver() {
+    let config = DriverConfig::PulseHoldExtension {
+      switch: Some(0x20),
+      invert_switch: Some(false),
+      initial_pwm_length: Duration::from_millis(30),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_power: Power::percent(25),
+      extension_length: Duration::from_millis(200),
+      rest: Duration::from_millis(100),
+    };
+    let request_str = ConfigureDriverCommand::new(&4, &config).to_string();
+    assert_eq!(request_str, "DL:4,0,20,78,1E,FF,3F,C8,64\r");
+    assert_eq!(config.mode(), 0x78);
+  }
+
+  #[test]
+  fn test_pulse_hold_extension_manual() {
+    let config = DriverConfig::PulseHoldExtension {
+      switch: None,
+      invert_switch: None,
+      initial_pwm_length: Duration::from_millis(30),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_power: Power::percent(25),
+      extension_length: Duration::from_millis(200),
+      rest: Duration::ZERO,
+    };
+    let request_str = ConfigureDriverCommand::new(&4, &config).to_string();
+    assert_eq!(request_str, "DL:4,C0,0,78,1E,FF,3F,C8,0\r");
+  }
 }
@@ -41,6 +41,26 @@ pub enum DriverConfig {
     secondary_pwm_length: Duration,
     rest: Duration,
   },
+  PulseCancel {
+    switch: Option<usize>,
+    invert_switch: Option<bool>,
+    /// Without one the driver is sent as a plain pulse (mode 10)
+    cancel_switch: Option<usize>,
+    invert_cancel_switch: Option<bool>,
+    initial_pwm_length: Duration,
+    initial_pwm_power: Power,
+    secondary_pwm_length: Duration,
+    secondary_pwm_power: Power,
+  },
+  PulseHoldExtension {
+    switch: Option<usize>,
+    invert_switch: Option<bool>,
+    initial_pwm_length: Duration,
+    initial_pwm_power: Power,
+    secondary_pwm_power: Power,
+    extension_length: Duration,
+    rest: Duration,
+  },
   DelayedPulse {
     switch: Option<usize>,
     invert_switch: Option<bool>,
@@ -78,6 +98,27 @@ pub enum DriverConfig {
 }
 
 impl DriverConfig {
+  /// Driver mode number as sent in `DL:`, e.g. `0x10` for pulse
+  pub fn mode(&self) -> u8 {
+    match self {
+      DriverConfig::Disabled => 0x00,
+      DriverConfig::Pulse { .. } => 0x10,
+      DriverConfig::PulseKick { .. } => 0x12,
+      DriverConfig::PulseHold { .. } => 0x18,
+      DriverConfig::PulseHoldCancel { .. } => 0x20,
+      DriverConfig::PulseCancel {
+        cancel_switch: None,
+        ..
+      } => 0x10,
+      DriverConfig::PulseCancel { .. } => 0x75,
+      DriverConfig::PulseHoldExtension { .. } => 0x78,
+      DriverConfig::DelayedPulse { .. } => 0x30,
+      DriverConfig::LongPulse { .. } => 0x70,
+      DriverConfig::FlipperMainDirect { .. } => 0x5E,
+      DriverConfig::FlipperHoldDirect { .. } => 0x5D,
+    }
+  }
+
   pub fn switch_id(&self) -> Option<usize> {
     match self {
       DriverConfig::Disabled => None,
@@ -85,6 +126,8 @@ impl DriverConfig {
       DriverConfig::PulseKick { switch, .. } => *switch,
       DriverConfig::PulseHold { switch, .. } => *switch,
       DriverConfig::PulseHoldCancel { switch, .. } => *switch,
+      DriverConfig::PulseCancel { switch, .. } => *switch,
+      DriverConfig::PulseHoldExtension { switch, .. } => *switch,
       DriverConfig::DelayedPulse { switch, .. } => *switch,
       DriverConfig::LongPulse { switch, .. } => *switch,
       DriverConfig::FlipperMainDirect { button_switch, .. } => Some(*button_switch),
@@ -1,7 +1,9 @@
 mod configure_hardware;
 mod id;
+mod node_info;
 mod watchdog;
 
 pub use configure_hardware::*;
 pub use id::*;
+pub use node_info::*;
 pub use watchdog::*;
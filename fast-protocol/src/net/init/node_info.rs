@@ -0,0 +1,105 @@
+use crate::*;
+
+/// Query a single node on the IO NET loop. Nodes are numbered in loop order starting at 0.
+/// https://fastpinball.com/fast-serial-protocol/net/nn/
+pub struct NodeInfoCommand {
+  node: u8,
+}
+
+impl NodeInfoCommand {
+  pub fn new(node: u8) -> Self {
+    Self { node }
+  }
+}
+
+impl FastCommand for NodeInfoCommand {
+  type Response = NodeInfoResponse;
+
+  fn prefix() -> &'static str {
+    "nn"
+  }
+
+  fn to_string(&self) -> String {
+    format!("NN:{:02X}\r", self.node)
+  }
+
+  fn parse(&self, raw: RawResponse) -> Result<Self::Response, FastResponseError> {
+    if raw.payload.eq_ignore_ascii_case("f") {
+      return Ok(NodeInfoResponse::Failed);
+    }
+
+    // e.g. 00,FP-I/O-3208-2   ,01.00,08,20,04,06,00,00,00,00
+    let parts: Vec<&str> = raw.payload.split(',').map(|part| part.trim()).collect();
+    if parts.len() < 5 {
+      return Err(FastResponseError::InvalidFormat);
+    }
+
+    let node = u8::from_str_radix(parts[0], 16).map_err(|_| FastResponseError::InvalidFormat)?;
+    let driver_count =
+      u32::from_str_radix(parts[3], 16).map_err(|_| FastResponseError::InvalidFormat)?;
+    let switch_count =
+      u32::from_str_radix(parts[4], 16).map_err(|_| FastResponseError::InvalidFormat)?;
+
+    Ok(NodeInfoResponse::Report {
+      node,
+      name: parts[1].to_string(),
+      firmware_version: parts[2].to_string(),
+      driver_count,
+      switch_count,
+    })
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeInfoResponse {
+  Report {
+    node: u8,
+    name: String,
+    firmware_version: String,
+    driver_count: u32,
+    switch_count: u32,
+  },
+  /// There is no node at this position on the loop
+  Failed,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_command() {
+    assert_eq!(NodeInfoCommand::new(1).to_string(), "NN:01\r");
+  }
+
+  #[test]
+  fn test_response_report() {
+    let result = NodeInfoCommand::new(0).parse(RawResponse {
+      prefix: "NN".to_string(),
+      payload: "00,FP-I/O-3208-2   ,01.00,08,20,04,06,00,00,00,00".to_string(),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      result,
+      Ok(NodeInfoResponse::Report {
+        node: 0,
+        name: "FP-I/O-3208-2".to_string(),
+        firmware_version: "01.00".to_string(),
+        driver_count: 8,
+        switch_count: 32,
+      })
+    );
+  }
+
+  #[test]
+  fn test_response_failed() {
+    let result = NodeInfoCommand::new(3).parse(RawResponse {
+      prefix: "NN".to_string(),
+      payload: "F".to_string(),
+      ..Default::default()
+    });
+
+    assert_eq!(result, Ok(NodeInfoResponse::Failed));
+  }
+}
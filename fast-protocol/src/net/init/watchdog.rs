@@ -53,10 +53,11 @@ pub fn response(data: &str) -> Result<WatchdogResponse, FastResponseError> {
     return Ok(WatchdogResponse::Failed);
   } else if data == "00000000" {
     Ok(WatchdogResponse::WatchdogDisabled)
-  } else if data == "FFFFFFFF" {
+  } else if data.eq_ignore_ascii_case("FFFFFFFF") {
     Ok(WatchdogResponse::WatchdogExpired)
   } else {
-    match data.parse::<u64>() {
+    // remaining time is reported in hex milliseconds
+    match u64::from_str_radix(data, 16) {
       Ok(remaining) => Ok(WatchdogResponse::WatchdogRemaining(Duration::from_millis(
         remaining,
       ))),
@@ -97,4 +98,20 @@ mod tests {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), WatchdogResponse::WatchdogExpired);
   }
+
+  #[test]
+  fn test_response_remaining() {
+    let result = response("000004E2");
+    assert_eq!(
+      result,
+      Ok(WatchdogResponse::WatchdogRemaining(Duration::from_millis(
+        1250
+      )))
+    );
+  }
+
+  #[test]
+  fn test_remaining_command() {
+    assert_eq!(WatchdogCommand::remaining().to_string(), "WD:\r");
+  }
 }
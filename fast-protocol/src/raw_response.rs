@@ -1,6 +1,18 @@
+use std::fmt::Display;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct RawResponse {
   pub prefix: String,
   pub address: Option<String>,
   pub payload: String,
 }
+
+/// Formats as the line received, e.g. `ID@48:EXP FP-EXP-2000 0.10`, without the trailing `\r`
+impl Display for RawResponse {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.address {
+      Some(address) => write!(f, "{}@{}:{}", self.prefix, address, self.payload),
+      None => write!(f, "{}:{}", self.prefix, self.payload),
+    }
+  }
+}
@@ -66,3 +66,141 @@ pub fn derive_frontbox_event(input: TokenStream) -> TokenStream {
 
   TokenStream::from(expanded)
 }
+
+/// Derive `SwitchId` for an enum of switch names, so a misspelled switch is a compile error rather than a switch
+/// which is silently never found.
+///
+/// Usage:
+/// ```ignore
+///   #[derive(Clone, Copy, SwitchId)]
+///   enum Switches {
+///     StartButton,             // "start_button"
+///     #[name = "trough_1"]
+///     TroughOne,
+///   }
+/// ```
+#[proc_macro_derive(SwitchId, attributes(name))]
+pub fn derive_switch_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(SwitchId), quote!(switch_name))
+}
+
+/// Derive `DriverId` for an enum of driver names. See `SwitchId` for naming.
+#[proc_macro_derive(DriverId, attributes(name))]
+pub fn derive_driver_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(DriverId), quote!(driver_name))
+}
+
+/// Derive `DriverGroupId` for an enum of driver group names. See `SwitchId` for naming.
+#[proc_macro_derive(DriverGroupId, attributes(name))]
+pub fn derive_driver_group_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(DriverGroupId), quote!(driver_group_name))
+}
+
+/// Derive `LedId` for an enum of LED names. See `SwitchId` for naming.
+#[proc_macro_derive(LedId, attributes(name))]
+pub fn derive_led_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(LedId), quote!(led_name))
+}
+
+/// Derive `MotorId` for an enum of motor names. See `SwitchId` for naming.
+#[proc_macro_derive(MotorId, attributes(name))]
+pub fn derive_motor_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(MotorId), quote!(motor_name))
+}
+
+/// Derive `ServoId` for an enum of servo names. See `SwitchId` for naming.
+#[proc_macro_derive(ServoId, attributes(name))]
+pub fn derive_servo_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(ServoId), quote!(servo_name))
+}
+
+/// Derive `StepperId` for an enum of stepper names. See `SwitchId` for naming.
+#[proc_macro_derive(StepperId, attributes(name))]
+pub fn derive_stepper_id(input: TokenStream) -> TokenStream {
+  derive_hardware_id(input, quote!(StepperId), quote!(stepper_name))
+}
+
+/// Implement one of the hardware id traits for an enum of unit variants, naming each variant in snake case unless
+/// it has a `#[name = "..."]` attribute
+fn derive_hardware_id(
+  input: TokenStream,
+  trait_name: proc_macro2::TokenStream,
+  method: proc_macro2::TokenStream,
+) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  let name = &input.ident;
+
+  let syn::Data::Enum(data) = &input.data else {
+    return syn::Error::new_spanned(
+      name,
+      format!("{} can only be derived for enums", trait_name),
+    )
+    .to_compile_error()
+    .into();
+  };
+
+  let mut arms = Vec::new();
+  for variant in &data.variants {
+    if !matches!(variant.fields, syn::Fields::Unit) {
+      return syn::Error::new_spanned(
+        variant,
+        format!("{} variants can't have fields", trait_name),
+      )
+      .to_compile_error()
+      .into();
+    }
+
+    let renamed = variant
+      .attrs
+      .iter()
+      .find(|attr| attr.path().is_ident("name"));
+    let hardware_name = match renamed {
+      Some(attr) => match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+          value:
+            syn::Expr::Lit(syn::ExprLit {
+              lit: syn::Lit::Str(lit),
+              ..
+            }),
+          ..
+        }) => lit.value(),
+        _ => {
+          return syn::Error::new_spanned(attr, "expected #[name = \"...\"]")
+            .to_compile_error()
+            .into();
+        }
+      },
+      None => to_snake_case(&variant.ident.to_string()),
+    };
+
+    let ident = &variant.ident;
+    arms.push(quote! { #name::#ident => #hardware_name });
+  }
+
+  let expanded = quote! {
+      impl #trait_name for #name {
+          fn #method(&self) -> &'static str {
+              match self {
+                  #(#arms,)*
+              }
+          }
+      }
+  };
+
+  TokenStream::from(expanded)
+}
+
+fn to_snake_case(s: &str) -> String {
+  let mut snake = String::new();
+  for (i, c) in s.chars().enumerate() {
+    if c.is_uppercase() {
+      if i > 0 {
+        snake.push('_');
+      }
+      snake.extend(c.to_lowercase());
+    } else {
+      snake.push(c);
+    }
+  }
+  snake
+}
@@ -0,0 +1,83 @@
+use fast_emulator::NeuronEmulator;
+use frontbox::plugins::free_play::FreePlay;
+use frontbox::prelude::*;
+use std::io::Write;
+
+// This example runs the machine against the software Neuron emulator instead of real hardware. A scripted task
+// presses the start button, which starts a game and fires the knocker.
+
+// Hardware names are enums, so a misspelled switch or driver doesn't compile
+#[derive(Clone, Copy, SwitchId)]
+pub enum Switches {
+  StartButton,
+  LeftSling,
+}
+
+#[derive(Clone, Copy, DriverId)]
+pub enum Drivers {
+  Knocker,
+  LeftSling,
+}
+
+#[tokio::main]
+async fn main() {
+  env_logger::Builder::from_default_env()
+    .format(|buf, record| writeln!(buf, "[{}] {}\r", record.level(), record.args()))
+    .init();
+
+  let (emulator, io_net, exp) = NeuronEmulator::new()
+    .with_io_board("FP-I/O-3208", 32, 8)
+    .spawn();
+
+  let mut io_network = IoNetworkBuilder::new();
+  io_network.add_board(
+    FastIoBoards::io_3208()
+      .with_switch(Switches::StartButton, 0)
+      .with_switch(Switches::LeftSling, 1)
+      .with_driver_cfg(Drivers::Knocker, 0, PulseMode::default())
+      // fired by the hardware as soon as the sling switch closes
+      .with_driver_cfg(
+        Drivers::LeftSling,
+        1,
+        PulseMode {
+          trigger_mode: DriverTriggerMode::switch(Switches::LeftSling),
+          ..Default::default()
+        },
+      ),
+  );
+
+  let mut machine = MachineBuilder::boot_with_transports(
+    BootConfig::default(),
+    io_net,
+    exp,
+    io_network.build(),
+    vec![],
+  )
+  .await
+  .expect("Failed to boot machine")
+  .build();
+
+  // press and release the start button once the machine is running
+  let script = emulator.clone();
+  tokio::spawn(async move {
+    tokio::time::sleep(Duration::from_millis(1000)).await;
+    script.close_switch(0);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    script.open_switch(0);
+  });
+
+  let _ = tokio::time::timeout(
+    Duration::from_secs(3),
+    machine.run(vec![
+      FreePlay::new(Switches::StartButton),
+      OnEventSystem::<GameStarted>::new(|_ctx, cmds| {
+        cmds.driver.activate(Drivers::Knocker, ActivationMode::Tap);
+      }),
+    ]),
+  )
+  .await;
+
+  let knocker = emulator.driver(0).expect("knocker driver exists");
+  log::info!("Knocker fired {} time(s)", knocker.pulses);
+  assert_eq!(knocker.pulses, 1);
+}
@@ -66,13 +66,14 @@ async fn main() {
       ),
   );
 
-  MachineBuilder::boot(BootConfig::default(), io_network.build(), vec![])
+  let boot_config = BootConfig::default()
+    .with_keyboard_mapping(KeyCode::Char('1'), switches::LOWER_DROP_TARGET1)
+    .with_keyboard_mapping(KeyCode::Char('2'), switches::LOWER_DROP_TARGET2)
+    .with_keyboard_mapping(KeyCode::Char('3'), switches::LOWER_DROP_TARGET3);
+
+  MachineBuilder::boot(boot_config, io_network.build(), vec![])
     .await
-    .add_keyboard_mappings(vec![
-      (KeyCode::Char('1'), switches::LOWER_DROP_TARGET1),
-      (KeyCode::Char('2'), switches::LOWER_DROP_TARGET2),
-      (KeyCode::Char('3'), switches::LOWER_DROP_TARGET3),
-    ])
+    .expect("Failed to boot machine")
     .build()
     .run(vec![DropTargetDownUp::new([
       switches::LOWER_DROP_TARGET1,
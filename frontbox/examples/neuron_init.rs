@@ -18,6 +18,7 @@ async fn main() {
     vec![],
   )
   .await
+  .expect("Failed to boot machine")
   .build()
   .run(vec![])
   .await;
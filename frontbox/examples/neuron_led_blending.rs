@@ -25,6 +25,7 @@ async fn main() {
         start: 0,
         led_type: LedType::WS2812,
         leds: vec![leds::DEMO1],
+        ..Default::default()
       }),
     ];
 
@@ -34,6 +35,7 @@ async fn main() {
     expansion_boards,
   )
   .await
+  .expect("Failed to boot machine")
   .build()
   .run(vec![System1::new(), System2::new()])
   .await;
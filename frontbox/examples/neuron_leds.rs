@@ -26,6 +26,7 @@ async fn main() {
         start: 0,
         led_type: LedType::WS2812,
         leds: vec![leds::DEMO1, leds::DEMO2, leds::DEMO3, leds::DEMO4],
+        ..Default::default()
       }),
     ];
 
@@ -35,6 +36,7 @@ async fn main() {
     expansion_boards,
   )
   .await
+  .expect("Failed to boot machine")
   .build()
   .run(vec![LedExample::new()])
   .await;
@@ -24,6 +24,7 @@ async fn main() {
     vec![],
   )
   .await
+  .expect("Failed to boot machine")
   .add_virtual_switch(KeyCode::Home, switches::START_BUTTON)
   .build()
   .run(vec![
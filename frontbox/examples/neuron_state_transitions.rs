@@ -13,6 +13,7 @@ async fn main() {
     vec![],
   )
   .await
+  .expect("Failed to boot machine")
   .build()
   .run(vec![ExampleSystem::new()])
   .await;
@@ -2,7 +2,12 @@ use tokio::sync::mpsc;
 
 use crate::commands::driver_commands::*;
 use crate::commands::driver_group_commands::*;
+use crate::commands::flipper_commands::*;
 use crate::commands::game_commands::*;
+use crate::commands::motor_commands::*;
+use crate::commands::servo_commands::*;
+use crate::commands::stepper_commands::*;
+use crate::commands::switch_commands::*;
 use crate::commands::system_commands::*;
 use crate::commands::timer_commands::*;
 use crate::commands::writeable_config::*;
@@ -18,7 +23,12 @@ pub struct Commands {
   listener_id: u64,
   pub driver: DriverCommands,
   pub driver_group: DriverGroupCommands,
+  pub flippers: FlipperCommands,
   pub game: GameCommands,
+  pub motor: MotorCommands,
+  pub servo: ServoCommands,
+  pub stepper: StepperCommands,
+  pub switch: SwitchCommands,
   pub system: SystemCommands,
   pub timer: TimerCommands,
   pub store: WriteableStore,
@@ -44,6 +54,21 @@ impl Commands {
       driver_group: DriverGroupCommands {
         machine: machine.clone(),
       },
+      flippers: FlipperCommands {
+        machine: machine.clone(),
+      },
+      motor: MotorCommands {
+        machine: machine.clone(),
+      },
+      servo: ServoCommands {
+        machine: machine.clone(),
+      },
+      stepper: StepperCommands {
+        machine: machine.clone(),
+      },
+      switch: SwitchCommands {
+        machine: machine.clone(),
+      },
       system: SystemCommands {
         system_manager: system_manager.clone(),
         listener_id,
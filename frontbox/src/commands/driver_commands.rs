@@ -3,8 +3,8 @@ use std::time::Duration;
 use fast_protocol::DriverTriggerControlMode;
 use tokio::sync::mpsc;
 
-use crate::DriverMode;
 use crate::prelude::MachineCommand;
+use crate::{DriverId, DriverMode};
 
 #[derive(Clone)]
 pub struct DriverCommands {
@@ -16,54 +16,61 @@ impl DriverCommands {
     Self { machine }
   }
 
-  pub fn reconfigure(&mut self, driver_name: &'static str, mode: impl DriverMode + Send + 'static) {
-    let _ = self
-      .machine
-      .send(MachineCommand::ConfigureDriver(driver_name, Box::new(mode)));
+  pub fn reconfigure(
+    &mut self,
+    driver_name: impl DriverId,
+    mode: impl DriverMode + Send + 'static,
+  ) {
+    let _ = self.machine.send(MachineCommand::ConfigureDriver(
+      driver_name.driver_name(),
+      Box::new(mode),
+    ));
   }
 
   /// Activate (trigger) a driver with the given mode. This emits `TL` commands to the FAST hardware
-  pub fn activate(&mut self, driver_name: &'static str, mode: ActivationMode) {
+  pub fn activate(&mut self, driver_name: impl DriverId, mode: ActivationMode) {
     let control_mode: DriverTriggerControlMode = match mode {
       ActivationMode::Automatic => DriverTriggerControlMode::Automatic,
       ActivationMode::Tap => DriverTriggerControlMode::Manual,
       ActivationMode::VirtualSwitchOn => DriverTriggerControlMode::On,
     };
     let _ = self.machine.send(MachineCommand::TriggerDriver(
-      driver_name,
+      driver_name.driver_name(),
       control_mode,
       None,
     ));
   }
 
   /// Deactivate a driver with the given mode. This emits `TL` commands to the FAST hardware
-  pub fn deactivate(&mut self, driver_name: &'static str, mode: DeactivationMode) {
+  pub fn deactivate(&mut self, driver_name: impl DriverId, mode: DeactivationMode) {
     let control_mode: DriverTriggerControlMode = match mode {
       DeactivationMode::Disabled => DriverTriggerControlMode::Automatic,
       DeactivationMode::VirtualSwitchOff => DriverTriggerControlMode::Off,
     };
     let _ = self.machine.send(MachineCommand::TriggerDriver(
-      driver_name,
+      driver_name.driver_name(),
       control_mode,
       None,
     ));
   }
 
-  pub fn trigger(&mut self, driver_name: &'static str, mode: DriverTriggerControlMode) {
-    let _ = self
-      .machine
-      .send(MachineCommand::TriggerDriver(driver_name, mode, None));
+  pub fn trigger(&mut self, driver_name: impl DriverId, mode: DriverTriggerControlMode) {
+    let _ = self.machine.send(MachineCommand::TriggerDriver(
+      driver_name.driver_name(),
+      mode,
+      None,
+    ));
   }
 
   /// Triggers a driver after the given delay time has elapsed
   pub fn trigger_delayed(
     &mut self,
-    driver_name: &'static str,
+    driver_name: impl DriverId,
     mode: DriverTriggerControlMode,
     delay: Duration,
   ) {
     let _ = self.machine.send(MachineCommand::TriggerDriver(
-      driver_name,
+      driver_name.driver_name(),
       mode,
       Some(delay),
     ));
@@ -4,6 +4,7 @@ use crate::commands::driver_commands::*;
 use fast_protocol::DriverTriggerControlMode;
 use tokio::sync::mpsc;
 
+use crate::DriverGroupId;
 use crate::prelude::MachineCommand;
 
 #[derive(Clone)]
@@ -17,47 +18,49 @@ impl DriverGroupCommands {
   }
 
   /// Activate (trigger) a driver with the given mode. This emits `TL` commands to the FAST hardware
-  pub fn activate(&mut self, group_name: &'static str, mode: ActivationMode) {
+  pub fn activate(&mut self, group_name: impl DriverGroupId, mode: ActivationMode) {
     let control_mode: DriverTriggerControlMode = match mode {
       ActivationMode::Automatic => DriverTriggerControlMode::Automatic,
       ActivationMode::Tap => DriverTriggerControlMode::Manual,
       ActivationMode::VirtualSwitchOn => DriverTriggerControlMode::On,
     };
     let _ = self.machine.send(MachineCommand::TriggerDriverGroup(
-      group_name,
+      group_name.driver_group_name(),
       control_mode,
       None,
     ));
   }
 
   /// Deactivate a driver with the given mode. This emits `TL` commands to the FAST hardware
-  pub fn deactivate(&mut self, group_name: &'static str, mode: DeactivationMode) {
+  pub fn deactivate(&mut self, group_name: impl DriverGroupId, mode: DeactivationMode) {
     let control_mode: DriverTriggerControlMode = match mode {
       DeactivationMode::Disabled => DriverTriggerControlMode::Automatic,
       DeactivationMode::VirtualSwitchOff => DriverTriggerControlMode::Off,
     };
     let _ = self.machine.send(MachineCommand::TriggerDriverGroup(
-      group_name,
+      group_name.driver_group_name(),
       control_mode,
       None,
     ));
   }
 
-  pub fn trigger(&mut self, group_name: &'static str, mode: DriverTriggerControlMode) {
-    let _ = self
-      .machine
-      .send(MachineCommand::TriggerDriverGroup(group_name, mode, None));
+  pub fn trigger(&mut self, group_name: impl DriverGroupId, mode: DriverTriggerControlMode) {
+    let _ = self.machine.send(MachineCommand::TriggerDriverGroup(
+      group_name.driver_group_name(),
+      mode,
+      None,
+    ));
   }
 
   /// Triggers a driver after the given delay time has elapsed
   pub fn trigger_delayed(
     &mut self,
-    group_name: &'static str,
+    group_name: impl DriverGroupId,
     mode: DriverTriggerControlMode,
     delay: Duration,
   ) {
     let _ = self.machine.send(MachineCommand::TriggerDriverGroup(
-      group_name,
+      group_name.driver_group_name(),
       mode,
       Some(delay),
     ));
@@ -0,0 +1,24 @@
+use tokio::sync::mpsc;
+
+use crate::prelude::MachineCommand;
+
+#[derive(Clone)]
+pub struct FlipperCommands {
+  pub(crate) machine: mpsc::UnboundedSender<MachineCommand>,
+}
+
+impl FlipperCommands {
+  pub fn new(machine: mpsc::UnboundedSender<MachineCommand>) -> Self {
+    Self { machine }
+  }
+
+  /// Let the hardware fire every flipper from its button
+  pub fn enable(&mut self) {
+    let _ = self.machine.send(MachineCommand::EnableFlippers);
+  }
+
+  /// Turn off every flipper, e.g. on tilt or at the end of a game
+  pub fn disable(&mut self) {
+    let _ = self.machine.send(MachineCommand::DisableFlippers);
+  }
+}
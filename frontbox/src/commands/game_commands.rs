@@ -19,6 +19,12 @@ impl GameCommands {
     let _ = self.machine.send(MachineCommand::EndGame);
   }
 
+  /// Resume a game which was paused by the machine dropping to a safe state, e.g. after `WatchdogExpired`. Hardware
+  /// rules are turned back on; flippers must be enabled again with `cmds.flippers.enable()`.
+  pub fn resume(&mut self) {
+    let _ = self.machine.send(MachineCommand::ResumeGame);
+  }
+
   pub fn add_player(&mut self) {
     let _ = self.machine.send(MachineCommand::AddPlayer);
   }
@@ -1,7 +1,12 @@
 mod commands;
 mod driver_commands;
 mod driver_group_commands;
+mod flipper_commands;
 mod game_commands;
+mod motor_commands;
+mod servo_commands;
+mod stepper_commands;
+mod switch_commands;
 mod system_commands;
 mod timer_commands;
 mod writeable_config;
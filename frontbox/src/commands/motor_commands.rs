@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use fast_protocol::MotorDirection;
+use tokio::sync::mpsc;
+
+use crate::hardware_definition::MotorId;
+use crate::prelude::MachineCommand;
+
+/// Motors are stopped automatically on shutdown, when the expansion network is reset and when `Tilted` is emitted
+#[derive(Clone)]
+pub struct MotorCommands {
+  pub(crate) machine: mpsc::UnboundedSender<MachineCommand>,
+}
+
+impl MotorCommands {
+  pub fn new(machine: mpsc::UnboundedSender<MachineCommand>) -> Self {
+    Self { machine }
+  }
+
+  /// Run a motor until it's stopped or reaches the limit switch for its direction. Speeds above the motor's max
+  /// speed are limited to it.
+  pub fn run(&mut self, motor_name: impl MotorId, direction: MotorDirection, speed: u8) {
+    self.run_for(motor_name, direction, speed, Duration::ZERO);
+  }
+
+  /// Run a motor for a fixed time. The board times the run, so the motor stops even if the machine doesn't.
+  pub fn run_for(
+    &mut self,
+    motor_name: impl MotorId,
+    direction: MotorDirection,
+    speed: u8,
+    time: Duration,
+  ) {
+    let _ = self.machine.send(MachineCommand::RunMotor(
+      motor_name.motor_name(),
+      direction,
+      speed,
+      time,
+    ));
+  }
+
+  /// Run a motor until the switch of one of its named positions closes
+  pub fn run_to(
+    &mut self,
+    motor_name: impl MotorId,
+    position_name: &'static str,
+    direction: MotorDirection,
+    speed: u8,
+  ) {
+    let _ = self.machine.send(MachineCommand::RunMotorTo(
+      motor_name.motor_name(),
+      position_name,
+      direction,
+      speed,
+    ));
+  }
+
+  /// Shake the cabinet with a shaker motor, e.g. `shake("shaker", 200, Duration::from_millis(500))`
+  pub fn shake(&mut self, motor_name: impl MotorId, intensity: u8, duration: Duration) {
+    self.run_for(motor_name, MotorDirection::Forward, intensity, duration);
+  }
+
+  pub fn stop(&mut self, motor_name: impl MotorId) {
+    let _ = self
+      .machine
+      .send(MachineCommand::StopMotor(motor_name.motor_name()));
+  }
+
+  pub fn stop_all(&mut self) {
+    let _ = self.machine.send(MachineCommand::StopMotors);
+  }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::hardware_definition::ServoId;
+use crate::prelude::MachineCommand;
+
+#[derive(Clone)]
+pub struct ServoCommands {
+  pub(crate) machine: mpsc::UnboundedSender<MachineCommand>,
+}
+
+impl ServoCommands {
+  pub fn new(machine: mpsc::UnboundedSender<MachineCommand>) -> Self {
+    Self { machine }
+  }
+
+  /// Move a servo to the given position as fast as it can go. Positions outside of the servo's travel are limited
+  /// to its min/max.
+  pub fn move_to(&mut self, servo_name: impl ServoId, position: u8) {
+    self.move_over(servo_name, position, Duration::ZERO);
+  }
+
+  /// Move a servo to the given position, taking `time` to get there. Use this to control how fast the servo moves.
+  pub fn move_over(&mut self, servo_name: impl ServoId, position: u8, time: Duration) {
+    let _ = self.machine.send(MachineCommand::MoveServo(
+      servo_name.servo_name(),
+      position,
+      time,
+    ));
+  }
+
+  /// Return a servo to its home position
+  pub fn home(&mut self, servo_name: impl ServoId) {
+    let _ = self
+      .machine
+      .send(MachineCommand::HomeServo(servo_name.servo_name()));
+  }
+}
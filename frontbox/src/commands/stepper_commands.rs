@@ -0,0 +1,91 @@
+use tokio::sync::mpsc;
+
+use crate::hardware_definition::StepperId;
+use crate::prelude::MachineCommand;
+
+/// Each move ends with a `StepperMoveComplete` event once the stepper stops
+#[derive(Clone)]
+pub struct StepperCommands {
+  pub(crate) machine: mpsc::UnboundedSender<MachineCommand>,
+}
+
+impl StepperCommands {
+  pub fn new(machine: mpsc::UnboundedSender<MachineCommand>) -> Self {
+    Self { machine }
+  }
+
+  /// Return a stepper home. Steppers with a home switch turn in reverse until it closes, steppers without one move
+  /// back to where they were at boot.
+  pub fn home(&mut self, stepper_name: impl StepperId) {
+    let _ = self
+      .machine
+      .send(MachineCommand::HomeStepper(stepper_name.stepper_name()));
+  }
+
+  /// Move a stepper to one of its named positions
+  pub fn move_to(&mut self, stepper_name: impl StepperId, position_name: &'static str) {
+    let _ = self.machine.send(MachineCommand::MoveStepperTo(
+      stepper_name.stepper_name(),
+      position_name,
+    ));
+  }
+
+  /// Move a stepper by a number of steps from wherever it is, negative steps move in reverse
+  pub fn move_by(&mut self, stepper_name: impl StepperId, steps: i32) {
+    let _ = self.machine.send(MachineCommand::MoveStepperBy(
+      stepper_name.stepper_name(),
+      steps,
+    ));
+  }
+
+  /// Turn a stepper by a number of revolutions, negative revolutions turn in reverse
+  pub fn rotate(&mut self, stepper_name: impl StepperId, revolutions: f32) {
+    let _ = self.machine.send(MachineCommand::RotateStepper(
+      stepper_name.stepper_name(),
+      revolutions,
+    ));
+  }
+
+  /// Stop a stepper where it is
+  pub fn stop(&mut self, stepper_name: impl StepperId) {
+    let _ = self
+      .machine
+      .send(MachineCommand::StopStepper(stepper_name.stepper_name()));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::prelude::*;
+
+  #[derive(Clone, Copy, StepperId)]
+  enum Steppers {
+    DropTarget,
+    #[name = "ramp"]
+    RampDiverter,
+  }
+
+  #[test]
+  fn test_commands_accept_stepper_ids() {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut steppers = StepperCommands::new(sender);
+
+    steppers.home(Steppers::DropTarget);
+    steppers.move_by(Steppers::RampDiverter, 20);
+    steppers.stop("drop_target");
+
+    assert!(matches!(
+      receiver.try_recv(),
+      Ok(MachineCommand::HomeStepper("drop_target"))
+    ));
+    assert!(matches!(
+      receiver.try_recv(),
+      Ok(MachineCommand::MoveStepperBy("ramp", 20))
+    ));
+    assert!(matches!(
+      receiver.try_recv(),
+      Ok(MachineCommand::StopStepper("drop_target"))
+    ));
+  }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::prelude::{MachineCommand, SwitchConfig, SwitchId};
+
+#[derive(Clone)]
+pub struct SwitchCommands {
+  pub(crate) machine: mpsc::UnboundedSender<MachineCommand>,
+}
+
+impl SwitchCommands {
+  pub fn new(machine: mpsc::UnboundedSender<MachineCommand>) -> Self {
+    Self { machine }
+  }
+
+  /// Replace a switch's configuration. Only hardware switches can be configured.
+  pub fn reconfigure(&mut self, switch_name: impl SwitchId, config: SwitchConfig) {
+    self.update(switch_name.switch_name(), move |current| *current = config);
+  }
+
+  /// Change how long a switch must be closed or open before the hardware reports it. `None` uses the hardware's
+  /// default.
+  pub fn set_debounce(
+    &mut self,
+    switch_name: impl SwitchId,
+    close: Option<Duration>,
+    open: Option<Duration>,
+  ) {
+    self.update(switch_name.switch_name(), move |config| {
+      config.debounce_close = close;
+      config.debounce_open = open;
+    });
+  }
+
+  /// Invert a switch, e.g. for an optical switch which is closed at rest
+  pub fn set_inverted(&mut self, switch_name: impl SwitchId, inverted: bool) {
+    self.update(switch_name.switch_name(), move |config| {
+      config.inverted = inverted
+    });
+  }
+
+  /// Stop or start reporting changes from a switch
+  pub fn set_reported(&mut self, switch_name: impl SwitchId, reported: bool) {
+    self.update(switch_name.switch_name(), move |config| {
+      config.reported = reported
+    });
+  }
+
+  /// Emit `SwitchHeld` whenever the switch stays closed for `duration`. A switch can be watched for several durations.
+  pub fn watch_held(&mut self, switch_name: impl SwitchId, duration: Duration) {
+    let _ = self.machine.send(MachineCommand::WatchSwitchHeld(
+      switch_name.switch_name(),
+      duration,
+    ));
+  }
+
+  /// Stop emitting `SwitchHeld` for the switch and duration
+  pub fn unwatch_held(&mut self, switch_name: impl SwitchId, duration: Duration) {
+    let _ = self.machine.send(MachineCommand::UnwatchSwitchHeld(
+      switch_name.switch_name(),
+      duration,
+    ));
+  }
+
+  fn update(
+    &mut self,
+    switch_name: &'static str,
+    f: impl FnOnce(&mut SwitchConfig) + Send + 'static,
+  ) {
+    let _ = self
+      .machine
+      .send(MachineCommand::ConfigureSwitch(switch_name, Box::new(f)));
+  }
+}
@@ -1,11 +1,28 @@
 use std::time::Duration;
 
+use crossterm::event::KeyCode;
+
+use crate::hardware_definition::SwitchId;
+
 #[derive(Debug, Clone)]
 pub struct BootConfig {
   pub io_net_port_path: &'static str,
   pub exp_port_path: &'static str,
   pub platform: FastPlatform,
+  /// Watchdog timeout set while booting, until the machine runs and `watchdog.timeout_ms` from `MachineConfig` takes
+  /// over. Should match that setting, whose default is the same.
   pub watchdog_interval: Duration,
+  /// Record all serial traffic to this file, which can later be replayed with `SerialRecording`
+  pub record_serial_to: Option<&'static str>,
+  /// Leave the watchdog disabled, and so the drivers unpowered, when the boards on the IO NET loop don't match the
+  /// `IoNetwork`. A board out of order shifts every switch and driver id after it, which can fire the wrong coil.
+  pub refuse_watchdog_on_io_mismatch: bool,
+  /// Fail to boot when a board runs firmware which is known not to support a feature the machine uses, rather than
+  /// only logging a warning
+  pub refuse_incompatible_firmware: bool,
+  /// Keys which trigger switches, for emulated switch triggering. Checked along with the rest of the machine at boot,
+  /// so a key mapped to a missing switch is reported with every other problem.
+  pub keyboard_mappings: Vec<(KeyCode, &'static str)>,
 }
 
 impl Default for BootConfig {
@@ -15,10 +32,24 @@ impl Default for BootConfig {
       exp_port_path: "/dev/ttyACM1",
       platform: FastPlatform::Neuron,
       watchdog_interval: Duration::from_millis(1250),
+      record_serial_to: None,
+      refuse_watchdog_on_io_mismatch: false,
+      refuse_incompatible_firmware: false,
+      keyboard_mappings: Vec::new(),
     }
   }
 }
 
+impl BootConfig {
+  /// Map a keyboard key to a switch for emulated switch triggering
+  pub fn with_keyboard_mapping(mut self, key: KeyCode, switch_name: impl SwitchId) -> Self {
+    self
+      .keyboard_mappings
+      .push((key, switch_name.switch_name()));
+    self
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum FastPlatform {
   Neuron = 2000,
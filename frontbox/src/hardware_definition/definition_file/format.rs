@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// The machine definition file. These serde types mirror it; durations are whole milliseconds and powers are
+/// percentages.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MachineFile {
+  #[serde(default)]
+  pub boot: BootFile,
+  #[serde(default)]
+  pub io_boards: Vec<IoBoardFile>,
+  #[serde(default)]
+  pub driver_groups: BTreeMap<String, Vec<String>>,
+  #[serde(default)]
+  pub flippers: Vec<FlipperFile>,
+  #[serde(default)]
+  pub expansion_boards: Vec<ExpansionBoardFile>,
+  /// Group name to the LEDs in it
+  #[serde(default)]
+  pub led_groups: BTreeMap<String, Vec<String>>,
+  /// Key to hardware switch name
+  #[serde(default)]
+  pub keyboard: BTreeMap<String, String>,
+  /// Key to the name of a switch which only exists on the keyboard
+  #[serde(default)]
+  pub virtual_switches: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BootFile {
+  pub io_net_port: Option<String>,
+  pub exp_port: Option<String>,
+  pub platform: Option<PlatformFile>,
+  pub watchdog_interval_ms: Option<u64>,
+  pub record_serial_to: Option<String>,
+  pub refuse_watchdog_on_io_mismatch: Option<bool>,
+  pub refuse_incompatible_firmware: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PlatformFile {
+  Neuron,
+  RetroSystem11,
+  RetroWpc89,
+  RetroWpc95,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct IoBoardFile {
+  /// `io_3208`, `io_1616`, `io_0804`, `cabinet` or `custom`
+  pub board: String,
+  /// Only for `custom` boards
+  pub switch_count: Option<u32>,
+  pub driver_count: Option<u32>,
+  #[serde(default)]
+  pub switches: Vec<SwitchFile>,
+  #[serde(default)]
+  pub drivers: Vec<DriverFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SwitchFile {
+  pub name: String,
+  pub pin: u16,
+  pub inverted: Option<bool>,
+  pub debounce_close_ms: Option<u64>,
+  pub debounce_open_ms: Option<u64>,
+  pub reported: Option<bool>,
+}
+
+impl SwitchFile {
+  pub fn has_config(&self) -> bool {
+    self.inverted.is_some()
+      || self.debounce_close_ms.is_some()
+      || self.debounce_open_ms.is_some()
+      || self.reported.is_some()
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DriverFile {
+  pub name: String,
+  pub pin: u16,
+  /// The `mode` and its settings, read with `mode()`. Flattening `Option<DriverModeFile>` directly would turn a
+  /// mistyped setting into a driver with no mode rather than an error.
+  #[serde(flatten)]
+  settings: serde_json::Map<String, serde_json::Value>,
+}
+
+impl DriverFile {
+  /// `None` for drivers which are configured later, e.g. by a flipper
+  pub fn mode(&self) -> Result<Option<DriverModeFile>, serde_json::Error> {
+    if self.settings.is_empty() {
+      return Ok(None);
+    }
+    DriverModeFile::deserialize(serde_json::Value::Object(self.settings.clone())).map(Some)
+  }
+}
+
+/// What fires a driver
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TriggerFile {
+  Disabled,
+  /// Fired by `cmds.driver`
+  #[default]
+  Virtual,
+  VirtualInverted,
+  Switch(String),
+  InvertedSwitch(String),
+}
+
+/// What fires a driver which watches two switches. A missing switch is the virtual switch.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DualTriggerFile {
+  pub flip_switch: Option<String>,
+  #[serde(default)]
+  pub invert_flip_switch: bool,
+  pub flop_switch: Option<String>,
+  #[serde(default)]
+  pub invert_flop_switch: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub(crate) enum DriverModeFile {
+  Pulse {
+    trigger: Option<TriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+    rest_ms: Option<u64>,
+  },
+  PulseKick {
+    trigger: Option<TriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+    kick_length_ms: Option<u64>,
+  },
+  PulseHold {
+    trigger: Option<TriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_power: Option<u8>,
+    rest_ms: Option<u64>,
+  },
+  PulseHoldCancel {
+    trigger: Option<DualTriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+    secondary_pwm_length_ms: Option<u64>,
+    rest_ms: Option<u64>,
+  },
+  PulseCancel {
+    trigger: Option<TriggerFile>,
+    cancel_switch: String,
+    invert_cancel_switch: Option<bool>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+  },
+  PulseHoldExtension {
+    trigger: Option<TriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_power: Option<u8>,
+    extension_length_ms: Option<u64>,
+    rest_ms: Option<u64>,
+  },
+  DelayedPulse {
+    trigger: Option<TriggerFile>,
+    delay_length_ms: Option<u64>,
+    initial_full_power_length_ms: Option<u64>,
+    secondary_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+    rest_ms: Option<u64>,
+  },
+  LongPulse {
+    trigger: Option<TriggerFile>,
+    initial_pwm_length_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_length_ms: Option<u64>,
+    secondary_pwm_power: Option<u8>,
+    rest_ms: Option<u64>,
+  },
+  FlipperMainDirect {
+    button_switch: String,
+    invert_button_switch: Option<bool>,
+    eos_switch: String,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_power: Option<u8>,
+    max_eos_time_ms: Option<u64>,
+    next_flip_refresh_ms: Option<u64>,
+  },
+  FlipperHoldDirect {
+    button_switch: String,
+    invert_button_switch: Option<bool>,
+    driver_on_time_ms: Option<u64>,
+    initial_pwm_power: Option<u8>,
+    secondary_pwm_power: Option<u8>,
+  },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FlipperFile {
+  pub name: String,
+  pub button_switch: String,
+  /// Single-wound flippers
+  pub driver: Option<String>,
+  pub pulse_ms: Option<u64>,
+  pub hold_power: Option<u8>,
+  /// Dual-wound flippers
+  pub main_driver: Option<String>,
+  pub hold_driver: Option<String>,
+  pub eos_switch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExpansionBoardFile {
+  /// `neutron`, `fp_exp0051`, `fp_exp0061`, `fp_exp0071`, `fp_exp0081`, `fp_exp0091`, `fp_exp1313` or `custom`
+  pub board: String,
+  #[serde(default)]
+  pub jumper_0: JumperFile,
+  #[serde(default)]
+  pub jumper_1: JumperFile,
+  /// Hex address, only for `custom` boards
+  pub address: Option<String>,
+  pub breakout: Option<u8>,
+  #[serde(default)]
+  pub led_ports: Vec<LedPortFile>,
+  #[serde(default)]
+  pub servos: Vec<ServoFile>,
+  #[serde(default)]
+  pub steppers: Vec<StepperFile>,
+  #[serde(default)]
+  pub motors: Vec<MotorFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JumperFile {
+  #[default]
+  Open,
+  Closed,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LedPortFile {
+  pub port: u8,
+  #[serde(default)]
+  pub start: u8,
+  #[serde(default)]
+  pub leds: Vec<String>,
+  #[serde(default)]
+  pub led_type: LedTypeFile,
+  /// Name for every LED on the port at once
+  pub group: Option<String>,
+  /// Playfield `[x, y]` of each LED, for spatial animations
+  #[serde(default)]
+  pub positions: Vec<(f32, f32)>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LedTypeFile {
+  #[default]
+  Ws2812,
+  Sk6812,
+  Apa102,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ServoFile {
+  pub port: u8,
+  pub name: String,
+  pub min: Option<u8>,
+  pub max: Option<u8>,
+  pub home: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct StepperFile {
+  pub port: u8,
+  pub name: String,
+  pub steps_per_revolution: Option<u32>,
+  /// Position name to steps from home
+  #[serde(default)]
+  pub positions: BTreeMap<String, i32>,
+  pub home_switch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MotorFile {
+  pub port: u8,
+  pub name: String,
+  pub max_speed: Option<u8>,
+  pub forward_limit: Option<String>,
+  pub reverse_limit: Option<String>,
+  /// Position name to the switch which closes at that position
+  #[serde(default)]
+  pub positions: BTreeMap<String, String>,
+}
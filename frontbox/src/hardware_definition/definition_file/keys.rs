@@ -0,0 +1,34 @@
+use crossterm::event::KeyCode;
+
+use crate::hardware_definition::definition_file::DefinitionError;
+
+/// A key as written in a definition file: a single character such as `s` or `1`, `space`, `enter`, `tab`,
+/// `backspace`, `esc`, the arrow keys `up`/`down`/`left`/`right`, or a function key `f1`-`f12`
+pub(crate) fn parse(key: &str) -> Result<KeyCode, DefinitionError> {
+  let mut chars = key.chars();
+  if let (Some(c), None) = (chars.next(), chars.next()) {
+    return Ok(KeyCode::Char(c));
+  }
+
+  let lower = key.to_lowercase();
+  let code = match lower.as_str() {
+    "space" => KeyCode::Char(' '),
+    "enter" => KeyCode::Enter,
+    "tab" => KeyCode::Tab,
+    "backspace" => KeyCode::Backspace,
+    "esc" => KeyCode::Esc,
+    "up" => KeyCode::Up,
+    "down" => KeyCode::Down,
+    "left" => KeyCode::Left,
+    "right" => KeyCode::Right,
+    _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+      Some(n @ 1..=12) => KeyCode::F(n),
+      _ => {
+        return Err(DefinitionError::Invalid {
+          message: format!("unknown keyboard key '{}'", key),
+        });
+      }
+    },
+  };
+  Ok(code)
+}
@@ -0,0 +1,1242 @@
+mod format;
+mod keys;
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::Path;
+use std::time::Duration;
+
+use crossterm::event::KeyCode;
+use fast_protocol::{LedType, Power};
+
+use crate::hardware_definition::*;
+use format::*;
+
+/// A whole machine read from a TOML or JSON file, so switches can be rewired and coils tuned without recompiling.
+/// Pass the parts to `MachineBuilder::boot`, then add the LED groups and virtual switches to the builder. The
+/// keyboard mappings are part of the `boot_config`.
+/// See `examples/machine.toml` for the format.
+///
+/// Names from the file live for the rest of the program, as definitions written in Rust do.
+#[derive(Debug)]
+pub struct MachineDefinition {
+  pub boot_config: BootConfig,
+  pub io_network: IoNetwork,
+  pub expansion_boards: Vec<ExpansionBoardDefinition>,
+  pub led_groups: Vec<(&'static str, Vec<&'static str>)>,
+  pub virtual_switches: Vec<(KeyCode, &'static str)>,
+}
+
+#[derive(Debug)]
+pub enum DefinitionError {
+  /// The file couldn't be read
+  Read { path: String, error: std::io::Error },
+  /// The file extension is neither `.toml` nor `.json`
+  UnknownFormat { path: String },
+  /// The file isn't valid TOML or JSON, or has fields the definition format doesn't
+  Parse { message: String },
+  /// The file is well formed but describes hardware which can't be built, e.g. a switch on a pin the board doesn't
+  /// have or a driver triggered by a switch which isn't defined. Every problem in the file is listed, one per line.
+  Invalid { message: String },
+}
+
+impl Display for DefinitionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DefinitionError::Read { path, error } => write!(f, "Unable to read {}: {}", path, error),
+      DefinitionError::UnknownFormat { path } => {
+        write!(f, "{} is not a .toml or .json machine definition", path)
+      }
+      DefinitionError::Parse { message } => write!(f, "Invalid machine definition: {}", message),
+      DefinitionError::Invalid { message } => write!(f, "Invalid machine definition: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for DefinitionError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      DefinitionError::Read { error, .. } => Some(error),
+      _ => None,
+    }
+  }
+}
+
+fn invalid(message: String) -> DefinitionError {
+  DefinitionError::Invalid { message }
+}
+
+/// Note an `Invalid` error and carry on, so the rest of the file is still checked and every problem is reported
+/// together. Any other error means the file can't be read any further and is passed on.
+fn note<T>(
+  result: Result<T, DefinitionError>,
+  problems: &mut Vec<String>,
+) -> Result<Option<T>, DefinitionError> {
+  match result {
+    Ok(value) => Ok(Some(value)),
+    Err(DefinitionError::Invalid { message }) => {
+      problems.push(message);
+      Ok(None)
+    }
+    Err(error) => Err(error),
+  }
+}
+
+impl MachineDefinition {
+  /// Read a definition, choosing TOML or JSON by the file extension
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, DefinitionError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|error| DefinitionError::Read {
+      path: path.display().to_string(),
+      error,
+    })?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+      Some("toml") => Self::from_toml(&source),
+      Some("json") => Self::from_json(&source),
+      _ => Err(DefinitionError::UnknownFormat {
+        path: path.display().to_string(),
+      }),
+    }
+  }
+
+  pub fn from_toml(source: &str) -> Result<Self, DefinitionError> {
+    let file: MachineFile = toml::from_str(source).map_err(|e| DefinitionError::Parse {
+      message: e.to_string(),
+    })?;
+    Self::from_file(file)
+  }
+
+  pub fn from_json(source: &str) -> Result<Self, DefinitionError> {
+    let file: MachineFile = serde_json::from_str(source).map_err(|e| DefinitionError::Parse {
+      message: e.to_string(),
+    })?;
+    Self::from_file(file)
+  }
+
+  fn from_file(file: MachineFile) -> Result<Self, DefinitionError> {
+    let switches = file
+      .io_boards
+      .iter()
+      .flat_map(|board| &board.switches)
+      .map(|switch| switch.name.as_str())
+      .collect();
+    let drivers = file
+      .io_boards
+      .iter()
+      .flat_map(|board| &board.drivers)
+      .map(|driver| driver.name.as_str())
+      .collect();
+    let names = Names { switches, drivers };
+    let mut problems = Vec::new();
+
+    let mut io_network = IoNetworkBuilder::new();
+    for board in &file.io_boards {
+      if let Some(board) = note(io_board(board, &names, &mut problems), &mut problems)? {
+        io_network.add_board(board);
+      }
+    }
+    for (group, members) in &file.driver_groups {
+      let mut known = true;
+      for driver in members {
+        let used_by = format!("driver group '{}'", group);
+        known &= note(names.driver(driver, &used_by), &mut problems)?.is_some();
+      }
+      if known {
+        io_network.add_driver_group(leak(group), members.iter().map(|d| leak(d)).collect());
+      }
+    }
+    for flipper in &file.flippers {
+      if let Some(flipper) = note(flipper_definition(flipper, &names), &mut problems)? {
+        io_network.add_flipper(flipper);
+      }
+    }
+
+    let mut expansion_boards = Vec::new();
+    for board in &file.expansion_boards {
+      let board = expansion_board(board, &names, &mut problems);
+      if let Some(board) = note(board, &mut problems)? {
+        expansion_boards.push(board);
+      }
+    }
+
+    let leds: HashSet<&str> = file
+      .expansion_boards
+      .iter()
+      .flat_map(|board| &board.led_ports)
+      .flat_map(|port| &port.leds)
+      .map(|led| led.as_str())
+      .collect();
+    let port_groups: HashSet<&str> = file
+      .expansion_boards
+      .iter()
+      .flat_map(|board| &board.led_ports)
+      .filter_map(|port| port.group.as_deref())
+      .collect();
+    let mut led_groups = Vec::new();
+    for (group, members) in &file.led_groups {
+      let before = problems.len();
+      if leds.contains(group.as_str()) {
+        problems.push(format!("LED group '{}' has the same name as an LED", group));
+      }
+      if port_groups.contains(group.as_str()) {
+        problems.push(format!(
+          "LED group '{}' is already the group of an LED port",
+          group
+        ));
+      }
+      for led in members {
+        if !leds.contains(led.as_str()) {
+          problems.push(format!(
+            "LED group '{}' has LED '{}' which is not defined",
+            group, led
+          ));
+        }
+      }
+      if problems.len() == before {
+        led_groups.push((leak(group), members.iter().map(|led| leak(led)).collect()));
+      }
+    }
+
+    let mut keyboard_mappings = Vec::new();
+    for (key, switch) in &file.keyboard {
+      if let Some(key) = note(keys::parse(key), &mut problems)? {
+        keyboard_mappings.push((key, leak(switch)));
+      }
+    }
+    let mut virtual_switches = Vec::new();
+    for (key, switch) in &file.virtual_switches {
+      if names.switches.contains(switch.as_str()) {
+        problems.push(format!(
+          "virtual switch '{}' has the same name as a hardware switch",
+          switch
+        ));
+        continue;
+      }
+      if let Some(key) = note(keys::parse(key), &mut problems)? {
+        virtual_switches.push((key, leak(switch)));
+      }
+    }
+
+    if let Err(invalid) = io_network.validate_with(&expansion_boards, &keyboard_mappings) {
+      problems.extend(invalid.iter().map(|p| p.to_string()));
+    }
+    if !problems.is_empty() {
+      return Err(invalid(problems.join("\n")));
+    }
+
+    Ok(Self {
+      boot_config: BootConfig {
+        keyboard_mappings,
+        ..boot_config(file.boot)
+      },
+      io_network: io_network.build(),
+      expansion_boards,
+      led_groups,
+      virtual_switches,
+    })
+  }
+}
+
+/// Switch and driver names defined on the IO boards, to check references against
+struct Names<'a> {
+  switches: HashSet<&'a str>,
+  drivers: HashSet<&'a str>,
+}
+
+impl Names<'_> {
+  fn switch(&self, name: &str, used_by: &str) -> Result<&'static str, DefinitionError> {
+    if !self.switches.contains(name) {
+      return Err(invalid(format!(
+        "{} uses switch '{}' which is not defined",
+        used_by, name
+      )));
+    }
+    Ok(leak(name))
+  }
+
+  fn driver(&self, name: &str, used_by: &str) -> Result<&'static str, DefinitionError> {
+    if !self.drivers.contains(name) {
+      return Err(invalid(format!(
+        "{} uses driver '{}' which is not defined",
+        used_by, name
+      )));
+    }
+    Ok(leak(name))
+  }
+
+  fn optional_switch(
+    &self,
+    name: &Option<String>,
+    used_by: &str,
+  ) -> Result<Option<&'static str>, DefinitionError> {
+    name
+      .as_ref()
+      .map(|name| self.switch(name, used_by))
+      .transpose()
+  }
+}
+
+fn leak(name: &str) -> &'static str {
+  Box::leak(name.to_string().into_boxed_str())
+}
+
+fn boot_config(boot: BootFile) -> BootConfig {
+  let default = BootConfig::default();
+  BootConfig {
+    io_net_port_path: boot
+      .io_net_port
+      .as_deref()
+      .map_or(default.io_net_port_path, leak),
+    exp_port_path: boot.exp_port.as_deref().map_or(default.exp_port_path, leak),
+    platform: match boot.platform {
+      None => default.platform,
+      Some(PlatformFile::Neuron) => FastPlatform::Neuron,
+      Some(PlatformFile::RetroSystem11) => FastPlatform::RetroSystem11,
+      Some(PlatformFile::RetroWpc89) => FastPlatform::RetroWPC89,
+      Some(PlatformFile::RetroWpc95) => FastPlatform::RetroWPC95,
+    },
+    watchdog_interval: boot
+      .watchdog_interval_ms
+      .map_or(default.watchdog_interval, Duration::from_millis),
+    record_serial_to: boot.record_serial_to.as_deref().map(leak),
+    refuse_watchdog_on_io_mismatch: boot
+      .refuse_watchdog_on_io_mismatch
+      .unwrap_or(default.refuse_watchdog_on_io_mismatch),
+    refuse_incompatible_firmware: boot
+      .refuse_incompatible_firmware
+      .unwrap_or(default.refuse_incompatible_firmware),
+    keyboard_mappings: default.keyboard_mappings,
+  }
+}
+
+/// Build an IO board, noting any switch or driver which can't be added and leaving it off the board
+fn io_board(
+  file: &IoBoardFile,
+  names: &Names,
+  problems: &mut Vec<String>,
+) -> Result<IoBoardBuilder, DefinitionError> {
+  let mut board = match file.board.as_str() {
+    "io_3208" => FastIoBoards::io_3208(),
+    "io_1616" => FastIoBoards::io_1616(),
+    "io_0804" => FastIoBoards::io_0804(),
+    "cabinet" => FastIoBoards::cabinet(),
+    "custom" => match (file.switch_count, file.driver_count) {
+      (Some(switch_count), Some(driver_count)) => FastIoBoards::custom(switch_count, driver_count),
+      _ => {
+        return Err(invalid(
+          "custom IO boards need a switch_count and a driver_count".to_string(),
+        ));
+      }
+    },
+    other => return Err(invalid(format!("unknown IO board '{}'", other))),
+  };
+
+  for switch in &file.switches {
+    if switch.pin as u32 >= board.switch_count {
+      problems.push(format!(
+        "switch '{}' is on pin {} but {} only has {} switches",
+        switch.name, switch.pin, board.description, board.switch_count
+      ));
+      continue;
+    }
+
+    board = if switch.has_config() {
+      let default = SwitchConfig::default();
+      let config = SwitchConfig {
+        inverted: switch.inverted.unwrap_or(default.inverted),
+        debounce_close: switch.debounce_close_ms.map(Duration::from_millis),
+        debounce_open: switch.debounce_open_ms.map(Duration::from_millis),
+        reported: switch.reported.unwrap_or(default.reported),
+      };
+      board.with_switch_cfg(leak(&switch.name), switch.pin, config)
+    } else {
+      board.with_switch(leak(&switch.name), switch.pin)
+    };
+  }
+
+  for driver in &file.drivers {
+    if driver.pin as u32 >= board.driver_count {
+      problems.push(format!(
+        "driver '{}' is on pin {} but {} only has {} drivers",
+        driver.name, driver.pin, board.description, board.driver_count
+      ));
+      continue;
+    }
+
+    let name = leak(&driver.name);
+    let used_by = format!("driver '{}'", driver.name);
+    let mode = driver.mode().map_err(|e| DefinitionError::Parse {
+      message: format!("{}: {}", used_by, e),
+    })?;
+    board = match &mode {
+      None => board.with_driver(name, driver.pin),
+      Some(mode) => match note(driver_mode_config(mode, names, &used_by), problems)? {
+        Some(config) => {
+          let mut board = board.with_driver(name, driver.pin);
+          board.driver_configs.insert(name, config);
+          board
+        }
+        None => board,
+      },
+    };
+  }
+
+  Ok(board)
+}
+
+fn ms(value: Option<u64>, default: Duration) -> Duration {
+  value.map_or(default, Duration::from_millis)
+}
+
+fn power(value: Option<u8>, default: Power, used_by: &str) -> Result<Power, DefinitionError> {
+  match value {
+    None => Ok(default),
+    Some(percent) if percent > 100 => Err(invalid(format!(
+      "{} has a power of {}%, the most is 100%",
+      used_by, percent
+    ))),
+    Some(percent) => Ok(Power::percent(percent)),
+  }
+}
+
+fn trigger(
+  file: &Option<TriggerFile>,
+  default: DriverTriggerMode,
+  names: &Names,
+  used_by: &str,
+) -> Result<DriverTriggerMode, DefinitionError> {
+  Ok(match file {
+    None => default,
+    Some(TriggerFile::Disabled) => DriverTriggerMode::Disabled,
+    Some(TriggerFile::Virtual) => DriverTriggerMode::VirtualSwitchTrue,
+    Some(TriggerFile::VirtualInverted) => DriverTriggerMode::VirtualSwitchFalse,
+    Some(TriggerFile::Switch(switch)) => DriverTriggerMode::Switch(names.switch(switch, used_by)?),
+    Some(TriggerFile::InvertedSwitch(switch)) => {
+      DriverTriggerMode::InvertedSwitch(names.switch(switch, used_by)?)
+    }
+  })
+}
+
+fn dual_trigger(
+  file: &Option<DualTriggerFile>,
+  names: &Names,
+  used_by: &str,
+) -> Result<DriverTriggerDualMode, DefinitionError> {
+  let Some(file) = file else {
+    return Ok(DriverTriggerDualMode::Disabled);
+  };
+  let flip = names.optional_switch(&file.flip_switch, used_by)?;
+  let flop = names.optional_switch(&file.flop_switch, used_by)?;
+
+  Ok(
+    match (flip, file.invert_flip_switch, flop, file.invert_flop_switch) {
+      (Some(flip_switch), false, Some(flop_switch), false) => {
+        DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchTrue {
+          flip_switch,
+          flop_switch,
+        }
+      }
+      (Some(flip_switch), true, Some(flop_switch), false) => {
+        DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchTrue {
+          flip_switch,
+          flop_switch,
+        }
+      }
+      (Some(flip_switch), false, Some(flop_switch), true) => {
+        DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchFalse {
+          flip_switch,
+          flop_switch,
+        }
+      }
+      (Some(flip_switch), true, Some(flop_switch), true) => {
+        DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchFalse {
+          flip_switch,
+          flop_switch,
+        }
+      }
+      (None, _, Some(flop_switch), false) => {
+        DriverTriggerDualMode::VirtualFlip_FlopSwitchTrue(flop_switch)
+      }
+      (None, _, Some(flop_switch), true) => {
+        DriverTriggerDualMode::VirtualFlip_FlopSwitchFalse(flop_switch)
+      }
+      (Some(flip_switch), false, None, _) => {
+        DriverTriggerDualMode::FlipSwitchTrue_VirtualFlop(flip_switch)
+      }
+      (Some(flip_switch), true, None, _) => {
+        DriverTriggerDualMode::FlipSwitchFalse_VirtualFlop(flip_switch)
+      }
+      (None, _, None, _) => DriverTriggerDualMode::Disabled,
+    },
+  )
+}
+
+/// A driver's mode, starting from the mode's defaults for anything the file leaves out
+fn driver_mode_config(
+  mode: &DriverModeFile,
+  names: &Names,
+  used_by: &str,
+) -> Result<Box<dyn DriverMode>, DefinitionError> {
+  Ok(match mode {
+    DriverModeFile::Pulse {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_length_ms,
+      secondary_pwm_power,
+      rest_ms,
+    } => {
+      let d = PulseMode::default();
+      let mode = PulseMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::PulseKick {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_length_ms,
+      secondary_pwm_power,
+      kick_length_ms,
+    } => {
+      let d = PulseKickMode::default();
+      let mode = PulseKickMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        kick_length: ms(*kick_length_ms, d.kick_length),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::PulseHold {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_power,
+      rest_ms,
+    } => {
+      let d = PulseHoldMode::default();
+      let mode = PulseHoldMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::PulseHoldCancel {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      secondary_pwm_power,
+      secondary_pwm_length_ms,
+      rest_ms,
+    } => {
+      let d = PulseHoldCancelMode::default();
+      let mode = PulseHoldCancelMode {
+        trigger_mode: dual_trigger(trigger_file, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::PulseCancel {
+      trigger: trigger_file,
+      cancel_switch,
+      invert_cancel_switch,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_length_ms,
+      secondary_pwm_power,
+    } => {
+      let d = PulseCancelMode::default();
+      let mode = PulseCancelMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        cancel_switch: Some(names.switch(cancel_switch, used_by)?),
+        invert_cancel_switch: invert_cancel_switch.or(d.invert_cancel_switch),
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::PulseHoldExtension {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_power,
+      extension_length_ms,
+      rest_ms,
+    } => {
+      let d = PulseHoldExtensionMode::default();
+      let mode = PulseHoldExtensionMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        extension_length: ms(*extension_length_ms, d.extension_length),
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::DelayedPulse {
+      trigger: trigger_file,
+      delay_length_ms,
+      initial_full_power_length_ms,
+      secondary_pwm_length_ms,
+      secondary_pwm_power,
+      rest_ms,
+    } => {
+      let d = DelayedPulseMode::default();
+      let mode = DelayedPulseMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        delay_length: ms(*delay_length_ms, d.delay_length),
+        initial_full_power_length: ms(*initial_full_power_length_ms, d.initial_full_power_length),
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::LongPulse {
+      trigger: trigger_file,
+      initial_pwm_length_ms,
+      initial_pwm_power,
+      secondary_pwm_length_ms,
+      secondary_pwm_power,
+      rest_ms,
+    } => {
+      let d = LongPulseMode::default();
+      let mode = LongPulseMode {
+        trigger_mode: trigger(trigger_file, d.trigger_mode, names, used_by)?,
+        initial_pwm_length: ms(*initial_pwm_length_ms, d.initial_pwm_length),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_length: ms(*secondary_pwm_length_ms, d.secondary_pwm_length),
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        rest: ms(*rest_ms, d.rest),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::FlipperMainDirect {
+      button_switch,
+      invert_button_switch,
+      eos_switch,
+      initial_pwm_power,
+      secondary_pwm_power,
+      max_eos_time_ms,
+      next_flip_refresh_ms,
+    } => {
+      let d = FlipperMainDirectMode::default();
+      let mode = FlipperMainDirectMode {
+        button_switch: names.switch(button_switch, used_by)?,
+        invert_button_switch: invert_button_switch.or(d.invert_button_switch),
+        eos_switch: names.switch(eos_switch, used_by)?,
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+        max_eos_time: ms(*max_eos_time_ms, d.max_eos_time),
+        next_flip_refresh: ms(*next_flip_refresh_ms, d.next_flip_refresh),
+      };
+      Box::new(mode)
+    }
+    DriverModeFile::FlipperHoldDirect {
+      button_switch,
+      invert_button_switch,
+      driver_on_time_ms,
+      initial_pwm_power,
+      secondary_pwm_power,
+    } => {
+      let d = FlipperHoldDirectMode::default();
+      let mode = FlipperHoldDirectMode {
+        button_switch: names.switch(button_switch, used_by)?,
+        invert_button_switch: invert_button_switch.or(d.invert_button_switch),
+        driver_on_time: ms(*driver_on_time_ms, d.driver_on_time),
+        initial_pwm_power: power(*initial_pwm_power, d.initial_pwm_power, used_by)?,
+        secondary_pwm_power: power(*secondary_pwm_power, d.secondary_pwm_power, used_by)?,
+      };
+      Box::new(mode)
+    }
+  })
+}
+
+fn flipper_definition(
+  file: &FlipperFile,
+  names: &Names,
+) -> Result<FlipperDefinition, DefinitionError> {
+  let used_by = format!("flipper '{}'", file.name);
+  let name = leak(&file.name);
+  let button_switch = names.switch(&file.button_switch, &used_by)?;
+
+  match (
+    &file.driver,
+    &file.main_driver,
+    &file.hold_driver,
+    &file.eos_switch,
+  ) {
+    (Some(driver), None, None, None) => {
+      let mut flipper =
+        FlipperDefinition::single_wound(name, button_switch, names.driver(driver, &used_by)?);
+      if let FlipperCoils::SingleWound {
+        pulse, hold_power, ..
+      } = &mut flipper.coils
+      {
+        *pulse = ms(file.pulse_ms, *pulse);
+        *hold_power = power(file.hold_power, *hold_power, &used_by)?;
+      }
+      Ok(flipper)
+    }
+    (None, Some(main_driver), Some(hold_driver), Some(eos_switch))
+      if file.pulse_ms.is_none() && file.hold_power.is_none() =>
+    {
+      Ok(FlipperDefinition::dual_wound(
+        name,
+        button_switch,
+        names.driver(main_driver, &used_by)?,
+        names.driver(hold_driver, &used_by)?,
+        names.switch(eos_switch, &used_by)?,
+      ))
+    }
+    _ => Err(invalid(format!(
+      "{} needs either a driver (single-wound, with optional pulse_ms and hold_power) or a main_driver, \
+       hold_driver and eos_switch (dual-wound)",
+      used_by
+    ))),
+  }
+}
+
+/// Build an expansion board, noting any LED port, servo, stepper or motor which can't be added and leaving it off
+/// the board
+fn expansion_board(
+  file: &ExpansionBoardFile,
+  names: &Names,
+  problems: &mut Vec<String>,
+) -> Result<ExpansionBoardDefinition, DefinitionError> {
+  let jumper = |jumper: &JumperFile| match jumper {
+    JumperFile::Open => JumperState::Open,
+    JumperFile::Closed => JumperState::Closed,
+  };
+  let (jumper_0, jumper_1) = (jumper(&file.jumper_0), jumper(&file.jumper_1));
+
+  let mut board = match file.board.as_str() {
+    "neutron" => ExpansionBoardDefinition::neutron(),
+    "fp_exp0051" => ExpansionBoardDefinition::fp_exp0051(jumper_0, jumper_1),
+    "fp_exp0061" => ExpansionBoardDefinition::fp_exp0061(jumper_0, jumper_1),
+    "fp_exp0071" => ExpansionBoardDefinition::fp_exp0071(jumper_0, jumper_1),
+    "fp_exp0081" => ExpansionBoardDefinition::fp_exp0081(jumper_0, jumper_1),
+    "fp_exp0091" => ExpansionBoardDefinition::fp_exp0091(jumper_0, jumper_1),
+    "fp_exp1313" => ExpansionBoardDefinition::fp_exp1313(jumper_0, jumper_1),
+    "custom" => {
+      let address = file.address.as_deref().unwrap_or_default();
+      if u8::from_str_radix(address, 16).is_err() {
+        return Err(invalid(format!(
+          "custom expansion board address '{}' is not a hex address",
+          address
+        )));
+      }
+      ExpansionBoardDefinition::custom(leak(address), file.breakout)
+    }
+    other => return Err(invalid(format!("unknown expansion board '{}'", other))),
+  };
+  if file.breakout.is_some() {
+    board.breakout = file.breakout;
+  }
+
+  for port in &file.led_ports {
+    if !port.positions.is_empty() && port.positions.len() != port.leds.len() {
+      problems.push(format!(
+        "LED port {} on expansion board '{}' has {} positions for {} LEDs",
+        port.port,
+        file.board,
+        port.positions.len(),
+        port.leds.len()
+      ));
+      continue;
+    }
+    board = board.with_led_port(LedPortDefinition {
+      port: port.port,
+      start: port.start,
+      leds: port.leds.iter().map(|led| leak(led)).collect(),
+      led_type: match port.led_type {
+        LedTypeFile::Ws2812 => LedType::WS2812,
+        LedTypeFile::Sk6812 => LedType::SK6812,
+        LedTypeFile::Apa102 => LedType::APA102,
+      },
+      group: port.group.as_deref().map(leak),
+      positions: port.positions.clone(),
+    });
+  }
+
+  for servo in &file.servos {
+    let d = ServoDefinition::default();
+    let definition = ServoDefinition {
+      port: servo.port,
+      name: leak(&servo.name),
+      min: servo.min.unwrap_or(d.min),
+      max: servo.max.unwrap_or(d.max),
+      home: servo.home.unwrap_or(d.home),
+    };
+    if definition.min > definition.max
+      || definition.home < definition.min
+      || definition.home > definition.max
+    {
+      problems.push(format!(
+        "servo '{}' home position {} is outside of its travel {}-{}",
+        servo.name, definition.home, definition.min, definition.max
+      ));
+      continue;
+    }
+    board = board.with_servo(definition);
+  }
+
+  for stepper in &file.steppers {
+    let used_by = format!("stepper '{}'", stepper.name);
+    let d = StepperDefinition::default();
+    let steps_per_revolution = stepper
+      .steps_per_revolution
+      .unwrap_or(d.steps_per_revolution);
+    if steps_per_revolution == 0 {
+      problems.push(format!(
+        "{} must have at least one step per revolution",
+        used_by
+      ));
+      continue;
+    }
+    let Some(home_switch) = note(
+      names.optional_switch(&stepper.home_switch, &used_by),
+      problems,
+    )?
+    else {
+      continue;
+    };
+    board = board.with_stepper(StepperDefinition {
+      port: stepper.port,
+      name: leak(&stepper.name),
+      steps_per_revolution,
+      positions: stepper
+        .positions
+        .iter()
+        .map(|(position, steps)| (leak(position), *steps))
+        .collect(),
+      home_switch,
+    });
+  }
+
+  for motor in &file.motors {
+    let used_by = format!("motor '{}'", motor.name);
+    let motor_switches = || -> Result<_, DefinitionError> {
+      let mut positions = Vec::new();
+      for (position, switch) in &motor.positions {
+        positions.push((leak(position), names.switch(switch, &used_by)?));
+      }
+      let forward_limit = names.optional_switch(&motor.forward_limit, &used_by)?;
+      let reverse_limit = names.optional_switch(&motor.reverse_limit, &used_by)?;
+      Ok((positions, forward_limit, reverse_limit))
+    };
+    let Some((positions, forward_limit, reverse_limit)) = note(motor_switches(), problems)? else {
+      continue;
+    };
+    board = board.with_motor(MotorDefinition {
+      port: motor.port,
+      name: leak(&motor.name),
+      max_speed: motor
+        .max_speed
+        .unwrap_or(MotorDefinition::default().max_speed),
+      forward_limit,
+      reverse_limit,
+      positions,
+    });
+  }
+
+  Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn driver_mode(definition: &MachineDefinition, name: &str) -> Option<u8> {
+    definition
+      .io_network
+      .drivers
+      .iter()
+      .find(|driver| driver.name == name)
+      .unwrap_or_else(|| panic!("driver '{}' missing", name))
+      .config
+      .as_ref()
+      .map(|config| config.mode())
+  }
+
+  fn invalid_message(result: Result<MachineDefinition, DefinitionError>) -> String {
+    match result {
+      Err(DefinitionError::Invalid { message }) => message,
+      other => panic!("expected an Invalid error, got {:?}", other),
+    }
+  }
+
+  fn parse_message(result: Result<MachineDefinition, DefinitionError>) -> String {
+    match result {
+      Err(DefinitionError::Parse { message }) => message,
+      other => panic!("expected a Parse error, got {:?}", other),
+    }
+  }
+
+  const ONE_BOARD: &str = r#"
+    [[io_boards]]
+    board = "io_3208"
+
+    [[io_boards.switches]]
+    name = "a"
+    pin = 0
+
+    [[io_boards.switches]]
+    name = "b"
+    pin = 1
+  "#;
+
+  #[test]
+  fn test_example_file() {
+    let definition = MachineDefinition::load(concat!(
+      env!("CARGO_MANIFEST_DIR"),
+      "/examples/machine.toml"
+    ))
+    .unwrap();
+
+    assert_eq!(definition.boot_config.io_net_port_path, "/dev/ttyACM0");
+    assert_eq!(definition.boot_config.exp_port_path, "/dev/ttyACM1");
+    assert_eq!(
+      definition.boot_config.keyboard_mappings,
+      vec![
+        (KeyCode::Char('s'), "start_button"),
+        (KeyCode::Char('z'), "left_flipper_button"),
+      ]
+    );
+
+    let mut switches: Vec<_> = definition
+      .io_network
+      .switches
+      .iter()
+      .map(|s| s.name)
+      .collect();
+    switches.sort();
+    assert_eq!(
+      switches,
+      vec![
+        "left_flipper_button",
+        "left_sling",
+        "start_button",
+        "trough_1"
+      ]
+    );
+    let trough = definition
+      .io_network
+      .switches
+      .iter()
+      .find(|s| s.name == "trough_1")
+      .unwrap();
+    let config = trough.config.as_ref().unwrap();
+    assert!(config.inverted);
+    assert_eq!(config.debounce_close, Some(Duration::from_millis(20)));
+
+    assert_eq!(driver_mode(&definition, "knocker"), Some(0x10));
+    assert_eq!(driver_mode(&definition, "left_sling"), Some(0x10));
+    assert_eq!(driver_mode(&definition, "left_flipper"), None);
+    assert_eq!(definition.io_network.flippers.len(), 1);
+    assert_eq!(
+      definition.io_network.flippers[0].button_switch,
+      "left_flipper_button"
+    );
+
+    assert_eq!(definition.expansion_boards.len(), 1);
+    let gi = &definition.expansion_boards[0].led_ports[1];
+    assert_eq!(gi.leds, vec!["gi_1", "gi_2", "gi_3"]);
+    assert_eq!(gi.group, Some("gi"));
+    assert_eq!(gi.positions[1], (10.25, 20.0));
+
+    assert_eq!(
+      definition.led_groups,
+      vec![("inserts", vec!["start_button_lamp", "shoot_again"])]
+    );
+    assert_eq!(
+      definition.virtual_switches,
+      vec![(KeyCode::Char('t'), "tilt")]
+    );
+  }
+
+  #[test]
+  fn test_driver_modes() {
+    let source = format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "pulse"
+      pin = 0
+      mode = "pulse"
+      trigger = {{ switch = "a" }}
+
+      [[io_boards.drivers]]
+      name = "pulse_kick"
+      pin = 1
+      mode = "pulse_kick"
+      kick_length_ms = 50
+
+      [[io_boards.drivers]]
+      name = "pulse_hold"
+      pin = 2
+      mode = "pulse_hold"
+      trigger = {{ inverted_switch = "a" }}
+
+      [[io_boards.drivers]]
+      name = "pulse_hold_cancel"
+      pin = 3
+      mode = "pulse_hold_cancel"
+      trigger = {{ flip_switch = "a", flop_switch = "b" }}
+
+      [[io_boards.drivers]]
+      name = "pulse_cancel"
+      pin = 4
+      mode = "pulse_cancel"
+      trigger = {{ switch = "a" }}
+      cancel_switch = "b"
+
+      [[io_boards.drivers]]
+      name = "pulse_hold_extension"
+      pin = 5
+      mode = "pulse_hold_extension"
+      extension_length_ms = 200
+
+      [[io_boards.drivers]]
+      name = "delayed_pulse"
+      pin = 6
+      mode = "delayed_pulse"
+      delay_length_ms = 100
+
+      [[io_boards.drivers]]
+      name = "long_pulse"
+      pin = 7
+      mode = "long_pulse"
+      initial_pwm_power = 75
+      "#,
+      ONE_BOARD
+    );
+    let definition = MachineDefinition::from_toml(&source).unwrap();
+
+    assert_eq!(driver_mode(&definition, "pulse"), Some(0x10));
+    assert_eq!(driver_mode(&definition, "pulse_kick"), Some(0x12));
+    assert_eq!(driver_mode(&definition, "pulse_hold"), Some(0x18));
+    assert_eq!(driver_mode(&definition, "pulse_hold_cancel"), Some(0x20));
+    assert_eq!(driver_mode(&definition, "pulse_cancel"), Some(0x75));
+    assert_eq!(driver_mode(&definition, "pulse_hold_extension"), Some(0x78));
+    assert_eq!(driver_mode(&definition, "delayed_pulse"), Some(0x30));
+    assert_eq!(driver_mode(&definition, "long_pulse"), Some(0x70));
+  }
+
+  #[test]
+  fn test_flipper_driver_modes() {
+    let source = format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "main"
+      pin = 0
+      mode = "flipper_main_direct"
+      button_switch = "a"
+      eos_switch = "b"
+
+      [[io_boards.drivers]]
+      name = "hold"
+      pin = 1
+      mode = "flipper_hold_direct"
+      button_switch = "a"
+      "#,
+      ONE_BOARD
+    );
+    let definition = MachineDefinition::from_toml(&source).unwrap();
+
+    assert_eq!(driver_mode(&definition, "main"), Some(0x5E));
+    assert_eq!(driver_mode(&definition, "hold"), Some(0x5D));
+  }
+
+  #[test]
+  fn test_json() {
+    let definition = MachineDefinition::from_json(
+      r#"{
+        "io_boards": [{
+          "board": "io_0804",
+          "switches": [{ "name": "a", "pin": 0 }],
+          "drivers": [{ "name": "kicker", "pin": 0, "mode": "pulse", "trigger": { "switch": "a" } }]
+        }],
+        "keyboard": { "space": "a" }
+      }"#,
+    )
+    .unwrap();
+
+    assert_eq!(driver_mode(&definition, "kicker"), Some(0x10));
+    assert_eq!(
+      definition.boot_config.keyboard_mappings,
+      vec![(KeyCode::Char(' '), "a")]
+    );
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    parse_message(MachineDefinition::from_toml("[unknown_section]\n"));
+    parse_message(MachineDefinition::from_json("{ \"io_boards\": "));
+
+    let mistyped = parse_message(MachineDefinition::from_toml(&format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "kicker"
+      pin = 0
+      mode = "pulse"
+      initial_pwm_lenght_ms = 20
+      "#,
+      ONE_BOARD
+    )));
+    assert!(mistyped.contains("driver 'kicker'"), "{}", mistyped);
+    assert!(mistyped.contains("initial_pwm_lenght_ms"), "{}", mistyped);
+
+    let unknown_mode = parse_message(MachineDefinition::from_toml(&format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "kicker"
+      pin = 0
+      mode = "pulse_forever"
+      "#,
+      ONE_BOARD
+    )));
+    assert!(unknown_mode.contains("pulse_forever"), "{}", unknown_mode);
+  }
+
+  #[test]
+  fn test_invalid_definitions() {
+    let unknown_trigger = invalid_message(MachineDefinition::from_toml(&format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "kicker"
+      pin = 0
+      mode = "pulse"
+      trigger = {{ switch = "c" }}
+      "#,
+      ONE_BOARD
+    )));
+    assert!(
+      unknown_trigger.contains("switch 'c' which is not defined"),
+      "{}",
+      unknown_trigger
+    );
+
+    let pin = invalid_message(MachineDefinition::from_toml(
+      r#"
+      [[io_boards]]
+      board = "io_0804"
+
+      [[io_boards.switches]]
+      name = "a"
+      pin = 8
+      "#,
+    ));
+    assert!(pin.contains("switch 'a' is on pin 8"), "{}", pin);
+
+    let power = invalid_message(MachineDefinition::from_toml(&format!(
+      r#"{}
+      [[io_boards.drivers]]
+      name = "kicker"
+      pin = 0
+      mode = "pulse"
+      initial_pwm_power = 101
+      "#,
+      ONE_BOARD
+    )));
+    assert!(power.contains("101%"), "{}", power);
+
+    let key = invalid_message(MachineDefinition::from_toml(&format!(
+      "{}\n[keyboard]\nnot_a_key = \"a\"\n",
+      ONE_BOARD
+    )));
+    assert!(key.contains("not_a_key"), "{}", key);
+
+    let duplicate = invalid_message(MachineDefinition::from_toml(&format!(
+      "{}\n[[io_boards.switches]]\nname = \"a\"\npin = 2\n",
+      ONE_BOARD
+    )));
+    assert!(duplicate.contains("'a'"), "{}", duplicate);
+
+    let positions = invalid_message(MachineDefinition::from_toml(
+      r#"
+      [[expansion_boards]]
+      board = "neutron"
+
+      [[expansion_boards.led_ports]]
+      port = 0
+      leds = ["a", "b"]
+      positions = [[0.0, 0.0]]
+      "#,
+    ));
+    assert!(positions.contains("positions"), "{}", positions);
+  }
+
+  #[test]
+  fn test_invalid_definition_reports_every_problem() {
+    let message = invalid_message(MachineDefinition::from_toml(&format!(
+      r#"{}
+      [[io_boards.switches]]
+      name = "far"
+      pin = 40
+
+      [[io_boards.switches]]
+      name = "a"
+      pin = 2
+
+      [[io_boards.drivers]]
+      name = "kicker"
+      pin = 0
+      mode = "pulse"
+      trigger = {{ switch = "c" }}
+
+      [[io_boards.drivers]]
+      name = "knocker"
+      pin = 1
+      mode = "pulse"
+      initial_pwm_power = 101
+
+      [keyboard]
+      not_a_key = "a"
+      "#,
+      ONE_BOARD
+    )));
+
+    for expected in [
+      "switch 'far' is on pin 40",
+      "switch 'c' which is not defined",
+      "101%",
+      "not_a_key",
+      "'a'",
+    ] {
+      assert!(message.contains(expected), "{}", message);
+    }
+    assert_eq!(message.lines().count(), 5, "{}", message);
+  }
+
+  #[test]
+  fn test_load_unknown_format() {
+    let path = std::env::temp_dir().join("frontbox_definition_test.txt");
+    std::fs::write(&path, "").unwrap();
+    let result = MachineDefinition::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(DefinitionError::UnknownFormat { .. })));
+    assert!(matches!(
+      MachineDefinition::load("/nonexistent/machine.toml"),
+      Err(DefinitionError::Read { .. })
+    ));
+  }
+}
@@ -1,4 +1,8 @@
-use crate::hardware_definition::exp::LedPortDefinition;
+use crate::hardware_definition::exp::{
+  LedPortDefinition, MotorDefinition, ServoDefinition, StepperDefinition,
+};
+
+type JumperedConstructor = fn(JumperState, JumperState) -> ExpansionBoardDefinition;
 
 /// https://fastpinball.com/programming/exp/#expansion-board-addresses
 #[derive(Debug, Clone)]
@@ -7,6 +11,9 @@ pub struct ExpansionBoardDefinition {
   pub(crate) address: u8,
   pub(crate) breakout: Option<u8>,
   pub(crate) led_ports: Vec<LedPortDefinition>,
+  pub(crate) servos: Vec<ServoDefinition>,
+  pub(crate) steppers: Vec<StepperDefinition>,
+  pub(crate) motors: Vec<MotorDefinition>,
 }
 
 impl ExpansionBoardDefinition {
@@ -15,6 +22,9 @@ impl ExpansionBoardDefinition {
       address: u8::from_str_radix(address, 16).unwrap(),
       breakout,
       led_ports: Vec::new(),
+      servos: Vec::new(),
+      steppers: Vec::new(),
+      motors: Vec::new(),
     }
   }
 
@@ -22,8 +32,17 @@ impl ExpansionBoardDefinition {
     Self::custom("48", None)
   }
 
-  // TODO: fp_exp0051
   /// 2 DC motors, 127 LEDs
+  pub fn fp_exp0051(jumper_0: JumperState, jumper_1: JumperState) -> Self {
+    let address = match (jumper_0, jumper_1) {
+      (JumperState::Open, JumperState::Open) => "98",
+      (JumperState::Closed, JumperState::Open) => "99",
+      (JumperState::Open, JumperState::Closed) => "9A",
+      (JumperState::Closed, JumperState::Closed) => "9B",
+    };
+
+    Self::custom(address, None)
+  }
 
   /// 2 stepper, 128 LEDs
   pub fn fp_exp0061(jumper_0: JumperState, jumper_1: JumperState) -> Self {
@@ -85,9 +104,76 @@ impl ExpansionBoardDefinition {
   }
 
   pub fn with_led_port(mut self, port: LedPortDefinition) -> Self {
+    if !port.positions.is_empty() && port.positions.len() != port.leds.len() {
+      panic!(
+        "LED port {} has {} positions for {} LEDs",
+        port.port,
+        port.positions.len(),
+        port.leds.len()
+      );
+    }
     self.led_ports.push(port);
     self
   }
+
+  pub fn with_servo(mut self, servo: ServoDefinition) -> Self {
+    if servo.min > servo.max || servo.home < servo.min || servo.home > servo.max {
+      panic!(
+        "Servo '{}' home position {} is outside of its travel {}-{}",
+        servo.name, servo.home, servo.min, servo.max
+      );
+    }
+
+    self.servos.push(servo);
+    self
+  }
+
+  pub fn with_stepper(mut self, stepper: StepperDefinition) -> Self {
+    if stepper.steps_per_revolution == 0 {
+      panic!(
+        "Stepper '{}' must have at least one step per revolution",
+        stepper.name
+      );
+    }
+
+    self.steppers.push(stepper);
+    self
+  }
+
+  pub fn with_motor(mut self, motor: MotorDefinition) -> Self {
+    self.motors.push(motor);
+    self
+  }
+
+  /// Every board address FAST assigns, along with the Rust source which defines a board at that address
+  pub(crate) fn known_addresses() -> Vec<(u8, String)> {
+    let mut known = vec![(Self::neutron().address, "neutron()".to_string())];
+
+    let jumpered: [(&str, JumperedConstructor); 6] = [
+      ("fp_exp0051", Self::fp_exp0051),
+      ("fp_exp0061", Self::fp_exp0061),
+      ("fp_exp0071", Self::fp_exp0071),
+      ("fp_exp0081", Self::fp_exp0081),
+      ("fp_exp0091", Self::fp_exp0091),
+      ("fp_exp1313", Self::fp_exp1313),
+    ];
+    for (name, constructor) in jumpered {
+      for jumper_1 in [JumperState::Open, JumperState::Closed] {
+        for jumper_0 in [JumperState::Open, JumperState::Closed] {
+          let source = format!(
+            "{}(JumperState::{:?}, JumperState::{:?})",
+            name, jumper_0, jumper_1
+          );
+          known.push((
+            constructor(jumper_0.clone(), jumper_1.clone()).address,
+            source,
+          ));
+        }
+      }
+    }
+
+    known
+  }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
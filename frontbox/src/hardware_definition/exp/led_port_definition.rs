@@ -1,11 +1,21 @@
+use std::collections::HashMap;
+
 use fast_protocol::LedType;
 
+use crate::hardware_definition::exp::ExpansionBoardDefinition;
+
 #[derive(Debug, Clone)]
 pub struct LedPortDefinition {
   pub port: u8,
   pub start: u8,
   pub leds: Vec<&'static str>,
   pub led_type: LedType,
+  /// Name which addresses every LED on the port at once, like a group added with `MachineBuilder::add_led_group`.
+  /// Ports sharing a group name form one group, e.g. GI spread over several ports.
+  pub group: Option<&'static str>,
+  /// Playfield x/y of each LED, in the same order as `leds`, for spatial animations. Any unit will do as long as
+  /// every port uses the same one. Leave empty for LEDs which have no place in the layout, e.g. cabinet lighting.
+  pub positions: Vec<(f32, f32)>,
 }
 
 impl Default for LedPortDefinition {
@@ -15,6 +25,23 @@ impl Default for LedPortDefinition {
       start: 0,
       leds: Vec::new(),
       led_type: LedType::WS2812,
+      group: None,
+      positions: Vec::new(),
+    }
+  }
+}
+
+/// The groups formed by naming a `group` on LED ports
+pub(crate) fn led_port_groups(
+  expansion_boards: &[ExpansionBoardDefinition],
+) -> HashMap<&'static str, Vec<&'static str>> {
+  let mut groups: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+  for board in expansion_boards {
+    for led_port in &board.led_ports {
+      if let Some(group) = led_port.group {
+        groups.entry(group).or_default().extend(&led_port.leds);
+      }
     }
   }
+  groups
 }
@@ -1,5 +1,11 @@
 mod expansion_board_definition;
 mod led_port_definition;
+mod motor_definition;
+mod servo_definition;
+mod stepper_definition;
 
 pub use expansion_board_definition::*;
 pub use led_port_definition::*;
+pub use motor_definition::*;
+pub use servo_definition::*;
+pub use stepper_definition::*;
@@ -0,0 +1,46 @@
+/// A DC motor on an expansion board motor port, e.g. one of the 2 ports of an FP-EXP-0051 or the shaker motor of an
+/// FP-EXP-1313. Speeds are PWM duty from 0 (stopped) to 255 (full power).
+#[derive(Debug, Clone)]
+pub struct MotorDefinition {
+  pub port: u8,
+  pub name: &'static str,
+  /// Fastest the motor may run, faster requests are limited to this
+  pub max_speed: u8,
+  /// Switch which closes at the end of forward travel. The motor stops when it closes and won't run forward while
+  /// it's closed.
+  pub forward_limit: Option<&'static str>,
+  /// Switch which closes at the end of reverse travel
+  pub reverse_limit: Option<&'static str>,
+  /// Named positions and the switch which closes at each, e.g. `("target_up", "target_up_switch")`, used with
+  /// `cmds.motor.run_to`
+  pub positions: Vec<(&'static str, &'static str)>,
+}
+
+impl Default for MotorDefinition {
+  fn default() -> Self {
+    Self {
+      port: 0,
+      name: "",
+      max_speed: u8::MAX,
+      forward_limit: None,
+      reverse_limit: None,
+      positions: Vec::new(),
+    }
+  }
+}
+
+impl MotorDefinition {
+  /// Limit a speed to the motor's max speed
+  pub fn clamp(&self, speed: u8) -> u8 {
+    speed.min(self.max_speed)
+  }
+
+  /// Switch which closes when the motor reaches a named position
+  pub fn position_switch(&self, name: &str) -> Option<&'static str> {
+    self
+      .positions
+      .iter()
+      .find(|(position, _)| *position == name)
+      .map(|(_, switch)| *switch)
+  }
+}
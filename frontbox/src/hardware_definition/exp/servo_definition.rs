@@ -0,0 +1,32 @@
+/// A servo on an expansion board servo port, e.g. one of the 4 ports of an FP-EXP-0071. Positions are 0-255 across
+/// the servo's full range.
+#[derive(Debug, Clone)]
+pub struct ServoDefinition {
+  pub port: u8,
+  pub name: &'static str,
+  /// Lowest position the servo may be moved to
+  pub min: u8,
+  /// Highest position the servo may be moved to
+  pub max: u8,
+  /// Where the servo is parked at boot and whenever the expansion network is reset
+  pub home: u8,
+}
+
+impl Default for ServoDefinition {
+  fn default() -> Self {
+    Self {
+      port: 0,
+      name: "",
+      min: 0,
+      max: u8::MAX,
+      home: 128,
+    }
+  }
+}
+
+impl ServoDefinition {
+  /// Limit a position to the servo's travel
+  pub fn clamp(&self, position: u8) -> u8 {
+    position.clamp(self.min, self.max)
+  }
+}
@@ -0,0 +1,42 @@
+/// A stepper motor on an expansion board stepper port, e.g. one of the 2 ports of an FP-EXP-0061. Positions are
+/// counted in steps from home, and may be negative.
+#[derive(Debug, Clone)]
+pub struct StepperDefinition {
+  pub port: u8,
+  pub name: &'static str,
+  /// Steps the motor takes to turn once. Used for `rotate` and as the furthest the stepper travels looking for its
+  /// home switch.
+  pub steps_per_revolution: u32,
+  /// Named places the stepper can be moved to with `cmds.stepper.move_to`, e.g. `("ramp_up", 1200)`
+  pub positions: Vec<(&'static str, i32)>,
+  /// Switch which closes when the stepper is at home. Without one, the stepper's position at boot is home.
+  pub home_switch: Option<&'static str>,
+}
+
+impl Default for StepperDefinition {
+  fn default() -> Self {
+    Self {
+      port: 0,
+      name: "",
+      steps_per_revolution: 200,
+      positions: Vec::new(),
+      home_switch: None,
+    }
+  }
+}
+
+impl StepperDefinition {
+  /// Steps from home of a named position
+  pub fn position(&self, name: &str) -> Option<i32> {
+    self
+      .positions
+      .iter()
+      .find(|(position, _)| *position == name)
+      .map(|(_, steps)| *steps)
+  }
+
+  /// Steps needed to turn the given number of revolutions, negative in reverse
+  pub fn revolutions(&self, revolutions: f32) -> i32 {
+    (self.steps_per_revolution as f32 * revolutions).round() as i32
+  }
+}
@@ -0,0 +1,97 @@
+/// A switch name. Everything which takes a switch, driver, driver group, LED, motor, servo or stepper name accepts
+/// either a `&'static str` or an enum deriving the matching trait, so names can be checked by the compiler:
+///
+/// ```ignore
+/// #[derive(Clone, Copy, SwitchId)]
+/// enum Switches {
+///   StartButton,
+///   LeftSling,
+/// }
+///
+/// FastIoBoards::io_3208()
+///   .with_switch(Switches::StartButton, 0)
+///   .with_switch(Switches::LeftSling, 1)
+///   .with_driver_cfg(Drivers::LeftSling, 0, PulseMode {
+///     trigger_mode: DriverTriggerMode::switch(Switches::LeftSling),
+///     ..Default::default()
+///   });
+///
+/// ctx.is_switch_closed(Switches::StartButton);
+/// ```
+///
+/// Each trait has its own method so one enum can derive several, e.g. a button and the lamp inside it.
+pub trait SwitchId {
+  fn switch_name(&self) -> &'static str;
+}
+
+/// A driver name. See `SwitchId`.
+pub trait DriverId {
+  fn driver_name(&self) -> &'static str;
+}
+
+/// A driver group name. See `SwitchId`.
+pub trait DriverGroupId {
+  fn driver_group_name(&self) -> &'static str;
+}
+
+/// An LED name. See `SwitchId`.
+pub trait LedId {
+  fn led_name(&self) -> &'static str;
+}
+
+/// A motor name. See `SwitchId`.
+pub trait MotorId {
+  fn motor_name(&self) -> &'static str;
+}
+
+/// A servo name. See `SwitchId`.
+pub trait ServoId {
+  fn servo_name(&self) -> &'static str;
+}
+
+/// A stepper name. See `SwitchId`.
+pub trait StepperId {
+  fn stepper_name(&self) -> &'static str;
+}
+
+impl SwitchId for &'static str {
+  fn switch_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl DriverId for &'static str {
+  fn driver_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl DriverGroupId for &'static str {
+  fn driver_group_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl LedId for &'static str {
+  fn led_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl MotorId for &'static str {
+  fn motor_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl ServoId for &'static str {
+  fn servo_name(&self) -> &'static str {
+    self
+  }
+}
+
+impl StepperId for &'static str {
+  fn stepper_name(&self) -> &'static str {
+    self
+  }
+}
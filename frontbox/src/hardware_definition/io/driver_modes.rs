@@ -10,6 +10,10 @@ use crate::{DriverTriggerDualMode, DriverTriggerMode};
 /// 2. Allows use of ..Default::default() since DriverConfig is an enum
 pub trait DriverMode {
   fn to_config(&self, switch_lookup: &dyn SwitchLookup) -> DriverConfig;
+
+  /// Names of the switches the mode refers to. A name missing from the lookup would otherwise be sent to the
+  /// hardware as switch 0, so these are checked before the mode is used.
+  fn switches(&self) -> Vec<&'static str>;
 }
 
 /// Mode 10 - Pulse the driver, up to 255ms, when triggered.
@@ -53,6 +57,10 @@ impl DriverMode for PulseMode {
       rest: self.rest,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 pub trait SwitchLookup {
@@ -108,6 +116,10 @@ impl DriverMode for PulseKickMode {
       kick_length: self.kick_length,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 /// Mode 18 - Holds a driver in the on state as long as the trigger is active. An initial PWM can be configured
@@ -149,6 +161,10 @@ impl DriverMode for PulseHoldMode {
       rest: self.rest,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 /// Mode 20 - Pulse then indefinitely hold the driver on until the trigger (flip) is deactivated -OR- the cancel
@@ -193,6 +209,120 @@ impl DriverMode for PulseHoldCancelMode {
       rest: self.rest,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switches()
+  }
+}
+
+/// Mode 75 - Pulse the driver like mode 10, cutting the pulse short as soon as the cancel switch is activated.
+/// Useful for kickbacks and up-posts which should stop pushing once the ball or post has moved.
+/// https://fastpinball.com/fast-serial-protocol/net/driver-mode/75/
+#[derive(Debug, Clone)]
+pub struct PulseCancelMode {
+  /// What causes the driver to fire (be triggered)
+  pub trigger_mode: DriverTriggerMode,
+  /// Switch which ends the pulse early. Without one the driver is a plain mode 10 pulse which always runs its full
+  /// length.
+  pub cancel_switch: Option<&'static str>,
+  /// Cancel when the switch opens instead of when it closes
+  pub invert_cancel_switch: Option<bool>,
+  pub initial_pwm_length: Duration,
+  pub initial_pwm_power: Power,
+  pub secondary_pwm_length: Duration,
+  pub secondary_pwm_power: Power,
+}
+
+impl Default for PulseCancelMode {
+  fn default() -> Self {
+    Self {
+      trigger_mode: DriverTriggerMode::VirtualSwitchTrue,
+      cancel_switch: None,
+      invert_cancel_switch: None,
+      initial_pwm_length: Duration::from_millis(30),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_length: Duration::ZERO,
+      secondary_pwm_power: Power::ZERO,
+    }
+  }
+}
+
+impl DriverMode for PulseCancelMode {
+  fn to_config(&self, switch_lookup: &dyn SwitchLookup) -> DriverConfig {
+    let (switch, invert_switch) = get_switch_invert(&self.trigger_mode, switch_lookup);
+
+    DriverConfig::PulseCancel {
+      switch,
+      invert_switch,
+      cancel_switch: self
+        .cancel_switch
+        .and_then(|name| switch_lookup.get_switch_id(name)),
+      invert_cancel_switch: self.invert_cancel_switch,
+      initial_pwm_length: self.initial_pwm_length,
+      initial_pwm_power: self.initial_pwm_power,
+      secondary_pwm_length: self.secondary_pwm_length,
+      secondary_pwm_power: self.secondary_pwm_power,
+    }
+  }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self
+      .trigger_mode
+      .trigger_switch()
+      .into_iter()
+      .chain(self.cancel_switch)
+      .collect()
+  }
+}
+
+/// Mode 78 - Pulse, then hold the driver on while the trigger is active and for an extension time after it ends.
+/// Useful for magnets which should keep their grip briefly after the switch releases.
+/// https://fastpinball.com/fast-serial-protocol/net/driver-mode/78/
+#[derive(Debug, Clone)]
+pub struct PulseHoldExtensionMode {
+  /// What causes the driver to fire (be triggered)
+  pub trigger_mode: DriverTriggerMode,
+  pub initial_pwm_length: Duration,
+  pub initial_pwm_power: Power,
+  /// Power the driver is held at after the initial pulse
+  pub secondary_pwm_power: Power,
+  /// How long the driver stays held after the trigger ends, up to 255ms
+  pub extension_length: Duration,
+  /// Time after the driver goes off before it can be triggered again
+  pub rest: Duration,
+}
+
+impl Default for PulseHoldExtensionMode {
+  fn default() -> Self {
+    Self {
+      trigger_mode: DriverTriggerMode::VirtualSwitchTrue,
+      initial_pwm_length: Duration::from_millis(30),
+      initial_pwm_power: Power::FULL,
+      secondary_pwm_power: Power::percent(25),
+      extension_length: Duration::from_millis(100),
+      rest: Duration::ZERO,
+    }
+  }
+}
+
+impl DriverMode for PulseHoldExtensionMode {
+  fn to_config(&self, switch_lookup: &dyn SwitchLookup) -> DriverConfig {
+    let (switch, invert_switch) = get_switch_invert(&self.trigger_mode, switch_lookup);
+
+    DriverConfig::PulseHoldExtension {
+      switch,
+      invert_switch,
+      initial_pwm_length: self.initial_pwm_length,
+      initial_pwm_power: self.initial_pwm_power,
+      secondary_pwm_power: self.secondary_pwm_power,
+      extension_length: self.extension_length,
+      rest: self.rest,
+    }
+  }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 /// Mode 30 - Insert a delay between when the switch is triggered and the driver fires.
@@ -237,6 +367,10 @@ impl DriverMode for DelayedPulseMode {
       rest: self.rest,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 /// Mode 70 - Pulse the driver for an initial time (up to 255ms), then hold it for a secondary time (up to 25s).
@@ -280,6 +414,10 @@ impl DriverMode for LongPulseMode {
       rest: self.rest,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    self.trigger_mode.trigger_switch().into_iter().collect()
+  }
 }
 
 /// Mode 80 - Premium flipper driver for main coil. Driver is active when button switch is closed.
@@ -310,20 +448,31 @@ impl Default for FlipperMainDirectMode {
 
 impl DriverMode for FlipperMainDirectMode {
   fn to_config(&self, switch_lookup: &dyn SwitchLookup) -> DriverConfig {
+    let (Some(button_switch), Some(eos_switch)) = (
+      required_switch(
+        switch_lookup,
+        self.button_switch,
+        "Flipper main direct mode",
+      ),
+      required_switch(switch_lookup, self.eos_switch, "Flipper main direct mode"),
+    ) else {
+      return DriverConfig::Disabled;
+    };
+
     DriverConfig::FlipperMainDirect {
-      button_switch: switch_lookup
-        .get_switch_id(self.button_switch)
-        .expect("Flipper main direct mode requires a valid button switch"),
+      button_switch,
       invert_button_switch: self.invert_button_switch,
-      eos_switch: switch_lookup
-        .get_switch_id(self.eos_switch)
-        .expect("Flipper main direct mode requires a valid EOS switch"),
+      eos_switch,
       initial_pwm_power: self.initial_pwm_power,
       secondary_pwm_power: self.secondary_pwm_power,
       max_eos_time: self.max_eos_time,
       next_flip_refresh: self.next_flip_refresh,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    vec![self.button_switch, self.eos_switch]
+  }
 }
 
 /// Mode 81 - Premium flipper driver for hold coil
@@ -350,16 +499,36 @@ impl Default for FlipperHoldDirectMode {
 
 impl DriverMode for FlipperHoldDirectMode {
   fn to_config(&self, switch_lookup: &dyn SwitchLookup) -> DriverConfig {
+    let Some(button_switch) = required_switch(
+      switch_lookup,
+      self.button_switch,
+      "Flipper hold direct mode",
+    ) else {
+      return DriverConfig::Disabled;
+    };
+
     DriverConfig::FlipperHoldDirect {
-      button_switch: switch_lookup
-        .get_switch_id(self.button_switch)
-        .expect("Flipper hold direct mode requires a valid button switch"),
+      button_switch,
       invert_button_switch: self.invert_button_switch,
       driver_on_time: self.driver_on_time,
       initial_pwm_power: self.initial_pwm_power,
       secondary_pwm_power: self.secondary_pwm_power,
     }
   }
+
+  fn switches(&self) -> Vec<&'static str> {
+    vec![self.button_switch]
+  }
+}
+
+/// Looks up a switch a mode can't work without. A missing switch leaves the driver disabled rather than wired to
+/// switch 0.
+fn required_switch(switch_lookup: &dyn SwitchLookup, name: &str, mode: &str) -> Option<usize> {
+  let id = switch_lookup.get_switch_id(name);
+  if id.is_none() {
+    log::error!("{} uses unknown switch '{}', driver disabled", mode, name);
+  }
+  id
 }
 
 fn get_switch_invert(
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use fast_protocol::{DriverConfig, Power};
+
+use crate::hardware_definition::io::{
+  DriverMode, DriverTriggerMode, FlipperHoldDirectMode, FlipperMainDirectMode, PulseHoldMode,
+  SwitchLookup,
+};
+use crate::hardware_definition::{DriverId, SwitchId};
+
+/// The coils of a flipper
+#[derive(Debug, Clone)]
+pub enum FlipperCoils {
+  /// One winding, pulsed at full power then held at `hold_power` while the button is closed
+  SingleWound {
+    driver: &'static str,
+    pulse: Duration,
+    hold_power: Power,
+  },
+  /// Separate power and hold windings. The hardware cuts the power winding when the end-of-stroke switch closes and
+  /// re-flips if the EOS opens while the button is held.
+  DualWound {
+    main_driver: &'static str,
+    hold_driver: &'static str,
+    eos_switch: &'static str,
+  },
+}
+
+/// A flipper and the button which controls it. Flippers are disabled until `cmds.flippers.enable()`.
+#[derive(Debug, Clone)]
+pub struct FlipperDefinition {
+  pub name: &'static str,
+  pub button_switch: &'static str,
+  pub coils: FlipperCoils,
+}
+
+impl FlipperDefinition {
+  pub fn single_wound(
+    name: &'static str,
+    button_switch: impl SwitchId,
+    driver: impl DriverId,
+  ) -> Self {
+    Self {
+      name,
+      button_switch: button_switch.switch_name(),
+      coils: FlipperCoils::SingleWound {
+        driver: driver.driver_name(),
+        pulse: Duration::from_millis(30),
+        hold_power: Power::percent(25),
+      },
+    }
+  }
+
+  pub fn dual_wound(
+    name: &'static str,
+    button_switch: impl SwitchId,
+    main_driver: impl DriverId,
+    hold_driver: impl DriverId,
+    eos_switch: impl SwitchId,
+  ) -> Self {
+    Self {
+      name,
+      button_switch: button_switch.switch_name(),
+      coils: FlipperCoils::DualWound {
+        main_driver: main_driver.driver_name(),
+        hold_driver: hold_driver.driver_name(),
+        eos_switch: eos_switch.switch_name(),
+      },
+    }
+  }
+
+  pub fn drivers(&self) -> Vec<&'static str> {
+    match &self.coils {
+      FlipperCoils::SingleWound { driver, .. } => vec![driver],
+      FlipperCoils::DualWound {
+        main_driver,
+        hold_driver,
+        ..
+      } => vec![main_driver, hold_driver],
+    }
+  }
+
+  pub fn switches(&self) -> Vec<&'static str> {
+    match &self.coils {
+      FlipperCoils::SingleWound { .. } => vec![self.button_switch],
+      FlipperCoils::DualWound { eos_switch, .. } => vec![self.button_switch, eos_switch],
+    }
+  }
+
+  /// Driver configs which let the hardware flip on the button
+  pub(crate) fn enabled_configs(
+    &self,
+    switch_lookup: &dyn SwitchLookup,
+  ) -> Vec<(&'static str, DriverConfig)> {
+    match &self.coils {
+      FlipperCoils::SingleWound {
+        driver,
+        pulse,
+        hold_power,
+      } => {
+        let mode = PulseHoldMode {
+          trigger_mode: DriverTriggerMode::switch(self.button_switch),
+          initial_pwm_length: *pulse,
+          secondary_pwm_power: *hold_power,
+          ..Default::default()
+        };
+        vec![(driver, mode.to_config(switch_lookup))]
+      }
+      FlipperCoils::DualWound {
+        main_driver,
+        hold_driver,
+        eos_switch,
+      } => {
+        let main = FlipperMainDirectMode {
+          button_switch: self.button_switch,
+          eos_switch,
+          ..Default::default()
+        };
+        let hold = FlipperHoldDirectMode {
+          button_switch: self.button_switch,
+          ..Default::default()
+        };
+        vec![
+          (main_driver, main.to_config(switch_lookup)),
+          (hold_driver, hold.to_config(switch_lookup)),
+        ]
+      }
+    }
+  }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use fast_protocol::Power;
+
+use crate::hardware_definition::io::{
+  DelayedPulseMode, DriverMode, DriverTriggerMode, PulseHoldMode, PulseMode,
+};
+use crate::hardware_definition::{DriverId, SwitchId};
+
+/// Where the game is, as far as hardware rules are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamePhase {
+  /// No game is in progress
+  Attract,
+  /// From `GameStarted` or `BallStarted` until the ball ends or the player tilts
+  BallInPlay,
+  /// From `BallEnded` until `BallStarted`, e.g. while bonus is counted
+  BetweenBalls,
+  /// From `Tilted` until the next `BallStarted`
+  Tilted,
+}
+
+/// A driver which the hardware fires by itself when a switch changes, e.g. slingshots, pop bumpers, flippers and
+/// kickbacks. The machine configures the driver in the phases the rule is enabled in and disables the driver in all
+/// others.
+pub struct HardwareRule {
+  pub(crate) switch: &'static str,
+  pub(crate) driver: &'static str,
+  pub(crate) mode: Box<dyn DriverMode + Send>,
+  pub(crate) phases: Vec<GamePhase>,
+}
+
+impl HardwareRule {
+  /// A rule with a custom driver mode. The mode's trigger should reference `switch`.
+  pub fn new(
+    switch: impl SwitchId,
+    driver: impl DriverId,
+    mode: impl DriverMode + Send + 'static,
+  ) -> Self {
+    Self {
+      switch: switch.switch_name(),
+      driver: driver.driver_name(),
+      mode: Box::new(mode),
+      phases: vec![GamePhase::BallInPlay],
+    }
+  }
+
+  /// Pulse the driver each time the switch closes, e.g. slingshots and pop bumpers
+  pub fn autofire(switch: impl SwitchId, driver: impl DriverId, pulse: Duration) -> Self {
+    let switch = switch.switch_name();
+    Self::new(
+      switch,
+      driver,
+      PulseMode {
+        trigger_mode: DriverTriggerMode::switch(switch),
+        initial_pwm_length: pulse,
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Pulse the driver then hold it at `hold_power` while the flipper button is closed
+  pub fn flipper(
+    button: impl SwitchId,
+    driver: impl DriverId,
+    pulse: Duration,
+    hold_power: Power,
+  ) -> Self {
+    let button = button.switch_name();
+    Self::new(
+      button,
+      driver,
+      PulseHoldMode {
+        trigger_mode: DriverTriggerMode::switch(button),
+        initial_pwm_length: pulse,
+        secondary_pwm_power: hold_power,
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Pulse the driver a moment after the switch closes, giving the ball time to settle in front of the kickback
+  pub fn kickback(
+    switch: impl SwitchId,
+    driver: impl DriverId,
+    delay: Duration,
+    pulse: Duration,
+  ) -> Self {
+    let switch = switch.switch_name();
+    Self::new(
+      switch,
+      driver,
+      DelayedPulseMode {
+        trigger_mode: DriverTriggerMode::switch(switch),
+        delay_length: delay,
+        initial_full_power_length: pulse,
+        ..Default::default()
+      },
+    )
+  }
+
+  /// The phases the rule is enabled in, `BallInPlay` only by default
+  pub fn enabled_in(mut self, phases: &[GamePhase]) -> Self {
+    self.phases = phases.to_vec();
+    self
+  }
+
+  pub(crate) fn is_enabled_in(&self, phase: GamePhase) -> bool {
+    self.phases.contains(&phase)
+  }
+}
+
+impl std::fmt::Debug for HardwareRule {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "HardwareRule({:?} -> {:?}, {:?})",
+      self.switch, self.driver, self.phases
+    )
+  }
+}
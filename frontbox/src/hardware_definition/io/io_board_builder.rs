@@ -1,8 +1,8 @@
 use core::panic;
 use std::collections::HashMap;
 
-use crate::DriverMode;
 use crate::hardware_definition::io::SwitchConfig;
+use crate::hardware_definition::{DriverId, DriverMode, SwitchId};
 
 #[derive(Default)]
 pub struct IoBoardBuilder {
@@ -16,7 +16,7 @@ pub struct IoBoardBuilder {
 }
 
 impl IoBoardBuilder {
-  pub fn with_switch(mut self, name: &'static str, pin: u16) -> Self {
+  pub fn with_switch(mut self, name: impl SwitchId, pin: u16) -> Self {
     if pin >= self.switch_count as u16 {
       panic!(
         "Switch index {} out of bounds for board with {} switches",
@@ -24,22 +24,19 @@ impl IoBoardBuilder {
       );
     }
 
-    self.switch_map.insert(pin, name);
+    self.switch_map.insert(pin, name.switch_name());
     self
   }
 
-  pub fn with_switch_cfg(mut self, name: &'static str, pin: u16, config: SwitchConfig) -> Self {
+  pub fn with_switch_cfg(mut self, name: impl SwitchId, pin: u16, config: SwitchConfig) -> Self {
+    let name = name.switch_name();
     self = self.with_switch(name, pin);
     self.switch_configs.insert(name, config);
     self
   }
 
-  pub fn with_driver_cfg(
-    mut self,
-    name: &'static str,
-    pin: u16,
-    config: impl DriverMode + 'static,
-  ) -> Self {
+  /// Add a driver which is configured later, e.g. by a flipper or with `cmds.driver.reconfigure`
+  pub fn with_driver(mut self, name: impl DriverId, pin: u16) -> Self {
     if pin >= self.driver_count as u16 {
       panic!(
         "Driver index {} out of bounds for board with {} drivers",
@@ -47,7 +44,18 @@ impl IoBoardBuilder {
       );
     }
 
-    self.driver_map.insert(pin, name);
+    self.driver_map.insert(pin, name.driver_name());
+    self
+  }
+
+  pub fn with_driver_cfg(
+    mut self,
+    name: impl DriverId,
+    pin: u16,
+    config: impl DriverMode + 'static,
+  ) -> Self {
+    let name = name.driver_name();
+    self = self.with_driver(name, pin);
     self.driver_configs.insert(name, Box::new(config));
     self
   }
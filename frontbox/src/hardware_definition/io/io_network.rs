@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use fast_protocol::DriverConfig;
+use fast_protocol::{DriverConfig, SwitchReportingMode};
+
+use crate::hardware_definition::io::FlipperDefinition;
 
 /** This module containes the "final" form that is shared with the rest of the code */
 
@@ -11,6 +13,7 @@ pub struct IoNetwork {
   pub switches: Vec<SwitchDefinition>,
   pub drivers: Vec<DriverDefinition>,
   pub driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  pub flippers: Vec<FlipperDefinition>,
 }
 
 /// Simplified description of an IO board
@@ -29,11 +32,26 @@ pub struct SwitchDefinition {
   pub config: Option<SwitchConfig>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SwitchConfig {
   pub inverted: bool,
   pub debounce_close: Option<Duration>,
   pub debounce_open: Option<Duration>,
+  /// Whether the hardware reports changes. A switch which isn't reported never changes state, e.g. to ignore a
+  /// broken switch until it's fixed.
+  pub reported: bool,
+}
+
+impl SwitchConfig {
+  pub(crate) fn reporting_mode(&self) -> SwitchReportingMode {
+    if !self.reported {
+      SwitchReportingMode::None
+    } else if self.inverted {
+      SwitchReportingMode::ReportInverted
+    } else {
+      SwitchReportingMode::ReportNormal
+    }
+  }
 }
 
 impl Default for SwitchConfig {
@@ -42,6 +60,7 @@ impl Default for SwitchConfig {
       inverted: false,
       debounce_close: None,
       debounce_open: None,
+      reported: true,
     }
   }
 }
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
 use crate::hardware_definition::io::*;
+use crate::hardware_definition::{DriverGroupId, DriverId};
 
 pub struct IoNetworkBuilder {
-  boards: Vec<IoBoardBuilder>,
-  driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  pub(crate) boards: Vec<IoBoardBuilder>,
+  pub(crate) driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  pub(crate) flippers: Vec<FlipperDefinition>,
 }
 
 impl IoNetworkBuilder {
@@ -12,6 +14,7 @@ impl IoNetworkBuilder {
     Self {
       boards: Vec::new(),
       driver_groups: HashMap::new(),
+      flippers: Vec::new(),
     }
   }
 
@@ -19,10 +22,17 @@ impl IoNetworkBuilder {
     self.boards.push(spec);
   }
 
-  pub fn add_driver_group(&mut self, name: &'static str, drivers: Vec<&'static str>) {
-    self.driver_groups.insert(name, drivers);
+  pub fn add_driver_group<D: DriverId>(&mut self, name: impl DriverGroupId, drivers: Vec<D>) {
+    let drivers = drivers.iter().map(|driver| driver.driver_name()).collect();
+    self.driver_groups.insert(name.driver_group_name(), drivers);
   }
 
+  pub fn add_flipper(&mut self, flipper: FlipperDefinition) {
+    self.flippers.push(flipper);
+  }
+
+  /// Build the network without checking it. Call `validate` first to find mistakes such as a driver triggered by a
+  /// misspelled switch; `MachineBuilder::boot` refuses a network with any.
   pub fn build(self) -> IoNetwork {
     let mut boards: Vec<IoBoardDefinition> = Vec::new();
     let mut switches = Vec::new();
@@ -75,6 +85,7 @@ impl IoNetworkBuilder {
       switches,
       drivers,
       driver_groups: self.driver_groups,
+      flippers: self.flippers,
     }
   }
 }
@@ -1,5 +1,7 @@
 pub mod driver_modes;
 mod fast_io_boards;
+mod flipper_definition;
+mod hardware_rule;
 mod io_board_builder;
 mod io_network;
 mod io_network_builder;
@@ -8,6 +10,8 @@ mod trigger_modes;
 // TODO: maybe don't export builders
 pub use driver_modes::*;
 pub use fast_io_boards::*;
+pub use flipper_definition::*;
+pub use hardware_rule::*;
 pub use io_board_builder::*;
 pub use io_network::*;
 pub use io_network_builder::*;
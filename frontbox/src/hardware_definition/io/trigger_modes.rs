@@ -1,3 +1,5 @@
+use crate::hardware_definition::SwitchId;
+
 /// DriverTriggerMode answers: What causes the driver to fire?
 #[derive(Debug, Clone, Default)]
 pub enum DriverTriggerMode {
@@ -48,3 +50,109 @@ pub enum DriverTriggerDualMode {
   /// Driver is active when flip switch is open and virtual switch (manually triggered) is true/on
   FlipSwitchFalse_VirtualFlop(&'static str),
 }
+
+impl DriverTriggerMode {
+  /// Driver is active when the switch is closed
+  pub fn switch(switch: impl SwitchId) -> Self {
+    DriverTriggerMode::Switch(switch.switch_name())
+  }
+
+  /// Driver is active when the switch is open
+  pub fn inverted_switch(switch: impl SwitchId) -> Self {
+    DriverTriggerMode::InvertedSwitch(switch.switch_name())
+  }
+
+  /// The hardware switch which triggers the driver, if any
+  pub fn trigger_switch(&self) -> Option<&'static str> {
+    match self {
+      DriverTriggerMode::Switch(s) | DriverTriggerMode::InvertedSwitch(s) => Some(s),
+      _ => None,
+    }
+  }
+}
+
+/// Constructors named after the variants, taking typed switch IDs
+impl DriverTriggerDualMode {
+  pub fn flip_switch_true_flop_switch_true(
+    flip_switch: impl SwitchId,
+    flop_switch: impl SwitchId,
+  ) -> Self {
+    DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchTrue {
+      flip_switch: flip_switch.switch_name(),
+      flop_switch: flop_switch.switch_name(),
+    }
+  }
+
+  pub fn flip_switch_false_flop_switch_true(
+    flip_switch: impl SwitchId,
+    flop_switch: impl SwitchId,
+  ) -> Self {
+    DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchTrue {
+      flip_switch: flip_switch.switch_name(),
+      flop_switch: flop_switch.switch_name(),
+    }
+  }
+
+  pub fn flip_switch_true_flop_switch_false(
+    flip_switch: impl SwitchId,
+    flop_switch: impl SwitchId,
+  ) -> Self {
+    DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchFalse {
+      flip_switch: flip_switch.switch_name(),
+      flop_switch: flop_switch.switch_name(),
+    }
+  }
+
+  pub fn flip_switch_false_flop_switch_false(
+    flip_switch: impl SwitchId,
+    flop_switch: impl SwitchId,
+  ) -> Self {
+    DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchFalse {
+      flip_switch: flip_switch.switch_name(),
+      flop_switch: flop_switch.switch_name(),
+    }
+  }
+
+  pub fn virtual_flip_flop_switch_true(flop_switch: impl SwitchId) -> Self {
+    DriverTriggerDualMode::VirtualFlip_FlopSwitchTrue(flop_switch.switch_name())
+  }
+
+  pub fn virtual_flip_flop_switch_false(flop_switch: impl SwitchId) -> Self {
+    DriverTriggerDualMode::VirtualFlip_FlopSwitchFalse(flop_switch.switch_name())
+  }
+
+  pub fn flip_switch_true_virtual_flop(flip_switch: impl SwitchId) -> Self {
+    DriverTriggerDualMode::FlipSwitchTrue_VirtualFlop(flip_switch.switch_name())
+  }
+
+  pub fn flip_switch_false_virtual_flop(flip_switch: impl SwitchId) -> Self {
+    DriverTriggerDualMode::FlipSwitchFalse_VirtualFlop(flip_switch.switch_name())
+  }
+
+  /// The hardware switches which trigger the driver
+  pub fn trigger_switches(&self) -> Vec<&'static str> {
+    match self {
+      DriverTriggerDualMode::Disabled => vec![],
+      DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchTrue {
+        flip_switch,
+        flop_switch,
+      }
+      | DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchTrue {
+        flip_switch,
+        flop_switch,
+      }
+      | DriverTriggerDualMode::FlipSwitchTrue_FlopSwitchFalse {
+        flip_switch,
+        flop_switch,
+      }
+      | DriverTriggerDualMode::FlipSwitchFalse_FlopSwitchFalse {
+        flip_switch,
+        flop_switch,
+      } => vec![flip_switch, flop_switch],
+      DriverTriggerDualMode::VirtualFlip_FlopSwitchTrue(s)
+      | DriverTriggerDualMode::VirtualFlip_FlopSwitchFalse(s)
+      | DriverTriggerDualMode::FlipSwitchTrue_VirtualFlop(s)
+      | DriverTriggerDualMode::FlipSwitchFalse_VirtualFlop(s) => vec![s],
+    }
+  }
+}
@@ -1,7 +1,13 @@
 mod boot_config;
+mod definition_file;
 mod exp;
+mod hardware_id;
 mod io;
+mod validation;
 
 pub use boot_config::*;
+pub use definition_file::{DefinitionError, MachineDefinition};
 pub use exp::*;
+pub use hardware_id::*;
 pub use io::*;
+pub use validation::*;
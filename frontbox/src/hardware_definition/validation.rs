@@ -0,0 +1,453 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use crossterm::event::KeyCode;
+
+use crate::hardware_definition::*;
+
+/// A mistake in a hardware definition, found by `IoNetworkBuilder::validate` or by `MachineBuilder::boot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+  /// Two switches share a name, so only one of them could be referenced
+  DuplicateSwitch(&'static str),
+  DuplicateDriver(&'static str),
+  DuplicateLed(&'static str),
+  /// A driver's mode references a switch which isn't defined
+  UnknownDriverSwitch {
+    driver: &'static str,
+    switch: &'static str,
+  },
+  UnknownGroupDriver {
+    group: &'static str,
+    driver: &'static str,
+  },
+  UnknownFlipperSwitch {
+    flipper: &'static str,
+    switch: &'static str,
+  },
+  UnknownFlipperDriver {
+    flipper: &'static str,
+    driver: &'static str,
+  },
+  UnknownKeyboardSwitch {
+    key: KeyCode,
+    switch: &'static str,
+  },
+  /// An LED port's group has the same name as an LED, so declarations for it would be ambiguous
+  LedGroupConflict(&'static str),
+  /// Two LED port definitions claim some of the same LEDs on one port
+  OverlappingLedPorts {
+    address: u8,
+    breakout: Option<u8>,
+    port: u8,
+  },
+}
+
+impl Display for ValidationError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ValidationError::DuplicateSwitch(name) => {
+        write!(f, "Switch '{}' is defined more than once", name)
+      }
+      ValidationError::DuplicateDriver(name) => {
+        write!(f, "Driver '{}' is defined more than once", name)
+      }
+      ValidationError::DuplicateLed(name) => write!(f, "LED '{}' is defined more than once", name),
+      ValidationError::LedGroupConflict(name) => {
+        write!(f, "LED group '{}' has the same name as an LED", name)
+      }
+      ValidationError::UnknownDriverSwitch { driver, switch } => {
+        write!(
+          f,
+          "Driver '{}' uses switch '{}' which is not defined",
+          driver, switch
+        )
+      }
+      ValidationError::UnknownGroupDriver { group, driver } => {
+        write!(
+          f,
+          "Driver group '{}' has driver '{}' which is not defined",
+          group, driver
+        )
+      }
+      ValidationError::UnknownFlipperSwitch { flipper, switch } => {
+        write!(
+          f,
+          "Flipper '{}' uses switch '{}' which is not defined",
+          flipper, switch
+        )
+      }
+      ValidationError::UnknownFlipperDriver { flipper, driver } => {
+        write!(
+          f,
+          "Flipper '{}' uses driver '{}' which is not defined",
+          flipper, driver
+        )
+      }
+      ValidationError::UnknownKeyboardSwitch { key, switch } => {
+        write!(
+          f,
+          "Key {} is mapped to switch '{}' which is not defined",
+          key, switch
+        )
+      }
+      ValidationError::OverlappingLedPorts {
+        address,
+        breakout,
+        port,
+      } => match breakout {
+        Some(breakout) => write!(
+          f,
+          "LED port {} on EXP board {:X} breakout {} is defined more than once with overlapping LEDs",
+          port, address, breakout
+        ),
+        None => write!(
+          f,
+          "LED port {} on EXP board {:X} is defined more than once with overlapping LEDs",
+          port, address
+        ),
+      },
+    }
+  }
+}
+
+/// Names which appear more than once, each reported once in the order they were first repeated
+fn duplicates(names: impl Iterator<Item = &'static str>) -> Vec<&'static str> {
+  let mut seen = HashSet::new();
+  let mut repeated = Vec::new();
+  for name in names {
+    if !seen.insert(name) && !repeated.contains(&name) {
+      repeated.push(name);
+    }
+  }
+  repeated
+}
+
+impl IoNetworkBuilder {
+  /// Check the network for mistakes which would otherwise go unnoticed until the machine misbehaves, e.g. a driver
+  /// triggered by a misspelled switch. Returns every problem found rather than stopping at the first.
+  pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+    self.validate_with(&[], &[])
+  }
+
+  /// Check the network along with the rest of the machine: the LEDs on the expansion boards and the switches the
+  /// keyboard is mapped to
+  pub fn validate_with(
+    &self,
+    expansion_boards: &[ExpansionBoardDefinition],
+    keyboard_mappings: &[(KeyCode, &'static str)],
+  ) -> Result<(), Vec<ValidationError>> {
+    let mut problems = Vec::new();
+
+    // pin order, so problems are reported in the order they appear on the boards
+    let mut switches = Vec::new();
+    let mut drivers = Vec::new();
+    for board in &self.boards {
+      let mut board_switches: Vec<_> = board.switch_map.iter().collect();
+      board_switches.sort();
+      switches.extend(board_switches.into_iter().map(|(_, name)| *name));
+
+      let mut board_drivers: Vec<_> = board.driver_map.iter().collect();
+      board_drivers.sort();
+      drivers.extend(board_drivers.into_iter().map(|(_, name)| *name));
+    }
+
+    problems.extend(
+      duplicates(switches.iter().copied())
+        .into_iter()
+        .map(ValidationError::DuplicateSwitch),
+    );
+    problems.extend(
+      duplicates(drivers.iter().copied())
+        .into_iter()
+        .map(ValidationError::DuplicateDriver),
+    );
+
+    for board in &self.boards {
+      let mut board_drivers: Vec<_> = board.driver_map.iter().collect();
+      board_drivers.sort();
+      for (_, driver) in board_drivers {
+        let Some(mode) = board.driver_configs.get(driver) else {
+          continue;
+        };
+        for switch in mode.switches() {
+          let problem = ValidationError::UnknownDriverSwitch { driver, switch };
+          if !switches.contains(&switch) && !problems.contains(&problem) {
+            problems.push(problem);
+          }
+        }
+      }
+    }
+
+    let mut groups: Vec<_> = self.driver_groups.iter().collect();
+    groups.sort();
+    for (group, members) in groups {
+      for driver in members {
+        if !drivers.contains(driver) {
+          problems.push(ValidationError::UnknownGroupDriver { group, driver });
+        }
+      }
+    }
+
+    for flipper in &self.flippers {
+      for switch in flipper.switches() {
+        if !switches.contains(&switch) {
+          problems.push(ValidationError::UnknownFlipperSwitch {
+            flipper: flipper.name,
+            switch,
+          });
+        }
+      }
+      for driver in flipper.drivers() {
+        if !drivers.contains(&driver) {
+          problems.push(ValidationError::UnknownFlipperDriver {
+            flipper: flipper.name,
+            driver,
+          });
+        }
+      }
+    }
+
+    problems.extend(validate_machine(
+      &switches,
+      expansion_boards,
+      keyboard_mappings,
+    ));
+
+    if problems.is_empty() {
+      Ok(())
+    } else {
+      Err(problems)
+    }
+  }
+}
+
+/// The checks which involve more than the IO network: the LEDs on the expansion boards and the switches the keyboard
+/// is mapped to. `MachineBuilder::boot` runs these before talking to the hardware.
+pub(crate) fn validate_machine(
+  switches: &[&'static str],
+  expansion_boards: &[ExpansionBoardDefinition],
+  keyboard_mappings: &[(KeyCode, &'static str)],
+) -> Vec<ValidationError> {
+  let mut problems = Vec::new();
+
+  for (key, switch) in keyboard_mappings {
+    if !switches.contains(switch) {
+      problems.push(ValidationError::UnknownKeyboardSwitch { key: *key, switch });
+    }
+  }
+
+  let leds = expansion_boards
+    .iter()
+    .flat_map(|board| &board.led_ports)
+    .flat_map(|port| port.leds.iter().copied());
+  problems.extend(
+    duplicates(leds.clone())
+      .into_iter()
+      .map(ValidationError::DuplicateLed),
+  );
+
+  let mut port_groups: Vec<_> = led_port_groups(expansion_boards).into_keys().collect();
+  port_groups.sort();
+  for group in port_groups {
+    if leds.clone().any(|led| led == group) {
+      problems.push(ValidationError::LedGroupConflict(group));
+    }
+  }
+
+  let ports: Vec<_> = expansion_boards
+    .iter()
+    .flat_map(|board| board.led_ports.iter().map(move |port| (board, port)))
+    .collect();
+  for (i, (board, port)) in ports.iter().enumerate() {
+    let range = port.start as usize..port.start as usize + port.leds.len();
+    let overlaps = ports[..i].iter().any(|(other_board, other)| {
+      let other_range = other.start as usize..other.start as usize + other.leds.len();
+      other_board.address == board.address
+        && other_board.breakout == board.breakout
+        && other.port == port.port
+        && range.start < other_range.end
+        && other_range.start < range.end
+    });
+    let problem = ValidationError::OverlappingLedPorts {
+      address: board.address,
+      breakout: board.breakout,
+      port: port.port,
+    };
+    if overlaps && !problems.contains(&problem) {
+      problems.push(problem);
+    }
+  }
+
+  problems
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn board() -> IoBoardBuilder {
+    FastIoBoards::io_0804()
+      .with_switch("start", 0)
+      .with_switch("sling", 1)
+      .with_driver("knocker", 0)
+  }
+
+  fn pulse(switch: &'static str) -> PulseMode {
+    PulseMode {
+      trigger_mode: DriverTriggerMode::switch(switch),
+      ..Default::default()
+    }
+  }
+
+  fn led_port(port: u8, start: u8, leds: Vec<&'static str>) -> LedPortDefinition {
+    LedPortDefinition {
+      port,
+      start,
+      leds,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_valid_network() {
+    let mut network = IoNetworkBuilder::new();
+    network.add_board(board().with_driver_cfg("sling", 1, pulse("sling")));
+    network.add_driver_group("coils", vec!["knocker", "sling"]);
+    network.add_flipper(FlipperDefinition::single_wound("left", "start", "knocker"));
+
+    let boards = vec![
+      ExpansionBoardDefinition::neutron().with_led_port(LedPortDefinition {
+        group: Some("gi"),
+        ..led_port(0, 0, vec!["gi_1", "gi_2"])
+      }),
+    ];
+    assert_eq!(
+      network.validate_with(&boards, &[(KeyCode::Char('s'), "start")]),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn test_duplicate_names() {
+    let mut network = IoNetworkBuilder::new();
+    network.add_board(board());
+    network.add_board(board().with_switch("sling", 2));
+
+    assert_eq!(
+      network.validate(),
+      Err(vec![
+        ValidationError::DuplicateSwitch("start"),
+        ValidationError::DuplicateSwitch("sling"),
+        ValidationError::DuplicateDriver("knocker"),
+      ])
+    );
+  }
+
+  #[test]
+  fn test_unknown_references() {
+    let mut network = IoNetworkBuilder::new();
+    network.add_board(
+      board()
+        .with_driver_cfg("left_sling", 1, pulse("left_slng"))
+        .with_driver_cfg("right_sling", 2, pulse("rigth_sling")),
+    );
+    network.add_driver_group("slings", vec!["left_sling", "right_slnig"]);
+    network.add_flipper(FlipperDefinition::dual_wound(
+      "left",
+      "lft_button",
+      "main",
+      "knocker",
+      "eos",
+    ));
+
+    assert_eq!(
+      network.validate(),
+      Err(vec![
+        ValidationError::UnknownDriverSwitch {
+          driver: "left_sling",
+          switch: "left_slng",
+        },
+        ValidationError::UnknownDriverSwitch {
+          driver: "right_sling",
+          switch: "rigth_sling",
+        },
+        ValidationError::UnknownGroupDriver {
+          group: "slings",
+          driver: "right_slnig",
+        },
+        ValidationError::UnknownFlipperSwitch {
+          flipper: "left",
+          switch: "lft_button",
+        },
+        ValidationError::UnknownFlipperSwitch {
+          flipper: "left",
+          switch: "eos",
+        },
+        ValidationError::UnknownFlipperDriver {
+          flipper: "left",
+          driver: "main",
+        },
+      ])
+    );
+  }
+
+  #[test]
+  fn test_keyboard_mappings() {
+    let mut network = IoNetworkBuilder::new();
+    network.add_board(board());
+
+    assert_eq!(
+      network.validate_with(
+        &[],
+        &[(KeyCode::Char('s'), "start"), (KeyCode::Enter, "strat")]
+      ),
+      Err(vec![ValidationError::UnknownKeyboardSwitch {
+        key: KeyCode::Enter,
+        switch: "strat",
+      }])
+    );
+  }
+
+  #[test]
+  fn test_leds() {
+    let boards = vec![
+      ExpansionBoardDefinition::neutron()
+        .with_led_port(LedPortDefinition {
+          group: Some("shoot_again"),
+          ..led_port(0, 0, vec!["shoot_again", "start_lamp"])
+        })
+        // starts inside the LEDs above
+        .with_led_port(led_port(0, 1, vec!["extra_ball"]))
+        // the same port, clear of the LEDs above
+        .with_led_port(led_port(0, 2, vec!["jackpot"])),
+      ExpansionBoardDefinition::custom("90", None).with_led_port(led_port(
+        0,
+        0,
+        vec!["start_lamp"],
+      )),
+    ];
+
+    assert_eq!(
+      validate_machine(&[], &boards, &[]),
+      vec![
+        ValidationError::DuplicateLed("start_lamp"),
+        ValidationError::LedGroupConflict("shoot_again"),
+        ValidationError::OverlappingLedPorts {
+          address: 0x48,
+          breakout: None,
+          port: 0,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_build_does_not_validate() {
+    let mut network = IoNetworkBuilder::new();
+    network.add_board(board().with_driver_cfg("sling", 1, pulse("slng")));
+
+    assert!(network.validate().is_err());
+    assert_eq!(network.build().drivers.len(), 2);
+  }
+}
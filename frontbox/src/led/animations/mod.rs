@@ -1,5 +1,7 @@
 mod interpolation_animation;
 mod sequence_animation;
+mod spatial_animation;
 
 pub use interpolation_animation::*;
 pub use sequence_animation::*;
+pub use spatial_animation::*;
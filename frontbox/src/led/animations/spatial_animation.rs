@@ -0,0 +1,199 @@
+use fast_protocol::Color;
+use std::time::Duration;
+
+use crate::led::animation::{Animation, AnimationCycle};
+use crate::led::curve::Curve;
+use crate::led::led_layout::LedLayout;
+
+/// The direction a spatial animation travels across the playfield
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spread {
+  /// Outwards from a point, like a ripple
+  Radial { origin: (f32, f32) },
+  /// In a straight line at an angle in degrees, 0 travelling towards +x and 90 towards +y
+  Linear { angle: f32 },
+}
+
+/// Animation implementation that sends a band of colour across the playfield, colouring each LED by its position
+/// rather than by a hand-written sequence. The band's leading edge is the first stop and its trailing edge the last.
+/// LEDs the band hasn't reached yet, or has already passed, are left undeclared so whatever is below shows through.
+///
+/// A wave from the left slingshot:
+/// ```ignore
+/// SpatialAnimation::radial_pulse(
+///   &layout,
+///   layout.position("left_sling_gi").unwrap(),
+///   Duration::from_millis(800),
+///   Color::orange(),
+///   AnimationCycle::Forever,
+/// )
+/// ```
+#[derive(Clone)]
+pub struct SpatialAnimation {
+  /// Each LED with its distance along the spread, from 0 for the first LED reached to 1 for the last
+  leds: Vec<(&'static str, f32)>,
+  /// Thickness of the band, as a fraction of the distance it travels
+  width: f32,
+  duration: Duration,
+  elapsed: Duration,
+  curve: Curve,
+  stops: Vec<Color>,
+  cycle: AnimationCycle,
+  cycle_count: u32,
+}
+
+impl SpatialAnimation {
+  pub fn new(
+    layout: &LedLayout,
+    spread: Spread,
+    width: f32,
+    duration: Duration,
+    curve: Curve,
+    stops: Vec<Color>,
+    cycle: AnimationCycle,
+  ) -> Box<Self> {
+    if stops.is_empty() {
+      panic!("Spatial animation needs at least one color stop");
+    }
+    if width <= 0.0 {
+      panic!("Spatial animation width must be positive, got {}", width);
+    }
+
+    let mut leds: Vec<_> = layout
+      .iter()
+      .map(|(name, (x, y))| {
+        let distance = match spread {
+          Spread::Radial { origin } => (x - origin.0).hypot(y - origin.1),
+          Spread::Linear { angle } => {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            x * cos + y * sin
+          }
+        };
+        (name, distance)
+      })
+      .collect();
+    leds.sort_by(|a, b| a.0.cmp(b.0));
+
+    // radial distances start at the origin, even if no LED is there
+    let nearest = match spread {
+      Spread::Radial { .. } => 0.0,
+      Spread::Linear { .. } => leds.iter().map(|(_, d)| *d).fold(f32::INFINITY, f32::min),
+    };
+    let furthest = leds
+      .iter()
+      .map(|(_, d)| *d)
+      .fold(f32::NEG_INFINITY, f32::max);
+    let span = furthest - nearest;
+    for (_, distance) in &mut leds {
+      *distance = if span > 0.0 {
+        (*distance - nearest) / span
+      } else {
+        0.0
+      };
+    }
+
+    Box::new(Self {
+      leds,
+      width,
+      duration,
+      elapsed: Duration::ZERO,
+      curve,
+      stops,
+      cycle,
+      cycle_count: 0,
+    })
+  }
+
+  /// A ring of `color` fading out behind it, travelling outwards from `origin`
+  pub fn radial_pulse(
+    layout: &LedLayout,
+    origin: (f32, f32),
+    duration: Duration,
+    color: Color,
+    cycle: AnimationCycle,
+  ) -> Box<Self> {
+    Self::new(
+      layout,
+      Spread::Radial { origin },
+      0.3,
+      duration,
+      Curve::Linear,
+      vec![color, Color::black()],
+      cycle,
+    )
+  }
+
+  /// A line of `color` fading out behind it, sweeping across the playfield at `angle` degrees
+  pub fn sweep(
+    layout: &LedLayout,
+    angle: f32,
+    duration: Duration,
+    color: Color,
+    cycle: AnimationCycle,
+  ) -> Box<Self> {
+    Self::new(
+      layout,
+      Spread::Linear { angle },
+      0.3,
+      duration,
+      Curve::Linear,
+      vec![color, Color::black()],
+      cycle,
+    )
+  }
+
+  /// Colour at `t` across the band, 0 being the leading edge
+  fn gradient(&self, t: f32) -> Color {
+    if self.stops.len() == 1 {
+      return self.stops[0].clone();
+    }
+    let position = t * (self.stops.len() - 1) as f32;
+    let index = (position as usize).min(self.stops.len() - 2);
+    self.stops[index].mix(&self.stops[index + 1], position - index as f32)
+  }
+}
+
+impl Animation<Vec<(&'static str, Color)>> for SpatialAnimation {
+  fn tick(&mut self, delta_time: Duration) -> Duration {
+    self.elapsed += delta_time;
+    if self.elapsed >= self.duration {
+      if self.cycle != AnimationCycle::Forever && self.cycle_count < u32::MAX {
+        self.cycle_count += 1;
+      }
+
+      if !self.is_complete() {
+        self.elapsed -= self.duration;
+        return self.elapsed;
+      }
+    }
+
+    Duration::ZERO
+  }
+
+  fn sample(&self) -> Vec<(&'static str, Color)> {
+    let phase = (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+    // the front travels past the furthest LED until the whole band has left the playfield
+    let front = self.curve.sample(phase) * (1.0 + self.width);
+    self
+      .leds
+      .iter()
+      .filter_map(|(name, distance)| {
+        let t = (front - distance) / self.width;
+        (0.0..=1.0).contains(&t).then(|| (*name, self.gradient(t)))
+      })
+      .collect()
+  }
+
+  fn is_complete(&self) -> bool {
+    match self.cycle {
+      AnimationCycle::Once => self.cycle_count > 0,
+      AnimationCycle::Times(n) => self.cycle_count >= n,
+      AnimationCycle::Forever => false,
+    }
+  }
+
+  fn reset(&mut self) {
+    self.elapsed = Duration::ZERO;
+    self.cycle_count = 0;
+  }
+}
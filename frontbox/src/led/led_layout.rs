@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use crate::hardware_definition::ExpansionBoardDefinition;
+
+/// Where each LED sits on the playfield, for animations which colour LEDs by their position
+#[derive(Debug, Clone, Default)]
+pub struct LedLayout {
+  positions: HashMap<&'static str, (f32, f32)>,
+}
+
+impl LedLayout {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The layout of every LED port which has `positions`
+  pub fn from_expansion_boards(expansion_boards: &[ExpansionBoardDefinition]) -> Self {
+    let mut layout = Self::new();
+    for board in expansion_boards {
+      for led_port in &board.led_ports {
+        for (led, position) in led_port.leds.iter().zip(&led_port.positions) {
+          layout.positions.insert(led, *position);
+        }
+      }
+    }
+    layout
+  }
+
+  pub fn with_led(mut self, name: &'static str, x: f32, y: f32) -> Self {
+    self.positions.insert(name, (x, y));
+    self
+  }
+
+  pub fn position(&self, name: &str) -> Option<(f32, f32)> {
+    self.positions.get(name).copied()
+  }
+
+  /// Only the LEDs in `names`, e.g. to run an effect across a single group
+  pub fn subset(&self, names: &[&str]) -> Self {
+    Self {
+      positions: self
+        .positions
+        .iter()
+        .filter(|(name, _)| names.contains(name))
+        .map(|(name, position)| (*name, *position))
+        .collect(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.positions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.positions.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&'static str, (f32, f32))> + '_ {
+    self
+      .positions
+      .iter()
+      .map(|(name, position)| (*name, *position))
+  }
+}
@@ -8,13 +8,20 @@ const LED_SET_BATCH_SIZE: usize = 24;
 
 pub struct LedRenderer {
   led_map: HashMap<&'static str, AddressableLed>,
+  /// Names which declare every LED they list at once
+  groups: HashMap<&'static str, Vec<&'static str>>,
   set_leds: HashMap<&'static str, LedState>,
   resolver: Box<dyn LedResolver>,
 }
 
 impl LedRenderer {
-  pub fn new(expansion_boards: &Vec<ExpansionBoardDefinition>) -> Self {
+  pub fn new(
+    expansion_boards: &Vec<ExpansionBoardDefinition>,
+    led_groups: HashMap<&'static str, Vec<&'static str>>,
+  ) -> Self {
     let mut led_map = HashMap::new();
+    let mut groups = led_port_groups(expansion_boards);
+    groups.extend(led_groups);
     let mut set_leds = HashMap::new();
 
     for board in expansion_boards {
@@ -38,6 +45,7 @@ impl LedRenderer {
 
     Self {
       led_map,
+      groups,
       set_leds,
       // resolver: Box::new(BezierMixResolver::new()),
       resolver: Box::new(AlternateResolver::new()),
@@ -59,26 +67,52 @@ impl LedRenderer {
     exp_port: &mut SerialInterface,
     led_declarations: HashMap<u64, HashMap<&'static str, LedState>>,
   ) {
-    // group declarations by LED name, finding conflicts
-    let mut conflicts: HashMap<&'static str, Vec<(u64, LedState)>> = HashMap::new();
-    let mut led_temp_updates: HashMap<&'static str, (u64, LedState)> = HashMap::new();
+    // group declarations by LED name, expanding LED groups to their members
+    let mut declared: HashMap<&'static str, Vec<(u64, LedState)>> = HashMap::new();
+    let mut declared_by_group: HashMap<&'static str, Vec<(u64, LedState, usize)>> = HashMap::new();
     for (system_id, states) in led_declarations {
       for (led_name, state) in states {
-        if let Some(conflict_list) = conflicts.get_mut(led_name) {
-          conflict_list.push((system_id, state));
-        } else if led_temp_updates.contains_key(led_name) {
-          let current = led_temp_updates.remove(led_name).unwrap();
-          conflicts.insert(led_name, vec![current, (system_id, state)]);
-        } else {
-          led_temp_updates.insert(led_name, (system_id, state));
+        let Some(members) = self.groups.get(led_name) else {
+          declared
+            .entry(led_name)
+            .or_default()
+            .push((system_id, state));
+          continue;
+        };
+
+        for member in members {
+          let list = declared_by_group.entry(member).or_default();
+          // an LED in two groups declared by the same system takes the smaller, more specific group
+          match list.iter_mut().find(|(id, _, _)| *id == system_id) {
+            Some(existing) if existing.2 > members.len() => {
+              *existing = (system_id, state.clone(), members.len())
+            }
+            Some(_) => {}
+            None => list.push((system_id, state.clone(), members.len())),
+          }
         }
       }
     }
 
+    // an LED declared by name overrides every group declaration for it
+    for (led_name, group_list) in declared_by_group {
+      declared.entry(led_name).or_insert_with(|| {
+        group_list
+          .into_iter()
+          .map(|(system_id, state, _)| (system_id, state))
+          .collect()
+      });
+    }
+
     // resolve conflicts
-    for (led_name, conflict_list) in conflicts {
-      let resolved = self.resolver.resolve(led_name, conflict_list);
-      led_temp_updates.insert(led_name, (0, resolved));
+    let mut led_temp_updates: HashMap<&'static str, (u64, LedState)> = HashMap::new();
+    for (led_name, mut list) in declared {
+      if list.len() == 1 {
+        led_temp_updates.insert(led_name, list.remove(0));
+      } else {
+        let resolved = self.resolver.resolve(led_name, list);
+        led_temp_updates.insert(led_name, (0, resolved));
+      }
     }
 
     // diff with previous frame
@@ -153,7 +187,7 @@ impl LedRenderer {
     for address in leds_to_set.keys() {
       for chunk in leds_to_set[&address].chunks(LED_SET_BATCH_SIZE) {
         let cmd = SetLedCommand::new(address.address, address.breakout, chunk.to_vec());
-        let _ = exp_port.dispatch(&cmd).await;
+        exp_port.dispatch(&cmd);
       }
     }
 
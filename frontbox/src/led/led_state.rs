@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use crate::LedId;
 use crate::led::animation::Animation;
 use fast_protocol::Color;
 
@@ -37,19 +38,23 @@ impl LedDeclarationBuilder {
     HashMap::new()
   }
 
-  pub fn off(mut self, name: &'static str) -> Self {
-    self.declarations.insert(name, LedState::Off);
+  /// Turn off an LED, or every LED in a group
+  pub fn off(mut self, name: impl LedId) -> Self {
+    self.declarations.insert(name.led_name(), LedState::Off);
     self
   }
 
-  pub fn on(mut self, name: &'static str, color: Color) -> Self {
-    self.declarations.insert(name, LedState::On(color));
+  /// Light an LED, or every LED in a group. LEDs declared by their own name keep their own color.
+  pub fn on(mut self, name: impl LedId, color: Color) -> Self {
+    self
+      .declarations
+      .insert(name.led_name(), LedState::On(color));
     self
   }
 
   pub fn next_frame(
     self,
-    name: &'static str,
+    name: impl LedId,
     animation: &mut Box<dyn Animation<Color> + 'static>,
   ) -> Self {
     animation.tick(self.delta_time);
@@ -1,6 +1,7 @@
 mod animation;
 mod animations;
 mod curve;
+mod led_layout;
 mod led_renderer;
 mod led_state;
 mod resolvers;
@@ -8,6 +9,7 @@ mod resolvers;
 pub use animation::*;
 pub use animations::*;
 pub use curve::*;
+pub use led_layout::*;
 pub use led_renderer::*;
 pub use led_state::*;
 pub use resolvers::*;
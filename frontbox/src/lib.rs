@@ -16,15 +16,23 @@ pub mod prelude {
   pub use crate::handle_event;
   pub use crate::hardware_definition::*;
   pub use crate::led::*;
+  pub use crate::machine::boot_error::{BootError, HardwareTarget};
   pub use crate::machine::config_value::{ConfigItem, ConfigValue};
   pub use crate::machine::context::Context;
   pub use crate::machine::event::*;
+  pub use crate::machine::expansion_inventory::{
+    ExpansionBreakout, ExpansionInventory, ExpansionMismatch,
+  };
+  pub use crate::machine::firmware_check::BoardFirmware;
+  pub use crate::machine::io_inventory::{IoInventory, IoNode, IoNodeMismatch};
   pub use crate::machine::machine::*;
   pub use crate::machine::machine_builder::*;
   pub use crate::machine::machine_command::MachineCommand;
   pub use crate::machine::machine_config::{MachineConfig, default_config};
   pub use crate::machine::plugin::*;
+  pub use crate::machine::serial_recorder::{SerialRecorder, SerialRecording};
   pub use crate::machine::switch_context::SwitchContext;
+  pub use crate::machine::transport::Transport;
   pub use crate::states::*;
   pub use crate::store::*;
   pub use crate::systems::{CloneableSystem, OnEventSystem, System, SystemTimer, TimerMode};
@@ -34,7 +42,7 @@ pub mod prelude {
   pub use crossterm::event::MediaKeyCode;
   pub use crossterm::event::ModifierKeyCode;
   pub use fast_protocol::driver_config::*;
-  pub use fast_protocol::{Color, DriverTriggerControlMode, LedType, Power};
+  pub use fast_protocol::{Color, DriverTriggerControlMode, LedType, MotorDirection, Power};
   pub use frontbox_derive::*;
   pub use serde::Serialize;
   pub use std::time::Duration;
@@ -0,0 +1,179 @@
+use std::fmt::Display;
+
+use fast_protocol::{Capability, FastResponseError, FirmwareVersion, RequestError};
+
+use crate::hardware_definition::ValidationError;
+use crate::machine::firmware_check::BoardFirmware;
+
+/// The hardware a failed boot step concerned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HardwareTarget {
+  Mainboard,
+  IoNode {
+    node: u8,
+  },
+  Switch {
+    name: &'static str,
+    id: usize,
+  },
+  Driver {
+    name: &'static str,
+    id: usize,
+  },
+  ExpansionBoard {
+    address: u8,
+  },
+  LedPort {
+    address: u8,
+    breakout: Option<u8>,
+    port: u8,
+  },
+  Servo {
+    name: &'static str,
+    address: u8,
+  },
+  Stepper {
+    name: &'static str,
+    address: u8,
+  },
+}
+
+impl Display for HardwareTarget {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HardwareTarget::Mainboard => write!(f, "Mainboard"),
+      HardwareTarget::IoNode { node } => write!(f, "IO NET node {}", node),
+      HardwareTarget::Switch { name, id } => write!(f, "Switch '{}' ({})", name, id),
+      HardwareTarget::Driver { name, id } => write!(f, "Driver '{}' ({})", name, id),
+      HardwareTarget::ExpansionBoard { address } => write!(f, "EXP board 0x{:X}", address),
+      HardwareTarget::LedPort {
+        address,
+        breakout: Some(breakout),
+        port,
+      } => write!(
+        f,
+        "LED port {} on EXP board 0x{:X} breakout {}",
+        port, address, breakout
+      ),
+      HardwareTarget::LedPort {
+        address,
+        breakout: None,
+        port,
+      } => write!(f, "LED port {} on EXP board 0x{:X}", port, address),
+      HardwareTarget::Servo { name, address } => {
+        write!(f, "Servo '{}' on EXP board 0x{:X}", name, address)
+      }
+      HardwareTarget::Stepper { name, address } => {
+        write!(f, "Stepper '{}' on EXP board 0x{:X}", name, address)
+      }
+    }
+  }
+}
+
+/// Why `MachineBuilder::boot` could not bring up the machine. The display text is suitable for showing to an operator.
+#[derive(Debug)]
+pub enum BootError {
+  /// The machine definition has mistakes, e.g. two LEDs with the same name or a key mapped to a missing switch. Every
+  /// problem found is listed.
+  InvalidDefinition { problems: Vec<ValidationError> },
+  /// A serial port could not be opened
+  PortUnavailable {
+    port: String,
+    error: tokio_serial::Error,
+  },
+  /// The file to record serial traffic to could not be created
+  RecordingUnavailable { path: String, error: std::io::Error },
+  /// A board runs firmware which is too old for the machine
+  IncompatibleFirmware {
+    target: HardwareTarget,
+    firmware: BoardFirmware,
+    required: FirmwareVersion,
+    /// The feature which needs newer firmware, or `None` when the firmware is older than the minimum supported
+    capability: Option<Capability>,
+  },
+  /// A command sent while configuring the hardware failed
+  Request {
+    target: HardwareTarget,
+    error: RequestError,
+  },
+}
+
+impl BootError {
+  pub(crate) fn request(target: HardwareTarget, error: RequestError) -> Self {
+    BootError::Request { target, error }
+  }
+}
+
+impl Display for BootError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BootError::InvalidDefinition { problems } => {
+        let problems: Vec<String> = problems.iter().map(|p| format!("  {}", p)).collect();
+        write!(f, "Invalid machine definition:\n{}", problems.join("\n"))
+      }
+      BootError::PortUnavailable { port, error } => {
+        write!(f, "Could not open serial port {}: {}", port, error)
+      }
+      BootError::RecordingUnavailable { path, error } => {
+        write!(f, "Could not create serial recording {}: {}", path, error)
+      }
+      BootError::IncompatibleFirmware {
+        target,
+        firmware,
+        required,
+        capability: None,
+      } => write!(
+        f,
+        "{} runs {} firmware {}, older than the minimum supported {}",
+        target, firmware.product_number, firmware.version, required
+      ),
+      BootError::IncompatibleFirmware {
+        target,
+        firmware,
+        required,
+        capability: Some(capability),
+      } => write!(
+        f,
+        "{} runs {} firmware {}, which does not support {} (needs {})",
+        target, firmware.product_number, firmware.version, capability, required
+      ),
+      BootError::Request { target, error } => match &error.error {
+        FastResponseError::Timeout => {
+          write!(f, "{} did not respond to {}", target, error.prefix())
+        }
+        FastResponseError::Failed => match &error.response {
+          Some(response) => write!(f, "{} rejected {} with {}", target, error.command, response),
+          None => write!(f, "{} rejected {}", target, error.command),
+        },
+        FastResponseError::Disconnected => write!(
+          f,
+          "Connection lost while sending {} to {}",
+          error.prefix(),
+          target
+        ),
+        _ => write!(
+          f,
+          "{} sent an unexpected response to {}: {}",
+          target,
+          error.prefix(),
+          error
+            .response
+            .as_ref()
+            .map_or("<none>".to_string(), |r| r.to_string())
+        ),
+      },
+    }
+  }
+}
+
+impl std::error::Error for BootError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      BootError::InvalidDefinition { .. } => None,
+      BootError::PortUnavailable { error, .. } => Some(error),
+      BootError::RecordingUnavailable { error, .. } => Some(error),
+      BootError::IncompatibleFirmware { .. } => None,
+      BootError::Request { error, .. } => Some(error),
+    }
+  }
+}
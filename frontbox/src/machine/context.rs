@@ -39,12 +39,22 @@ impl<'a> Context<'a> {
     }
   }
 
-  pub fn is_switch_closed(&self, switch_name: &'static str) -> Option<bool> {
-    self.switches.is_closed_by_name(switch_name)
+  pub fn is_switch_closed(&self, switch_name: impl SwitchId) -> Option<bool> {
+    self.switches.is_closed_by_name(switch_name.switch_name())
   }
 
-  pub fn is_switch_open(&self, switch_name: &'static str) -> Option<bool> {
-    self.switches.is_open_by_name(switch_name)
+  pub fn is_switch_open(&self, switch_name: impl SwitchId) -> Option<bool> {
+    self.switches.is_open_by_name(switch_name.switch_name())
+  }
+
+  /// How long the switch has been closed, zero if it's open. `None` if there is no such switch.
+  pub fn switch_closed_for(&self, switch_name: impl SwitchId) -> Option<Duration> {
+    self.switches.closed_for_by_name(switch_name.switch_name())
+  }
+
+  /// How long the switch has been open, zero if it's closed. `None` if there is no such switch.
+  pub fn switch_open_for(&self, switch_name: impl SwitchId) -> Option<Duration> {
+    self.switches.open_for_by_name(switch_name.switch_name())
   }
 }
 
@@ -64,6 +74,11 @@ impl<'a> ReadonlyGameState<'a> {
   pub fn player_count(&self) -> u8 {
     self.game_state.player_count
   }
+
+  /// Whether the game is paused because the machine dropped to a safe state, e.g. the watchdog expired
+  pub fn is_paused(&self) -> bool {
+    self.game_state.paused
+  }
 }
 
 pub struct ReadonlyStore<'a> {
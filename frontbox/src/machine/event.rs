@@ -81,7 +81,7 @@ impl PlayerAdded {
   }
 }
 
-/// Runs when a player is added
+/// Runs when play passes to another player
 #[derive(Debug)]
 #[allow(unused)]
 pub struct PlayerChanged {
@@ -95,3 +95,142 @@ impl PlayerChanged {
     })
   }
 }
+
+/// Emit when a new ball is put into play. Hardware rules for `GamePhase::BallInPlay` are enabled.
+#[derive(Debug)]
+pub struct BallStarted;
+
+impl BallStarted {
+  pub fn new() -> Box<BallStarted> {
+    Box::new(Self)
+  }
+}
+
+/// Emit when the ball in play drains. Hardware rules for `GamePhase::BetweenBalls` are enabled until `BallStarted`.
+#[derive(Debug)]
+pub struct BallEnded;
+
+impl BallEnded {
+  pub fn new() -> Box<BallEnded> {
+    Box::new(Self)
+  }
+}
+
+/// Emit when the player tilts. Motors are stopped as soon as the machine sees it, and hardware rules for
+/// `GamePhase::Tilted` are enabled until the next `BallStarted`.
+#[derive(Debug)]
+pub struct Tilted;
+
+impl Tilted {
+  pub fn new() -> Box<Tilted> {
+    Box::new(Self)
+  }
+}
+
+/// Runs once a switch has stayed closed for `duration`, for switches watched with `cmds.switch.watch_held`. Runs again
+/// only after the switch opens and is held again.
+#[derive(Debug)]
+#[allow(unused)]
+pub struct SwitchHeld {
+  pub switch: Switch,
+  pub duration: Duration,
+}
+
+impl SwitchHeld {
+  pub fn new(switch: Switch, duration: Duration) -> Box<SwitchHeld> {
+    Box::new(Self { switch, duration })
+  }
+}
+
+/// Runs when a flipper's button closes while flippers are enabled. The hardware has already fired the flipper.
+#[derive(Debug)]
+#[allow(unused)]
+pub struct FlipperActivated {
+  pub flipper: &'static str,
+}
+
+impl FlipperActivated {
+  pub fn new(flipper: &'static str) -> Box<FlipperActivated> {
+    Box::new(Self { flipper })
+  }
+}
+
+/// Runs when a flipper's button opens while flippers are enabled
+#[derive(Debug)]
+#[allow(unused)]
+pub struct FlipperReleased {
+  pub flipper: &'static str,
+}
+
+impl FlipperReleased {
+  pub fn new(flipper: &'static str) -> Box<FlipperReleased> {
+    Box::new(Self { flipper })
+  }
+}
+
+/// Runs when a stepper stops after a move or homing, with its position in steps from home. Steppers which are
+/// stopped with `cmds.stepper.stop` also report their position.
+#[derive(Debug)]
+#[allow(unused)]
+pub struct StepperMoveComplete {
+  pub stepper: &'static str,
+  pub position: i32,
+}
+
+impl StepperMoveComplete {
+  pub fn new(stepper: &'static str, position: i32) -> Box<StepperMoveComplete> {
+    Box::new(Self { stepper, position })
+  }
+
+  /// Whether this is the named stepper, e.g. `e.is(Steppers::DropTarget)` for a stepper enum deriving `StepperId`
+  pub fn is(&self, stepper: impl StepperId) -> bool {
+    self.stepper == stepper.stepper_name()
+  }
+}
+
+/// Runs when the connection to the hardware is lost. The watchdog is no longer fed, so high voltage will be turned off
+/// by the hardware, and nothing sent to the hardware takes effect until `HardwareReconnected`. The machine has already
+/// gone to a safe state: flippers, hardware rules and motors are off and any game is paused.
+#[derive(Debug)]
+pub struct HardwareDisconnected;
+
+impl HardwareDisconnected {
+  pub fn new() -> Box<HardwareDisconnected> {
+    Box::new(Self)
+  }
+}
+
+/// Runs when the hardware watchdog runs out because the machine couldn't refresh it in time. The machine has already
+/// gone to a safe state: flippers, hardware rules and motors are off and any game is paused until
+/// `cmds.game.resume()`.
+#[derive(Debug)]
+pub struct WatchdogExpired;
+
+impl WatchdogExpired {
+  pub fn new() -> Box<WatchdogExpired> {
+    Box::new(Self)
+  }
+}
+
+/// Runs when high voltage is turned off, either because the watchdog expired or the hardware was lost. Coils won't
+/// fire until it's back.
+#[derive(Debug)]
+pub struct HighVoltageLost;
+
+impl HighVoltageLost {
+  pub fn new() -> Box<HighVoltageLost> {
+    Box::new(Self)
+  }
+}
+
+/// Runs once the hardware has been reconnected and reconfigured after being lost. Drivers are back to their definition's
+/// configs and flippers are disabled. A game in progress is still paused, with hardware rules off, until
+/// `cmds.game.resume()`.
+#[derive(Debug)]
+pub struct HardwareReconnected;
+
+impl HardwareReconnected {
+  pub fn new() -> Box<HardwareReconnected> {
+    Box::new(Self)
+  }
+}
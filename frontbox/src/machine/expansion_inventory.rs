@@ -0,0 +1,239 @@
+use std::fmt::Display;
+
+use fast_protocol::ExpansionBreakoutInfo;
+
+use crate::hardware_definition::ExpansionBoardDefinition;
+
+/// An active breakout reported by `IH:`. Breakout 0 is the expansion board itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionBreakout {
+  pub address: u8,
+  pub breakout: u8,
+  pub led_ports: u8,
+  /// Number of LED indexes available across all ports of the breakout
+  pub leds: u16,
+}
+
+impl ExpansionBreakout {
+  pub(crate) fn from_info(info: ExpansionBreakoutInfo) -> Option<Self> {
+    match info {
+      ExpansionBreakoutInfo::Active {
+        expansion_board,
+        breakout,
+        led_ports,
+        leds,
+        ..
+      } => Some(Self {
+        address: expansion_board,
+        breakout,
+        led_ports,
+        leds,
+      }),
+      ExpansionBreakoutInfo::Inactive { .. } => None,
+    }
+  }
+}
+
+/// A difference between the declared expansion boards and what the EXP bus reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionMismatch {
+  /// A declared board or breakout did not respond or reported itself inactive
+  MissingBreakout { address: u8, breakout: u8 },
+  /// An active breakout, on any board address, which no `ExpansionBoardDefinition` covers
+  UndeclaredBreakout { address: u8, breakout: u8 },
+  /// A declared LED port which the breakout doesn't have
+  LedPortOutOfRange {
+    address: u8,
+    breakout: u8,
+    port: u8,
+    led_ports: u8,
+  },
+  /// A declared LED port which runs past the LEDs the breakout supports
+  TooManyLeds {
+    address: u8,
+    breakout: u8,
+    port: u8,
+    end: u16,
+    leds: u16,
+  },
+}
+
+impl Display for ExpansionMismatch {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ExpansionMismatch::MissingBreakout { address, breakout } => {
+        write!(
+          f,
+          "{} is declared but was not found",
+          location(*address, *breakout)
+        )
+      }
+      ExpansionMismatch::UndeclaredBreakout { address, breakout } => {
+        write!(
+          f,
+          "{} was found but is not declared",
+          location(*address, *breakout)
+        )
+      }
+      ExpansionMismatch::LedPortOutOfRange {
+        address,
+        breakout,
+        port,
+        led_ports,
+      } => write!(
+        f,
+        "LED port {} is declared but {} only has {} LED ports",
+        port,
+        location(*address, *breakout),
+        led_ports
+      ),
+      ExpansionMismatch::TooManyLeds {
+        address,
+        breakout,
+        port,
+        end,
+        leds,
+      } => write!(
+        f,
+        "LED port {} uses LEDs up to {} but {} only supports {} LEDs",
+        port,
+        end,
+        location(*address, *breakout),
+        leds
+      ),
+    }
+  }
+}
+
+fn location(address: u8, breakout: u8) -> String {
+  if breakout == 0 {
+    format!("EXP board 0x{:X}", address)
+  } else {
+    format!("EXP board 0x{:X} breakout {}", address, breakout)
+  }
+}
+
+/// Result of comparing the breakouts reported on the EXP bus with the declared expansion boards
+#[derive(Debug, Clone)]
+pub struct ExpansionInventory {
+  pub breakouts: Vec<ExpansionBreakout>,
+  pub mismatches: Vec<ExpansionMismatch>,
+}
+
+impl ExpansionInventory {
+  pub fn compare(declared: &[ExpansionBoardDefinition], breakouts: Vec<ExpansionBreakout>) -> Self {
+    let mut mismatches = Vec::new();
+
+    for board in declared {
+      let breakout = board.breakout.unwrap_or(0);
+      let Some(found) = breakouts
+        .iter()
+        .find(|b| b.address == board.address && b.breakout == breakout)
+      else {
+        mismatches.push(ExpansionMismatch::MissingBreakout {
+          address: board.address,
+          breakout,
+        });
+        continue;
+      };
+
+      for led_port in &board.led_ports {
+        let end = led_port.start as u16 + led_port.leds.len() as u16;
+        if led_port.port >= found.led_ports {
+          mismatches.push(ExpansionMismatch::LedPortOutOfRange {
+            address: board.address,
+            breakout,
+            port: led_port.port,
+            led_ports: found.led_ports,
+          });
+        } else if end > found.leds {
+          mismatches.push(ExpansionMismatch::TooManyLeds {
+            address: board.address,
+            breakout,
+            port: led_port.port,
+            end,
+            leds: found.leds,
+          });
+        }
+      }
+    }
+
+    for found in &breakouts {
+      let is_declared = declared
+        .iter()
+        .any(|b| b.address == found.address && b.breakout.unwrap_or(0) == found.breakout);
+      if !is_declared {
+        mismatches.push(ExpansionMismatch::UndeclaredBreakout {
+          address: found.address,
+          breakout: found.breakout,
+        });
+      }
+    }
+
+    Self {
+      breakouts,
+      mismatches,
+    }
+  }
+
+  /// Whether the EXP bus matches the definitions
+  pub fn is_match(&self) -> bool {
+    self.mismatches.is_empty()
+  }
+}
+
+/// One line per mismatch, suitable for showing to an operator
+impl Display for ExpansionInventory {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.is_match() {
+      return write!(
+        f,
+        "EXP bus matches the {} declared boards and breakouts",
+        self.breakouts.len()
+      );
+    }
+
+    write!(f, "EXP bus does not match the declared boards:")?;
+    for mismatch in &self.mismatches {
+      write!(f, "\n  {}", mismatch)?;
+    }
+    Ok(())
+  }
+}
+
+/// Rust source for an `ExpansionBoardDefinition` list covering the given breakouts, with every LED port declared
+/// and its LEDs left for the machine author to name
+pub(crate) fn starter_definitions(breakouts: &[ExpansionBreakout]) -> String {
+  let known = ExpansionBoardDefinition::known_addresses();
+  let mut source = String::from("vec![\n");
+
+  for found in breakouts {
+    let constructor = match known.iter().find(|(address, _)| *address == found.address) {
+      Some((_, constructor)) if found.breakout == 0 => constructor.clone(),
+      _ => format!(
+        "custom(\"{:X}\", {})",
+        found.address,
+        if found.breakout == 0 {
+          "None".to_string()
+        } else {
+          format!("Some({})", found.breakout)
+        }
+      ),
+    };
+    source.push_str(&format!("  ExpansionBoardDefinition::{}", constructor));
+
+    // split the LEDs evenly between the ports as a starting point
+    let leds_per_port = found.leds / (found.led_ports.max(1) as u16);
+    for port in 0..found.led_ports {
+      source.push_str(&format!(
+        "\n    .with_led_port(LedPortDefinition {{ port: {}, start: {}, leds: vec![], ..Default::default() }})",
+        port,
+        port as u16 * leds_per_port
+      ));
+    }
+    source.push_str(",\n");
+  }
+
+  source.push(']');
+  source
+}
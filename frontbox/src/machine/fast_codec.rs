@@ -3,8 +3,16 @@ use tokio_util::codec::Decoder;
 
 use fast_protocol::RawResponse;
 
+/// Longest line kept while waiting for its `\r`. Anything longer is noise rather than a FAST message.
+const MAX_LINE_LENGTH: usize = 1024;
+
 pub struct FastRawCodec;
 
+/// A line which isn't a FAST message, e.g. noise on the serial line. It's handed on as an item rather than an error,
+/// since an error ends the stream and would look like a lost connection.
+#[derive(Debug)]
+pub struct MalformedLine(pub String);
+
 /// Decode incoming serial data into RawResponse structs. This is a low level parsing that just splits the raw string into
 /// command, optional address, and payload.
 impl FastRawCodec {
@@ -14,50 +22,53 @@ impl FastRawCodec {
 }
 
 impl Decoder for FastRawCodec {
-  type Item = RawResponse;
+  type Item = Result<RawResponse, MalformedLine>;
   type Error = std::io::Error;
 
   fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-    if src.len() > 1024 {
-      log::warn!(
-        "Codec buffer has {} bytes without \\r terminator!",
-        src.len()
-      );
-    }
+    loop {
+      // Find the index of the \r byte
+      let Some(i) = src.iter().position(|&b| b == b'\r') else {
+        if src.len() > MAX_LINE_LENGTH {
+          let data = src.split_to(src.len());
+          return Ok(Some(Err(MalformedLine(
+            String::from_utf8_lossy(&data).to_string(),
+          ))));
+        }
+        // Not enough data for a full line yet
+        return Ok(None);
+      };
 
-    // Find the index of the \r byte
-    if let Some(i) = src.iter().position(|&b| b == b'\r') {
       // Remove the data up to the \r from the buffer
       let data = src.split_to(i);
       // Remove the \r itself so it's not in the next message
       src.advance(1);
 
-      // Parse to RawResponse
-      let s = String::from_utf8_lossy(&data).to_string();
+      // blank lines, e.g. from clearing the serial buffer
+      if data.is_empty() {
+        continue;
+      }
+
+      let Ok(s) = std::str::from_utf8(&data) else {
+        return Ok(Some(Err(MalformedLine(
+          String::from_utf8_lossy(&data).to_string(),
+        ))));
+      };
 
       // split on first :
-      let mut parts = s.splitn(2, ':');
-      let cmd = parts.next().unwrap_or("").to_string();
-      let payload = parts.next().unwrap_or("").to_string();
-
-      if cmd.contains("@") {
-        let mut cmd_parts = cmd.splitn(2, '@');
-        let cmd = cmd_parts.next().unwrap_or("").to_string();
-        let address = cmd_parts.next().map(|s| s.to_string());
-        return Ok(Some(RawResponse {
-          prefix: cmd,
-          address,
-          payload,
-        }));
-      } else {
-        return Ok(Some(RawResponse {
-          prefix: cmd,
-          address: None,
-          payload,
-        }));
-      }
+      let Some((cmd, payload)) = s.split_once(':') else {
+        return Ok(Some(Err(MalformedLine(s.to_string()))));
+      };
+
+      let (prefix, address) = match cmd.split_once('@') {
+        Some((prefix, address)) => (prefix, Some(address.to_string())),
+        None => (cmd, None),
+      };
+      return Ok(Some(Ok(RawResponse {
+        prefix: prefix.to_string(),
+        address,
+        payload: payload.to_string(),
+      })));
     }
-    // Not enough data for a full line yet
-    Ok(None)
   }
 }
@@ -0,0 +1,113 @@
+use fast_protocol::{Capability, FirmwareVersion, IdResponse, firmware_support};
+
+use crate::machine::boot_error::{BootError, HardwareTarget};
+
+/// Processor and firmware as reported by `ID:`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardFirmware {
+  pub processor: String,
+  pub product_number: String,
+  pub version: String,
+}
+
+impl BoardFirmware {
+  pub(crate) fn from_id(response: IdResponse) -> Option<Self> {
+    match response {
+      IdResponse::Report {
+        processor,
+        product_number,
+        firmware_version,
+      } => Some(Self {
+        processor,
+        product_number,
+        version: firmware_version,
+      }),
+      IdResponse::Failed => None,
+    }
+  }
+
+  /// Check the firmware against the compatibility table for the capabilities the machine uses, returning the
+  /// capabilities it lacks as errors. Firmware which is merely untested is only logged.
+  pub(crate) fn problems(
+    &self,
+    target: HardwareTarget,
+    capabilities: &[Capability],
+  ) -> Vec<BootError> {
+    let Some(support) = firmware_support(&self.processor, &self.product_number) else {
+      log::warn!(
+        "🥾 No firmware compatibility information for {} {} {}",
+        target,
+        self.processor,
+        self.product_number
+      );
+      return Vec::new();
+    };
+    let Some(version) = FirmwareVersion::parse(&self.version) else {
+      log::warn!(
+        "🥾 {} reported an unrecognized firmware version {}",
+        target,
+        self.version
+      );
+      return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    if version < support.minimum {
+      problems.push((None, support.minimum));
+    } else {
+      for capability in capabilities {
+        if !support.supports(version, *capability) {
+          problems.push((Some(*capability), support.required_for(*capability)));
+        }
+      }
+    }
+
+    if version < support.known_good {
+      log::warn!(
+        "🥾 {} firmware {} has not been tested with frontbox, {} is known to work",
+        target,
+        version,
+        support.known_good
+      );
+    }
+
+    problems
+      .into_iter()
+      .map(|(capability, required)| BootError::IncompatibleFirmware {
+        target: target.clone(),
+        firmware: self.clone(),
+        required,
+        capability,
+      })
+      .collect()
+  }
+}
+
+impl BoardFirmware {
+  /// The capability as an error if the firmware is known not to support it. Unlike `problems`, nothing is logged, so
+  /// this suits checking capabilities one at a time after boot.
+  pub(crate) fn unsupported(
+    &self,
+    target: HardwareTarget,
+    capability: Capability,
+  ) -> Option<BootError> {
+    let support = firmware_support(&self.processor, &self.product_number)?;
+    let version = FirmwareVersion::parse(&self.version)?;
+    if support.supports(version, capability) {
+      return None;
+    }
+    Some(BootError::IncompatibleFirmware {
+      target,
+      firmware: self.clone(),
+      required: support.required_for(capability),
+      capability: Some(capability),
+    })
+  }
+}
+
+/// Add a capability to the list unless it's already there
+pub(crate) fn require(capabilities: &mut Vec<Capability>, capability: Capability) {
+  if !capabilities.contains(&capability) {
+    capabilities.push(capability);
+  }
+}
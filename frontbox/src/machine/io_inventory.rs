@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use crate::hardware_definition::IoBoardDefinition;
+
+/// An IO board found on the IO NET loop by node enumeration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoNode {
+  /// Position on the loop, starting at 0
+  pub node: u8,
+  pub name: String,
+  pub firmware_version: String,
+  pub switch_count: u32,
+  pub driver_count: u32,
+}
+
+/// A difference between the boards defined in `IoNetwork` and the boards on the loop
+#[derive(Debug, Clone)]
+pub enum IoNodeMismatch {
+  /// A defined board was not found on the loop
+  Missing {
+    node: u8,
+    expected: IoBoardDefinition,
+  },
+  /// The loop has more boards than were defined
+  Unexpected { found: IoNode },
+  /// The board at this position has a different number of switches or drivers than defined, which usually means
+  /// boards are connected in a different order than they were added to `IoNetworkBuilder`
+  Different {
+    expected: IoBoardDefinition,
+    found: IoNode,
+  },
+}
+
+impl Display for IoNodeMismatch {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IoNodeMismatch::Missing { node, expected } => write!(
+        f,
+        "Node {}: expected {} ({} switches, {} drivers), found nothing",
+        node, expected.description, expected.switch_count, expected.driver_count
+      ),
+      IoNodeMismatch::Unexpected { found } => write!(
+        f,
+        "Node {}: found {} ({} switches, {} drivers) which is not defined",
+        found.node, found.name, found.switch_count, found.driver_count
+      ),
+      IoNodeMismatch::Different { expected, found } => write!(
+        f,
+        "Node {}: expected {} ({} switches, {} drivers), found {} ({} switches, {} drivers)",
+        found.node,
+        expected.description,
+        expected.switch_count,
+        expected.driver_count,
+        found.name,
+        found.switch_count,
+        found.driver_count
+      ),
+    }
+  }
+}
+
+/// Result of comparing the IO NET loop with the boards defined in `IoNetwork`. Boards are matched by position, so a
+/// board out of order shifts the switch and driver ids of every board after it.
+#[derive(Debug, Clone)]
+pub struct IoInventory {
+  pub nodes: Vec<IoNode>,
+  pub mismatches: Vec<IoNodeMismatch>,
+}
+
+impl IoInventory {
+  pub fn compare(expected: &[IoBoardDefinition], nodes: Vec<IoNode>) -> Self {
+    let mut mismatches = Vec::new();
+
+    for (i, board) in expected.iter().enumerate() {
+      match nodes.get(i) {
+        None => mismatches.push(IoNodeMismatch::Missing {
+          node: i as u8,
+          expected: board.clone(),
+        }),
+        Some(node)
+          if node.switch_count != board.switch_count || node.driver_count != board.driver_count =>
+        {
+          mismatches.push(IoNodeMismatch::Different {
+            expected: board.clone(),
+            found: node.clone(),
+          })
+        }
+        Some(_) => {}
+      }
+    }
+
+    for node in nodes.iter().skip(expected.len()) {
+      mismatches.push(IoNodeMismatch::Unexpected {
+        found: node.clone(),
+      });
+    }
+
+    Self { nodes, mismatches }
+  }
+
+  /// Whether the loop matches the definition
+  pub fn is_match(&self) -> bool {
+    self.mismatches.is_empty()
+  }
+}
+
+/// One line per mismatch, suitable for showing to an operator
+impl Display for IoInventory {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.is_match() {
+      return write!(
+        f,
+        "IO NET loop matches the {} defined boards",
+        self.nodes.len()
+      );
+    }
+
+    write!(
+      f,
+      "IO NET loop does not match the defined boards, switch and driver ids may be wrong:"
+    )?;
+    for mismatch in &self.mismatches {
+      write!(f, "\n  {}", mismatch)?;
+    }
+    Ok(())
+  }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::future::Future;
 use std::time::Duration;
 
 use crate::machine::event::FrontboxEvent;
@@ -18,11 +19,25 @@ use crossterm::{
   terminal::{disable_raw_mode, enable_raw_mode},
 };
 use fast_protocol::*;
+use futures_util::FutureExt;
 use tokio::sync::mpsc;
 
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct GameState {
   pub active_player: u8,
   pub player_count: u8,
+  /// Set when the machine drops to a safe state mid-game, e.g. the watchdog expired. Hardware rules stay off until
+  /// `cmds.game.resume()`.
+  pub paused: bool,
+}
+
+/// What the builder collected on top of the boards themselves, for `Machine::new`
+pub(crate) struct HardwareSetup {
+  pub driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  pub led_groups: HashMap<&'static str, Vec<&'static str>>,
+  pub hardware_rules: Vec<HardwareRule>,
+  pub flippers: Vec<FlipperDefinition>,
 }
 
 pub struct Machine {
@@ -35,8 +50,23 @@ pub struct Machine {
   io_boards: Vec<IoBoardDefinition>,
   expansion_boards: Vec<ExpansionBoardDefinition>,
   driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  hardware_rules: Vec<HardwareRule>,
+  game_phase: GamePhase,
+  flippers: Vec<FlipperDefinition>,
+  flippers_enabled: bool,
+  platform: FastPlatform,
+  /// Set when the IO NET loop doesn't match the definition and the watchdog must stay off
+  watchdog_blocked: bool,
+  hardware_lost: bool,
   system_tick: Duration,
   led_renderer: LedRenderer,
+  /// Steppers which are moving, polled every system tick until they stop
+  stepper_moves: HashMap<&'static str, StepperMove>,
+  next_stepper_move: u64,
+  /// Motors which run until a switch closes
+  running_motors: HashMap<&'static str, RunningMotor>,
+  /// Switches which emit `SwitchHeld` after staying closed
+  held_switches: Vec<HeldSwitch>,
   global_store: Store,
   global_systems: Vec<SystemContainer>,
   switches: SwitchContext,
@@ -60,14 +90,32 @@ impl Machine {
     config: MachineConfig,
     io_boards: Vec<IoBoardDefinition>,
     expansion_boards: Vec<ExpansionBoardDefinition>,
-    driver_groups: HashMap<&'static str, Vec<&'static str>>,
+    setup: HardwareSetup,
+    platform: FastPlatform,
+    watchdog_blocked: bool,
   ) -> Self {
+    let HardwareSetup {
+      driver_groups,
+      led_groups,
+      hardware_rules,
+      flippers,
+    } = setup;
     let (command_sender, command_receiver) = mpsc::unbounded_channel();
     let (system_sender, system_receiver) = mpsc::unbounded_channel();
     let (store_sender, store_receiver) = mpsc::unbounded_channel();
     let watchdog_interval = config
       .get_value_as_u64(default_config::WATCHDOG_TICK)
       .unwrap_or(1000);
+    let watchdog_timeout = config
+      .get_value_as_u64(default_config::WATCHDOG_TIMEOUT)
+      .unwrap_or(1250);
+    if watchdog_timeout <= watchdog_interval {
+      log::warn!(
+        "Watchdog timeout {}ms is not longer than the {}ms tick, high voltage will drop between refreshes",
+        watchdog_timeout,
+        watchdog_interval
+      );
+    }
 
     let system_tick = Duration::from_millis(
       config
@@ -93,13 +141,24 @@ impl Machine {
       store_sender,
       store_receiver,
       config,
-      led_renderer: LedRenderer::new(&expansion_boards),
+      led_renderer: LedRenderer::new(&expansion_boards, led_groups),
+      stepper_moves: HashMap::new(),
+      next_stepper_move: 0,
+      running_motors: HashMap::new(),
+      held_switches: Vec::new(),
       io_boards,
       expansion_boards,
       system_tick,
       global_store: Store::new(),
       global_systems: Vec::new(),
       driver_groups,
+      hardware_rules,
+      game_phase: GamePhase::Attract,
+      flippers,
+      flippers_enabled: false,
+      platform,
+      watchdog_blocked,
+      hardware_lost: false,
       states: States::new(),
     }
   }
@@ -108,6 +167,7 @@ impl Machine {
     // Note: FAST system seems to expect the watchdog to always be running (e.g. otherwise the low voltage drivers don't work)
     // Once the smart power filter board firmware is updated, there will likely be a separate command to enable/disable high voltage
     self.enable_watchdog().await;
+    self.apply_hardware_rules();
 
     // initialize systems
     {
@@ -152,14 +212,24 @@ impl Machine {
       let _ = tx.send(MachineCommand::Shutdown);
     });
 
+    // retry the connection periodically if the hardware is lost
+    let mut reconnect_interval = tokio::time::interval(RECONNECT_INTERVAL);
+    reconnect_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
     log::info!("⟳ Machine run loop started.");
 
     loop {
       tokio::select! {
-        Some(event) = self.io_port.read_event() => {
+        event = self.io_port.read_event(), if self.io_port.is_connected() => {
           // Add incoming hardware events to the command queue
           // this ensures they are processed in order with any commands emitted by systems in response to those events
-          self.command_sender.send(MachineCommand::HardwareEvent(event)).ok();
+          if let Some(event) = event {
+            self.command_sender.send(MachineCommand::HardwareEvent(event)).ok();
+          }
+        }
+
+        _ = reconnect_interval.tick(), if self.hardware_lost => {
+          self.reconnect().await;
         }
 
         Some(command) = self.system_receiver.recv() => {
@@ -190,6 +260,8 @@ impl Machine {
         Some(command) = self.command_receiver.recv() => {
           if matches!(command, MachineCommand::SystemTick)
             || matches!(command, MachineCommand::WatchdogTick)
+            || matches!(command, MachineCommand::WatchdogStatus(..))
+            || matches!(command, MachineCommand::StepperStatus(..))
           {
             log::trace!("Executing machine command: {:?}", command);
           } else {
@@ -204,6 +276,10 @@ impl Machine {
           self.run_machine_command(command).await;
         }
       }
+
+      if !self.hardware_lost && !self.is_hardware_connected() {
+        self.on_hardware_lost();
+      }
     }
 
     if self.keyboard_switch_map.len() > 0 {
@@ -218,17 +294,28 @@ impl Machine {
 
     // Reset expansion boards (LEDs servos, etc.) to an off/default state
     self.reset_expansion_network().await;
+    for address in self.servo_board_addresses() {
+      self.park_servos(address).await;
+    }
   }
 
   async fn run_machine_command(&mut self, command: MachineCommand) {
     match command {
       MachineCommand::StartGame => self.start_game().await,
       MachineCommand::EndGame => self.end_game().await,
+      MachineCommand::ResumeGame => self.resume_game(),
       MachineCommand::AddPlayer => self.add_player(),
       MachineCommand::AdvancePlayer => self.advance_player().await,
       MachineCommand::ConfigureDriver(driver_name, mode) => {
         self.configure_driver(driver_name, mode).await
       }
+      MachineCommand::ConfigureSwitch(switch_name, f) => self.configure_switch(switch_name, f),
+      MachineCommand::WatchSwitchHeld(switch_name, duration) => {
+        self.watch_held_switch(switch_name, duration)
+      }
+      MachineCommand::UnwatchSwitchHeld(switch_name, duration) => self
+        .held_switches
+        .retain(|held| !(held.switch.name == switch_name && held.duration == duration)),
       MachineCommand::TriggerDriver(driver_name, mode, delay) => {
         self.trigger_driver(driver_name, mode, delay).await
       }
@@ -244,31 +331,161 @@ impl Machine {
           system.on_tick(tick_duration, ctx, cmds);
         });
         self.render_leds().await;
+        self.poll_steppers();
+        self.check_held_switches();
       }
       MachineCommand::HardwareEvent(event) => match event {
         EventResponse::Switch { switch_id, state } => self.run_switch_event(switch_id, state),
       },
+      MachineCommand::SwitchReport(switches) => self.switches.update_switch_states(switches),
       MachineCommand::Key(event) => self.on_key_press(event),
-      MachineCommand::WatchdogTick => {
-        let _ = self
-          .io_port
-          .request(
-            &WatchdogCommand::set(Duration::from_millis(1250)),
-            Duration::from_secs(1),
-          )
-          .await;
+      MachineCommand::WatchdogTick => self.refresh_watchdog(),
+      MachineCommand::WatchdogStatus(remaining, refreshed) => {
+        self.on_watchdog_status(remaining, refreshed)
       }
       MachineCommand::ResetExpansionNetwork => {
-        self.reset_expansion_network().await;
+        tokio::spawn(self.reset_expansion_network());
+      }
+      MachineCommand::MoveServo(servo_name, position, time) => {
+        self.move_servo(servo_name, Some(position), time)
+      }
+      MachineCommand::HomeServo(servo_name) => self.move_servo(servo_name, None, Duration::ZERO),
+      MachineCommand::ParkServos(address) => {
+        tokio::spawn(self.park_servos(address));
+      }
+      MachineCommand::RehomeSteppers(address) => {
+        tokio::spawn(self.rehome_steppers(address));
+      }
+      MachineCommand::HomeStepper(stepper_name) => self.home_stepper(stepper_name),
+      MachineCommand::MoveStepperTo(stepper_name, position_name) => {
+        self.move_stepper_to(stepper_name, position_name)
+      }
+      MachineCommand::MoveStepperBy(stepper_name, steps) => {
+        self.move_stepper_by(stepper_name, steps, false)
+      }
+      MachineCommand::RotateStepper(stepper_name, revolutions) => {
+        if let Some((_, stepper)) = find_stepper(&self.expansion_boards, stepper_name) {
+          let steps = stepper.revolutions(revolutions);
+          self.move_stepper_by(stepper_name, steps, false);
+        } else {
+          log::error!("Attempted to rotate unknown stepper: {}", stepper_name);
+        }
+      }
+      MachineCommand::StopStepper(stepper_name) => self.stop_stepper(stepper_name),
+      MachineCommand::RunMotor(motor_name, direction, speed, time) => {
+        self.run_motor(motor_name, direction, speed, time, None)
+      }
+      MachineCommand::RunMotorTo(motor_name, position_name, direction, speed) => {
+        match find_motor(&self.expansion_boards, motor_name) {
+          Some((_, motor)) => match motor.position_switch(position_name) {
+            Some(switch) => {
+              self.run_motor(motor_name, direction, speed, Duration::ZERO, Some(switch))
+            }
+            None => log::error!(
+              "Motor {} has no position named {}",
+              motor_name,
+              position_name
+            ),
+          },
+          None => log::error!("Attempted to run unknown motor: {}", motor_name),
+        }
+      }
+      MachineCommand::StopMotor(motor_name) => self.stop_motor(motor_name),
+      MachineCommand::StopMotors => {
+        self.running_motors.clear();
+        tokio::spawn(self.stop_motors());
+      }
+      MachineCommand::EnableFlippers => self.set_flippers_enabled(true),
+      MachineCommand::DisableFlippers => self.set_flippers_enabled(false),
+      MachineCommand::StepperStatus(stepper_name, move_id, status) => {
+        self.on_stepper_status(stepper_name, move_id, status)
       }
       MachineCommand::Shutdown => {}
-      MachineCommand::EmitEvent(e) => self.emit(e),
+      MachineCommand::EmitEvent(e) => {
+        let event = e.as_ref().as_any();
+        if event.is::<Tilted>() {
+          log::info!("Tilted, stopping motors");
+          self.running_motors.clear();
+          tokio::spawn(self.stop_motors());
+          if self.game_state.is_some() {
+            self.set_game_phase(GamePhase::Tilted);
+          }
+        } else if event.is::<BallEnded>() && self.game_phase != GamePhase::Tilted {
+          self.set_game_phase(GamePhase::BetweenBalls);
+        } else if event.is::<BallStarted>() && self.game_state.is_some() {
+          self.set_game_phase(GamePhase::BallInPlay);
+        }
+        self.emit(e)
+      }
       MachineCommand::StateTransition(f) => f(&mut self.states),
     }
   }
 
   // ---
 
+  fn is_hardware_connected(&self) -> bool {
+    self.io_port.is_connected() && self.exp_port.is_connected()
+  }
+
+  /// Stop driving the hardware once either port is lost and go to the safe state, so nothing is armed when it comes
+  /// back. Systems are told so they can e.g. pause ball search.
+  fn on_hardware_lost(&mut self) {
+    log::error!("Connection to hardware lost, reconnecting...");
+    self.hardware_lost = true;
+    self.watchdog.disable();
+    self.enter_safe_state();
+    self.led_renderer.reset();
+    self.emit(HardwareDisconnected::new());
+    self.emit(HighVoltageLost::new());
+  }
+
+  /// Reopen lost ports and repeat the boot configuration. Flippers stay off until systems enable them again, and during
+  /// a game so do hardware rules, until the game is resumed.
+  async fn reconnect(&mut self) {
+    for port in [&mut self.io_port, &mut self.exp_port] {
+      if !port.is_connected()
+        && let Err(e) = port.reconnect().await
+      {
+        log::warn!("Reconnect failed: {}", e);
+        return;
+      }
+    }
+
+    if let Err(e) = self.reconfigure().await {
+      log::warn!("Reconnect failed: {}", e);
+      return;
+    }
+
+    // the connection may have dropped again part way through
+    if !self.is_hardware_connected() {
+      return;
+    }
+
+    log::info!("Hardware reconnected");
+    self.hardware_lost = false;
+    self.enable_watchdog().await;
+    if self.game_state.is_none() {
+      self.apply_hardware_rules();
+    }
+    self.report_switches(vec![MachineCommand::EmitEvent(HardwareReconnected::new())]);
+  }
+
+  async fn reconfigure(&mut self) -> Result<(), BootError> {
+    MachineBuilder::boot_mainboard(&mut self.io_port).await?;
+    MachineBuilder::configure_hardware(&mut self.io_port, self.platform.clone()).await?;
+
+    for (switch, config) in self.switches.configs() {
+      MachineBuilder::configure_switch(&mut self.io_port, switch.id, switch.name, config).await?;
+    }
+
+    // only the definition's configs, flipper and hardware rule configs are left off until systems re-enable them
+    let drivers: Vec<_> = self.driver_lookup.values().cloned().collect();
+    MachineBuilder::configure_drivers(&mut self.io_port, &drivers).await?;
+
+    self.reset_expansion_network().await;
+    MachineBuilder::configure_led_ports(&mut self.exp_port, &self.expansion_boards).await
+  }
+
   fn emit(&mut self, event: Box<dyn FrontboxEvent>) {
     self.dispatch_to_current_systems(|system, ctx, cmds| {
       system.on_event(event.as_ref(), ctx, cmds);
@@ -280,9 +497,19 @@ impl Machine {
       self.switches.update_switch_state(switch_id, state);
 
       if matches!(state, SwitchState::Closed) {
+        self.on_stepper_home_switch(switch.name);
+        self.on_motor_switch(switch.name);
+        let name = switch.name;
         self.emit(SwitchClosed::new(switch));
+        if let Some(flipper) = self.enabled_flipper_for(name) {
+          self.emit(FlipperActivated::new(flipper));
+        }
       } else {
+        let name = switch.name;
         self.emit(SwitchOpened::new(switch));
+        if let Some(flipper) = self.enabled_flipper_for(name) {
+          self.emit(FlipperReleased::new(flipper));
+        }
       }
     } else {
       // Repor as native board/switch id since this is the easiest way to figure out current switch wiring
@@ -355,15 +582,35 @@ impl Machine {
     self.game_state = Some(GameState {
       active_player: 0,
       player_count: 1,
+      paused: false,
     });
-    self.report_switches().await; // sync initial switch states
-    self.emit(GameStarted::new());
+    self.set_game_phase(GamePhase::BallInPlay);
+    // sync initial switch states before systems are told about the game
+    self.report_switches(vec![MachineCommand::EmitEvent(GameStarted::new())]);
+  }
+
+  fn resume_game(&mut self) {
+    let Some(game_state) = &mut self.game_state else {
+      log::warn!("Attempted to resume but no game in progress");
+      return;
+    };
+    if !game_state.paused {
+      return;
+    }
+
+    log::info!("Resuming game");
+    game_state.paused = false;
+    if self.watchdog.is_expired() {
+      log::warn!("Watchdog is still expired, hardware rules stay off until it is refreshed");
+    }
+    self.apply_hardware_rules();
   }
 
   async fn end_game(&mut self) {
     log::info!("Ending game");
     self.emit(GameEnded::new());
     self.game_state = None;
+    self.set_game_phase(GamePhase::Attract);
   }
 
   fn add_player(&mut self) {
@@ -390,18 +637,25 @@ impl Machine {
       if game_state.active_player >= game_state.player_count {
         game_state.active_player = 0;
       }
+      let active_player = game_state.active_player;
+      self.emit(PlayerChanged::new(active_player));
     }
 
-    self.reset_expansion_network().await;
-    self.report_switches().await;
+    tokio::spawn(self.reset_expansion_network());
+    self.report_switches(vec![]);
   }
 
   async fn enable_watchdog(&mut self) {
+    if self.watchdog_blocked {
+      log::error!("Watchdog not enabled, the IO NET loop does not match the defined boards");
+      return;
+    }
+
     self.watchdog.enable();
     let _ = self
       .io_port
       .request(
-        &WatchdogCommand::set(Duration::from_millis(1250)),
+        &WatchdogCommand::set(self.watchdog_timeout()),
         Duration::from_secs(1),
       )
       .await;
@@ -410,6 +664,78 @@ impl Machine {
     tokio::time::sleep(Duration::from_millis(300)).await;
   }
 
+  fn watchdog_timeout(&self) -> Duration {
+    Duration::from_millis(
+      self
+        .config
+        .get_value_as_u64(default_config::WATCHDOG_TIMEOUT)
+        .unwrap_or(1250),
+    )
+  }
+
+  /// Ask the hardware how long the watchdog had left, then refresh it. The result comes back as `WatchdogStatus`.
+  fn refresh_watchdog(&self) {
+    let remaining = self
+      .io_port
+      .start_request(WatchdogCommand::remaining(), Duration::from_secs(1));
+    let refresh = self.io_port.start_request(
+      WatchdogCommand::set(self.watchdog_timeout()),
+      Duration::from_secs(1),
+    );
+    let sender = self.command_sender.clone();
+
+    tokio::spawn(async move {
+      let remaining = remaining.await.ok();
+      let refreshed = matches!(refresh.await, Ok(WatchdogResponse::Processed));
+      sender
+        .send(MachineCommand::WatchdogStatus(remaining, refreshed))
+        .ok();
+    });
+  }
+
+  fn on_watchdog_status(&mut self, remaining: Option<WatchdogResponse>, refreshed: bool) {
+    // a refresh still in flight when the watchdog was turned off
+    if !self.watchdog.is_enabled() {
+      return;
+    }
+
+    let expired = if remaining == Some(WatchdogResponse::WatchdogExpired) {
+      self.watchdog.expire()
+    } else if !refreshed {
+      log::warn!(
+        "Watchdog refresh missed ({} in a row)",
+        self.watchdog.missed_refreshes() + 1
+      );
+      self.watchdog.missed(self.watchdog_timeout())
+    } else {
+      false
+    };
+
+    if expired {
+      log::error!("Watchdog expired, high voltage is off");
+      self.enter_safe_state();
+      self.emit(WatchdogExpired::new());
+      self.emit(HighVoltageLost::new());
+    }
+
+    if refreshed && self.watchdog.refreshed() {
+      log::info!("Watchdog running again, high voltage is back on");
+      self.apply_hardware_rules();
+    }
+  }
+
+  /// Turn off everything which moves on its own: flippers, hardware rules and motors. A game in progress is paused
+  /// until `cmds.game.resume()`.
+  fn enter_safe_state(&mut self) {
+    self.set_flippers_enabled(false);
+    self.running_motors.clear();
+    tokio::spawn(self.stop_motors());
+    if let Some(game_state) = &mut self.game_state {
+      game_state.paused = true;
+    }
+    self.apply_hardware_rules();
+  }
+
   #[allow(unused)]
   async fn disable_watchdog(&mut self) {
     self.watchdog.disable();
@@ -422,50 +748,278 @@ impl Machine {
   }
 
   async fn configure_driver(&mut self, driver: &'static str, mode: Box<dyn DriverMode>) {
-    match self.driver_lookup.get(driver) {
-      Some(driver) => {
-        let config = mode.to_config(&self.switches);
+    if !self.driver_lookup.contains_key(driver) {
+      log::error!("Attempted to configure unknown driver: {}", driver);
+      return;
+    }
 
-        log::info!("Configuring driver {}", driver.name);
-        match self
-          .io_port
-          .request(
-            &ConfigureDriverCommand::new(&driver.id, &config),
-            Duration::from_secs(2),
-          )
-          .await
-        {
-          Ok(ProcessedResponse::Processed) => {
-            log::debug!("Driver {} configured successfully", driver.name);
-          }
-          Ok(ProcessedResponse::Failed) => {
-            log::error!("Driver {} configuration failed", driver.name);
-          }
-          Err(e) => {
-            log::error!("Error configuring driver {}: {}", driver.name, e);
-          }
+    if let Some(switch) = mode
+      .switches()
+      .into_iter()
+      .find(|switch| self.switches.switch_by_name(switch).is_none())
+    {
+      log::error!(
+        "Attempted to configure driver {} with unknown switch: {}",
+        driver,
+        switch
+      );
+      return;
+    }
+
+    let config = mode.to_config(&self.switches);
+    self.write_driver_config(driver, config);
+  }
+
+  /// Change a switch's configuration on the hardware and in the switch context, so it's reapplied after a reconnect
+  fn configure_switch(
+    &mut self,
+    switch_name: &'static str,
+    f: Box<dyn FnOnce(&mut SwitchConfig) + Send>,
+  ) {
+    let Some(switch) = self.switches.switch_by_name(switch_name).cloned() else {
+      log::error!("Attempted to configure unknown switch: {}", switch_name);
+      return;
+    };
+    let hardware_switches: usize = self
+      .io_boards
+      .iter()
+      .map(|board| board.switch_count as usize)
+      .sum();
+    if switch.id >= hardware_switches {
+      log::error!("Attempted to configure virtual switch: {}", switch_name);
+      return;
+    }
+
+    let previous = self.switches.config(switch.id);
+    let mut config = previous.clone();
+    f(&mut config);
+    if config == previous {
+      return;
+    }
+
+    log::info!("Configuring switch {} with {:?}", switch_name, config);
+    let response = self.io_port.start_request(
+      ConfigureSwitchCommand::new(
+        switch.id,
+        config.reporting_mode(),
+        config.debounce_close,
+        config.debounce_open,
+      ),
+      Duration::from_secs(1),
+    );
+    let inversion_changed = config.inverted != previous.inverted;
+    self.switches.set_config(switch.id, config);
+
+    tokio::spawn(async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => {
+          log::debug!("Switch {} configured successfully", switch_name);
+        }
+        Ok(ProcessedResponse::Failed) => {
+          log::error!("Switch {} configuration failed", switch_name);
+        }
+        Err(e) => {
+          log::error!("Error configuring switch {}: {}", switch_name, e);
         }
       }
-      None => {
-        log::error!("Attempted to configure unknown driver: {}", driver);
-        return;
+    });
+
+    // the stored state of the switch was inverted by the old config, so read it again
+    if inversion_changed {
+      self.report_switches(vec![]);
+    }
+  }
+
+  fn watch_held_switch(&mut self, switch_name: &'static str, duration: Duration) {
+    let Some(switch) = self.switches.switch_by_name(switch_name).cloned() else {
+      log::error!("Attempted to watch unknown switch: {}", switch_name);
+      return;
+    };
+    if self
+      .held_switches
+      .iter()
+      .any(|held| held.switch.id == switch.id && held.duration == duration)
+    {
+      return;
+    }
+
+    // a switch which is already held past the duration doesn't count until it's pressed again
+    let fired = self
+      .switches
+      .closed_for(switch.id)
+      .is_some_and(|closed_for| closed_for >= duration);
+    self.held_switches.push(HeldSwitch {
+      switch,
+      duration,
+      fired,
+    });
+  }
+
+  /// Emit `SwitchHeld` for watched switches which have now been closed long enough
+  fn check_held_switches(&mut self) {
+    let mut held = Vec::new();
+    for watch in &mut self.held_switches {
+      let closed_for = self
+        .switches
+        .closed_for(watch.switch.id)
+        .unwrap_or_default();
+      if closed_for.is_zero() {
+        watch.fired = false;
+      } else if !watch.fired && closed_for >= watch.duration {
+        watch.fired = true;
+        held.push(SwitchHeld::new(watch.switch.clone(), watch.duration));
       }
     }
+
+    for event in held {
+      self.emit(event);
+    }
   }
 
-  async fn report_switches(&mut self) {
-    match self
-      .io_port
-      .request(&ReportSwitchesCommand::new(), Duration::from_secs(2))
-      .await
+  /// Enable or disable hardware rules for the new phase. Only rules which change are sent to the hardware.
+  fn set_game_phase(&mut self, phase: GamePhase) {
+    if phase == self.game_phase {
+      return;
+    }
+
+    log::info!("Game phase {:?} -> {:?}", self.game_phase, phase);
+    let previous = self.game_phase;
+    self.game_phase = phase;
+
+    let changed: Vec<_> = self
+      .hardware_rules
+      .iter()
+      .filter(|rule| rule.is_enabled_in(previous) != rule.is_enabled_in(phase))
+      .map(|rule| (rule.driver, self.hardware_rule_config(rule)))
+      .collect();
+    for (driver, config) in changed {
+      self.write_driver_config(driver, config);
+    }
+  }
+
+  /// Configure every hardware rule's driver for the current phase
+  fn apply_hardware_rules(&mut self) {
+    let configs: Vec<_> = self
+      .hardware_rules
+      .iter()
+      .map(|rule| (rule.driver, self.hardware_rule_config(rule)))
+      .collect();
+    for (driver, config) in configs {
+      self.write_driver_config(driver, config);
+    }
+  }
+
+  fn hardware_rule_config(&self, rule: &HardwareRule) -> DriverConfig {
+    let paused = self.game_state.as_ref().is_some_and(|g| g.paused);
+    if rule.is_enabled_in(self.game_phase)
+      && !paused
+      && !self.watchdog.is_expired()
+      && !self.hardware_lost
     {
-      Ok(SwitchReportResponse::SwitchReport { switches }) => {
-        self.switches.update_switch_states(switches);
+      rule.mode.to_config(&self.switches)
+    } else {
+      DriverConfig::Disabled
+    }
+  }
+
+  fn set_flippers_enabled(&mut self, enabled: bool) {
+    if enabled == self.flippers_enabled {
+      return;
+    }
+    if enabled && self.watchdog.is_expired() {
+      log::warn!("Flippers not enabled, the watchdog is expired");
+      return;
+    }
+
+    log::info!(
+      "{} flippers",
+      if enabled { "Enabling" } else { "Disabling" }
+    );
+    self.flippers_enabled = enabled;
+
+    let configs: Vec<_> = self
+      .flippers
+      .iter()
+      .flat_map(|flipper| {
+        if enabled {
+          flipper.enabled_configs(&self.switches)
+        } else {
+          flipper
+            .drivers()
+            .into_iter()
+            .map(|driver| (driver, DriverConfig::Disabled))
+            .collect()
+        }
+      })
+      .collect();
+    for (driver, config) in configs {
+      self.write_driver_config(driver, config);
+    }
+  }
+
+  /// The flipper controlled by the button switch, if flippers are enabled
+  fn enabled_flipper_for(&self, switch_name: &str) -> Option<&'static str> {
+    if !self.flippers_enabled {
+      return None;
+    }
+
+    self
+      .flippers
+      .iter()
+      .find(|flipper| flipper.button_switch == switch_name)
+      .map(|flipper| flipper.name)
+  }
+
+  /// Send the driver config to the hardware. After a reconnect the driver goes back to its definition's config.
+  fn write_driver_config(&mut self, driver: &'static str, config: DriverConfig) {
+    let Some(driver) = self.driver_lookup.get(driver) else {
+      return;
+    };
+
+    log::info!("Configuring driver {}", driver.name);
+    let name = driver.name;
+    let response = self.io_port.start_request(
+      ConfigureDriverCommand::new(&driver.id, &config),
+      Duration::from_secs(2),
+    );
+
+    tokio::spawn(async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => {
+          log::debug!("Driver {} configured successfully", name);
+        }
+        Ok(ProcessedResponse::Failed) => {
+          log::error!("Driver {} configuration failed", name);
+        }
+        Err(e) => {
+          log::error!("Error configuring driver {}: {}", name, e);
+        }
+      }
+    });
+  }
+
+  /// Request the state of every switch without waiting on the response. The switch context is updated through the
+  /// command queue once the report arrives, followed by the `then` commands.
+  fn report_switches(&self, then: Vec<MachineCommand>) {
+    let response = self
+      .io_port
+      .start_request(ReportSwitchesCommand::new(), Duration::from_secs(2));
+    let sender = self.command_sender.clone();
+
+    tokio::spawn(async move {
+      match response.await {
+        Ok(SwitchReportResponse::SwitchReport { switches }) => {
+          sender.send(MachineCommand::SwitchReport(switches)).ok();
+        }
+        _ => {
+          log::error!("Failed to report switches");
+        }
       }
-      _ => {
-        log::error!("Failed to report switches");
+
+      for command in then {
+        sender.send(command).ok();
       }
-    }
+    });
   }
 
   async fn trigger_driver(
@@ -483,8 +1037,7 @@ impl Machine {
         log::info!("Triggering driver {}", driver.name);
         self
           .io_port
-          .dispatch(&TriggerDriverCommand::new(driver.id, mode, None))
-          .await;
+          .dispatch(&TriggerDriverCommand::new(driver.id, mode, None));
       }
       None => {
         log::error!("Attempted to trigger unknown driver: {}", driver);
@@ -531,10 +1084,574 @@ impl Machine {
     }
   }
 
-  async fn reset_expansion_network(&mut self) {
+  /// Reset expansion boards to an off/default state. The resets are sent immediately, the returned future completes
+  /// once every board has responded.
+  fn reset_expansion_network(&mut self) -> impl Future<Output = ()> + Send + 'static {
     self.led_renderer.reset();
-    // TODO: move this to a better common location
-    MachineBuilder::reset_expansion_boards(&mut self.exp_port, &self.expansion_boards).await;
+
+    // stop motors explicitly rather than trusting the reset to
+    self.running_motors.clear();
+    let stopped = self.stop_motors();
+
+    let mut resets = Vec::new();
+    if self.exp_port.is_connected() {
+      for board in self
+        .expansion_boards
+        .iter()
+        .filter(|b| b.breakout.is_none())
+      {
+        log::info!("Resetting expansion board at address {:X}", board.address);
+        let address = board.address;
+        let response = self
+          .exp_port
+          .start_request(BoardResetCommand::new(address), Duration::from_millis(2000));
+        let has_servos = self.servo_board_addresses().contains(&address);
+        let has_steppers = self.stepper_board_addresses().contains(&address);
+        let commands = self.command_sender.clone();
+
+        resets.push(async move {
+          match response.await {
+            Ok(ProcessedResponse::Processed) => {
+              log::debug!("Expansion board {:X} reset successfully", address);
+              if has_servos {
+                let _ = commands.send(MachineCommand::ParkServos(address));
+              }
+              if has_steppers {
+                let _ = commands.send(MachineCommand::RehomeSteppers(address));
+              }
+            }
+            Ok(ProcessedResponse::Failed) => {
+              log::error!("Expansion board {:X} reset failed", address);
+            }
+            Err(e) => {
+              log::error!("Error resetting expansion board {:X}: {}", address, e);
+            }
+          }
+        });
+      }
+    }
+
+    stopped.then(|_| futures_util::future::join_all(resets).map(|_| ()))
+  }
+
+  /// Addresses of the expansion boards which have servos
+  fn servo_board_addresses(&self) -> Vec<u8> {
+    let mut addresses: Vec<u8> = self
+      .expansion_boards
+      .iter()
+      .filter(|b| !b.servos.is_empty())
+      .map(|b| b.address)
+      .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+  }
+
+  /// Addresses of the expansion boards which have steppers
+  fn stepper_board_addresses(&self) -> Vec<u8> {
+    let mut addresses: Vec<u8> = self
+      .expansion_boards
+      .iter()
+      .filter(|b| !b.steppers.is_empty())
+      .map(|b| b.address)
+      .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+  }
+
+  /// Move a servo, or return it home when no position is given
+  fn move_servo(&mut self, servo_name: &'static str, position: Option<u8>, time: Duration) {
+    let Some((board, servo)) = self.expansion_boards.iter().find_map(|board| {
+      board
+        .servos
+        .iter()
+        .find(|s| s.name == servo_name)
+        .map(|servo| (board, servo))
+    }) else {
+      log::error!("Attempted to move unknown servo: {}", servo_name);
+      return;
+    };
+
+    let target = match position {
+      Some(position) => {
+        let clamped = servo.clamp(position);
+        if clamped != position {
+          log::warn!(
+            "Servo {} position {} is outside of its travel, moving to {}",
+            servo_name,
+            position,
+            clamped
+          );
+        }
+        clamped
+      }
+      None => servo.home,
+    };
+
+    self.exp_port.dispatch(&MoveServoCommand::new(
+      board.address,
+      board.breakout,
+      servo.port,
+      target,
+      time,
+    ));
+  }
+
+  /// Configure the servos on the board at the given address and move them home. A board reset clears the servo
+  /// configuration, so this follows every reset.
+  fn park_servos(&mut self, address: u8) -> impl Future<Output = ()> + Send + 'static {
+    let mut configured = Vec::new();
+    if self.exp_port.is_connected() {
+      for board in self
+        .expansion_boards
+        .iter()
+        .filter(|b| b.address == address)
+      {
+        for servo in &board.servos {
+          let response = self.exp_port.start_request(
+            ConfigureServoCommand::new(
+              board.address,
+              board.breakout,
+              servo.port,
+              servo.min,
+              servo.max,
+              servo.home,
+            ),
+            Duration::from_millis(500),
+          );
+          let name = servo.name;
+          configured.push(async move {
+            match response.await {
+              Ok(ProcessedResponse::Processed) => log::debug!("Servo {} parked", name),
+              Ok(ProcessedResponse::Failed) => log::error!("Servo {} configuration failed", name),
+              Err(e) => log::error!("Error configuring servo {}: {}", name, e),
+            }
+          });
+
+          let home = MoveServoCommand::new(
+            board.address,
+            board.breakout,
+            servo.port,
+            servo.home,
+            Duration::ZERO,
+          );
+          self.exp_port.dispatch(&home);
+        }
+      }
+    }
+
+    futures_util::future::join_all(configured).map(|_| ())
+  }
+
+  /// Find home again for the steppers on the board at the given address. A board reset loses their positions, so
+  /// this follows every reset. Moves in progress are dropped, as any positions they report are from before the reset.
+  /// Steppers with a home switch look for it, the others count from where they stopped, as they do at boot.
+  fn rehome_steppers(&mut self, address: u8) -> impl Future<Output = ()> + Send + 'static {
+    let steppers: Vec<_> = self
+      .expansion_boards
+      .iter()
+      .filter(|b| b.address == address)
+      .flat_map(|board| {
+        board.steppers.iter().map(move |stepper| {
+          (
+            stepper.name,
+            board.breakout,
+            stepper.port,
+            stepper.home_switch,
+          )
+        })
+      })
+      .collect();
+
+    for (name, _, _, home_switch) in &steppers {
+      self.stepper_moves.remove(name);
+      if home_switch.is_some() {
+        self.home_stepper(name);
+      }
+    }
+
+    let mut zeroed = Vec::new();
+    if self.exp_port.is_connected() {
+      for (name, breakout, port, _) in steppers.into_iter().filter(|s| s.3.is_none()) {
+        let response = self.exp_port.start_request(
+          HomeStepperCommand::new(address, breakout, port),
+          Duration::from_millis(500),
+        );
+        zeroed.push(async move {
+          match response.await {
+            Ok(ProcessedResponse::Processed) => log::debug!("Stepper {} zeroed", name),
+            Ok(ProcessedResponse::Failed) => log::error!("Stepper {} failed to zero", name),
+            Err(e) => log::error!("Error zeroing stepper {}: {}", name, e),
+          }
+        });
+      }
+    }
+
+    futures_util::future::join_all(zeroed).map(|_| ())
+  }
+
+  fn run_motor(
+    &mut self,
+    motor_name: &'static str,
+    direction: MotorDirection,
+    speed: u8,
+    time: Duration,
+    stop_at: Option<&'static str>,
+  ) {
+    let Some((board, motor)) = find_motor(&self.expansion_boards, motor_name) else {
+      log::error!("Attempted to run unknown motor: {}", motor_name);
+      return;
+    };
+
+    let limit = match direction {
+      MotorDirection::Forward => motor.forward_limit,
+      MotorDirection::Reverse => motor.reverse_limit,
+    };
+    for switch in limit.iter().chain(stop_at.iter()) {
+      if self.switches.is_closed_by_name(switch) == Some(true) {
+        log::warn!(
+          "Motor {} not run {:?}, switch {} is already closed",
+          motor_name,
+          direction,
+          switch
+        );
+        return;
+      }
+    }
+
+    let clamped = motor.clamp(speed);
+    if clamped != speed {
+      log::warn!(
+        "Motor {} speed {} is above its max speed, running at {}",
+        motor_name,
+        speed,
+        clamped
+      );
+    }
+
+    let command = DriveMotorCommand::new(
+      board.address,
+      board.breakout,
+      motor.port,
+      direction,
+      clamped,
+      time,
+    );
+    tokio::spawn(self.drive_motor(motor_name, command));
+
+    if clamped > 0 && time.is_zero() {
+      self
+        .running_motors
+        .insert(motor_name, RunningMotor { direction, stop_at });
+    } else {
+      self.running_motors.remove(motor_name);
+    }
+  }
+
+  fn stop_motor(&mut self, motor_name: &'static str) {
+    let Some((board, motor)) = find_motor(&self.expansion_boards, motor_name) else {
+      log::error!("Attempted to stop unknown motor: {}", motor_name);
+      return;
+    };
+
+    let command = DriveMotorCommand::stop(board.address, board.breakout, motor.port);
+    tokio::spawn(self.drive_motor(motor_name, command));
+    self.running_motors.remove(motor_name);
+  }
+
+  /// Stop every motor, whether or not it's known to be running. Timed runs are stopped too.
+  fn stop_motors(&self) -> impl Future<Output = ()> + Send + 'static {
+    let mut stopped = Vec::new();
+    if self.exp_port.is_connected() {
+      let commands: Vec<_> = self
+        .expansion_boards
+        .iter()
+        .flat_map(|board| {
+          board.motors.iter().map(|motor| {
+            (
+              motor.name,
+              DriveMotorCommand::stop(board.address, board.breakout, motor.port),
+            )
+          })
+        })
+        .collect();
+      for (motor_name, command) in commands {
+        stopped.push(self.drive_motor(motor_name, command));
+      }
+    }
+
+    futures_util::future::join_all(stopped).map(|_| ())
+  }
+
+  /// Send a motor command, the returned future logs the outcome
+  fn drive_motor(
+    &self,
+    motor_name: &'static str,
+    command: DriveMotorCommand,
+  ) -> impl Future<Output = ()> + Send + 'static {
+    let response = self
+      .exp_port
+      .start_request(command, Duration::from_millis(500));
+    async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => log::debug!("Motor {} updated", motor_name),
+        Ok(ProcessedResponse::Failed) => log::error!("Motor {} command failed", motor_name),
+        Err(e) => log::error!("Error driving motor {}: {}", motor_name, e),
+      }
+    }
+  }
+
+  /// Stop any running motor which has reached its limit or the position it was run to
+  fn on_motor_switch(&mut self, switch_name: &'static str) {
+    let stopped: Vec<_> = self
+      .running_motors
+      .iter()
+      .filter(|(name, running)| {
+        running.stop_at == Some(switch_name)
+          || find_motor(&self.expansion_boards, name).is_some_and(|(_, motor)| {
+            let limit = match running.direction {
+              MotorDirection::Forward => motor.forward_limit,
+              MotorDirection::Reverse => motor.reverse_limit,
+            };
+            limit == Some(switch_name)
+          })
+      })
+      .map(|(name, _)| *name)
+      .collect();
+
+    for motor_name in stopped {
+      log::debug!("Motor {} stopped by switch {}", motor_name, switch_name);
+      self.stop_motor(motor_name);
+    }
+  }
+
+  /// Return a stepper home, either by turning in reverse until its home switch closes or by moving to position 0
+  fn home_stepper(&mut self, stepper_name: &'static str) {
+    let Some((board, stepper)) = find_stepper(&self.expansion_boards, stepper_name) else {
+      log::error!("Attempted to home unknown stepper: {}", stepper_name);
+      return;
+    };
+
+    let Some(home_switch) = stepper.home_switch else {
+      let command = MoveStepperCommand::new(board.address, board.breakout, stepper.port, 0);
+      self.start_stepper_move(stepper_name, command, false);
+      return;
+    };
+
+    match self.switches.is_closed_by_name(home_switch) {
+      Some(true) => {
+        let (address, breakout, port) = (board.address, board.breakout, stepper.port);
+        self.zero_stepper(stepper_name, address, breakout, port);
+      }
+      Some(false) => {
+        let steps = -(stepper.steps_per_revolution as i32);
+        self.move_stepper_by(stepper_name, steps, true);
+      }
+      None => log::error!(
+        "Stepper {} home switch {} does not exist",
+        stepper_name,
+        home_switch
+      ),
+    }
+  }
+
+  fn move_stepper_to(&mut self, stepper_name: &'static str, position_name: &'static str) {
+    let Some((board, stepper)) = find_stepper(&self.expansion_boards, stepper_name) else {
+      log::error!("Attempted to move unknown stepper: {}", stepper_name);
+      return;
+    };
+    let Some(position) = stepper.position(position_name) else {
+      log::error!(
+        "Stepper {} has no position named {}",
+        stepper_name,
+        position_name
+      );
+      return;
+    };
+
+    let command = MoveStepperCommand::new(board.address, board.breakout, stepper.port, position);
+    self.start_stepper_move(stepper_name, command, false);
+  }
+
+  fn move_stepper_by(&mut self, stepper_name: &'static str, steps: i32, seeking_home: bool) {
+    let Some((board, stepper)) = find_stepper(&self.expansion_boards, stepper_name) else {
+      log::error!("Attempted to move unknown stepper: {}", stepper_name);
+      return;
+    };
+
+    let command =
+      MoveStepperRelativeCommand::new(board.address, board.breakout, stepper.port, steps);
+    self.start_stepper_move(stepper_name, command, seeking_home);
+  }
+
+  /// Send a move and start polling for its end. The reply only says whether the move was accepted, so it's logged
+  /// here rather than passed on as an event.
+  fn start_stepper_move<C>(&mut self, stepper_name: &'static str, command: C, seeking_home: bool)
+  where
+    C: FastCommand<Response = ProcessedResponse> + Send + Sync + 'static,
+  {
+    let response = self
+      .exp_port
+      .start_request(command, Duration::from_millis(500));
+    tokio::spawn(async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => log::debug!("Stepper {} moving", stepper_name),
+        Ok(ProcessedResponse::Failed) => log::error!("Stepper {} failed to move", stepper_name),
+        Err(e) => log::error!("Error moving stepper {}: {}", stepper_name, e),
+      }
+    });
+    self.track_stepper_move(stepper_name, seeking_home);
+  }
+
+  /// Stop a stepper. It's polled like any other move so its final position is reported.
+  fn stop_stepper(&mut self, stepper_name: &'static str) {
+    let Some((board, stepper)) = find_stepper(&self.expansion_boards, stepper_name) else {
+      log::error!("Attempted to stop unknown stepper: {}", stepper_name);
+      return;
+    };
+
+    let response = self.exp_port.start_request(
+      StopStepperCommand::new(board.address, board.breakout, stepper.port),
+      Duration::from_millis(500),
+    );
+    tokio::spawn(async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => log::debug!("Stepper {} stopped", stepper_name),
+        Ok(ProcessedResponse::Failed) => log::error!("Stepper {} failed to stop", stepper_name),
+        Err(e) => log::error!("Error stopping stepper {}: {}", stepper_name, e),
+      }
+    });
+    self.track_stepper_move(stepper_name, false);
+  }
+
+  /// Start polling a stepper for the end of a new move. Responses to polls for an earlier move are ignored.
+  fn track_stepper_move(&mut self, stepper_name: &'static str, seeking_home: bool) {
+    self.next_stepper_move += 1;
+    self.stepper_moves.insert(
+      stepper_name,
+      StepperMove {
+        id: self.next_stepper_move,
+        seeking_home,
+        polling: false,
+      },
+    );
+  }
+
+  /// Stop any stepper looking for this home switch and count its positions from here
+  fn on_stepper_home_switch(&mut self, switch_name: &'static str) {
+    let homed: Vec<_> = self
+      .stepper_moves
+      .iter()
+      .filter(|(_, m)| m.seeking_home)
+      .filter_map(|(name, _)| find_stepper(&self.expansion_boards, name))
+      .filter(|(_, stepper)| stepper.home_switch == Some(switch_name))
+      .map(|(board, stepper)| (stepper.name, board.address, board.breakout, stepper.port))
+      .collect();
+
+    for (stepper_name, address, breakout, port) in homed {
+      self.stepper_moves.remove(stepper_name);
+      self
+        .exp_port
+        .dispatch(&StopStepperCommand::new(address, breakout, port));
+      self.zero_stepper(stepper_name, address, breakout, port);
+    }
+  }
+
+  /// Make the stepper's current location home, completing the move once the board confirms
+  fn zero_stepper(
+    &mut self,
+    stepper_name: &'static str,
+    address: u8,
+    breakout: Option<u8>,
+    port: u8,
+  ) {
+    let response = self.exp_port.start_request(
+      HomeStepperCommand::new(address, breakout, port),
+      Duration::from_millis(500),
+    );
+    let commands = self.command_sender.clone();
+    tokio::spawn(async move {
+      match response.await {
+        Ok(ProcessedResponse::Processed) => {
+          log::debug!("Stepper {} homed", stepper_name);
+          let event = StepperMoveComplete::new(stepper_name, 0);
+          let _ = commands.send(MachineCommand::EmitEvent(event));
+        }
+        Ok(ProcessedResponse::Failed) => log::error!("Stepper {} failed to home", stepper_name),
+        Err(e) => log::error!("Error homing stepper {}: {}", stepper_name, e),
+      }
+    });
+  }
+
+  /// Ask each moving stepper where it is, unless it's already been asked
+  fn poll_steppers(&mut self) {
+    for (stepper_name, stepper_move) in self.stepper_moves.iter_mut() {
+      if stepper_move.polling {
+        continue;
+      }
+      let Some((board, stepper)) = find_stepper(&self.expansion_boards, stepper_name) else {
+        continue;
+      };
+
+      stepper_move.polling = true;
+      let response = self.exp_port.start_request(
+        StepperStatusCommand::new(board.address, board.breakout, stepper.port),
+        Duration::from_millis(500),
+      );
+      let (stepper_name, move_id) = (*stepper_name, stepper_move.id);
+      let commands = self.command_sender.clone();
+      tokio::spawn(async move {
+        let status = match response.await {
+          Ok(status) => Some(status),
+          Err(e) => {
+            log::warn!("Error polling stepper {}: {}", stepper_name, e);
+            None
+          }
+        };
+        let _ = commands.send(MachineCommand::StepperStatus(stepper_name, move_id, status));
+      });
+    }
+  }
+
+  fn on_stepper_status(
+    &mut self,
+    stepper_name: &'static str,
+    move_id: u64,
+    status: Option<StepperStatusResponse>,
+  ) {
+    let Some(stepper_move) = self.stepper_moves.get_mut(stepper_name) else {
+      return;
+    };
+    if stepper_move.id != move_id {
+      return;
+    }
+    stepper_move.polling = false;
+
+    match status {
+      // try again next tick
+      None | Some(StepperStatusResponse::Status { moving: true, .. }) => {}
+      Some(StepperStatusResponse::Status {
+        moving: false,
+        position,
+        ..
+      }) => {
+        let seeking_home = stepper_move.seeking_home;
+        self.stepper_moves.remove(stepper_name);
+        if seeking_home {
+          log::error!(
+            "Stepper {} turned a full revolution without finding its home switch",
+            stepper_name
+          );
+        } else {
+          self.emit(StepperMoveComplete::new(stepper_name, position));
+        }
+      }
+      Some(StepperStatusResponse::Failed) => {
+        self.stepper_moves.remove(stepper_name);
+        log::error!("Stepper {} did not report its position", stepper_name);
+      }
+    }
   }
 
   async fn render_leds(&mut self) {
@@ -559,6 +1676,56 @@ impl Machine {
   }
 }
 
+/// A stepper move which hasn't been reported complete yet
+struct StepperMove {
+  id: u64,
+  /// Turning towards the home switch, which ends the move when it closes
+  seeking_home: bool,
+  /// A status request is in flight
+  polling: bool,
+}
+
+/// A switch watched for being held closed
+struct HeldSwitch {
+  switch: Switch,
+  duration: Duration,
+  /// `SwitchHeld` was emitted for the current closure
+  fired: bool,
+}
+
+/// A motor which runs until it's stopped, by a limit switch, or by reaching a position
+struct RunningMotor {
+  direction: MotorDirection,
+  /// Position switch the motor was run to
+  stop_at: Option<&'static str>,
+}
+
+fn find_motor<'a>(
+  expansion_boards: &'a [ExpansionBoardDefinition],
+  motor_name: &str,
+) -> Option<(&'a ExpansionBoardDefinition, &'a MotorDefinition)> {
+  expansion_boards.iter().find_map(|board| {
+    board
+      .motors
+      .iter()
+      .find(|m| m.name == motor_name)
+      .map(|motor| (board, motor))
+  })
+}
+
+fn find_stepper<'a>(
+  expansion_boards: &'a [ExpansionBoardDefinition],
+  stepper_name: &str,
+) -> Option<(&'a ExpansionBoardDefinition, &'a StepperDefinition)> {
+  expansion_boards.iter().find_map(|board| {
+    board
+      .steppers
+      .iter()
+      .find(|s| s.name == stepper_name)
+      .map(|stepper| (board, stepper))
+  })
+}
+
 #[derive(Debug, Clone)]
 pub struct Switch {
   pub id: usize,
@@ -566,6 +1733,11 @@ pub struct Switch {
 }
 
 impl Switch {
+  /// Whether this is the named switch, e.g. `e.switch.is(Switches::StartButton)` for a switch enum deriving `SwitchId`
+  pub fn is(&self, switch: impl SwitchId) -> bool {
+    self.name == switch.switch_name()
+  }
+
   pub fn is_virtual(&self) -> bool {
     self.id > u16::MAX as usize
   }
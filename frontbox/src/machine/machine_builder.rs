@@ -2,11 +2,24 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use crate::hardware_definition::*;
+use crate::machine::expansion_inventory::{
+  ExpansionBreakout, ExpansionInventory, starter_definitions,
+};
+use crate::machine::firmware_check::{BoardFirmware, require};
+use crate::machine::io_inventory::{IoInventory, IoNode};
+use crate::machine::machine::HardwareSetup;
 use crate::machine::serial_interface::SerialInterface;
+use crate::machine::serial_recorder::{EXP_CHANNEL, IO_NET_CHANNEL, SerialRecorder};
 use crate::machine::switch_context::SwitchContext;
+use crate::machine::transport::Transport;
 use crate::prelude::*;
 use fast_protocol::*;
 
+/// Breakouts are numbered with a single hex digit
+const MAX_BREAKOUTS: u8 = 16;
+/// Times the watchdog is set at boot before giving up on it
+const WATCHDOG_ATTEMPTS: u32 = 5;
+
 pub struct MachineBuilder {
   io_port: SerialInterface,
   exp_port: SerialInterface,
@@ -18,6 +31,15 @@ pub struct MachineBuilder {
   expansion_boards: Vec<ExpansionBoardDefinition>,
   io_boards: Vec<IoBoardDefinition>,
   driver_groups: HashMap<&'static str, Vec<&'static str>>,
+  led_groups: HashMap<&'static str, Vec<&'static str>>,
+  hardware_rules: Vec<HardwareRule>,
+  flippers: Vec<FlipperDefinition>,
+  platform: FastPlatform,
+  mainboard: BoardFirmware,
+  refuse_incompatible_firmware: bool,
+  io_inventory: IoInventory,
+  expansion_inventory: ExpansionInventory,
+  watchdog_blocked: bool,
 }
 
 impl MachineBuilder {
@@ -25,90 +47,438 @@ impl MachineBuilder {
     config: BootConfig,
     io_network: IoNetwork,
     expansion_boards: Vec<ExpansionBoardDefinition>,
-  ) -> Self {
-    let mut io_port = SerialInterface::new(config.io_net_port_path)
+  ) -> Result<Self, BootError> {
+    MachineBuilder::check_definition(&config, &io_network, &expansion_boards)
+      .map_err(|problems| BootError::InvalidDefinition { problems })?;
+
+    let io_port = SerialInterface::new(config.io_net_port_path)
       .await
-      .expect("Failed to open IO NET port");
+      .map_err(|error| BootError::PortUnavailable {
+        port: config.io_net_port_path.to_string(),
+        error,
+      })?;
     log::info!("🥾 Opened IO NET port at {}", config.io_net_port_path);
 
-    MachineBuilder::boot_mainboard(&mut io_port).await;
-    MachineBuilder::configure_hardware(&mut io_port, config.platform).await;
-    MachineBuilder::verify_watchdog(&mut io_port).await;
-    MachineBuilder::configure_switches(&mut io_port, &io_network.switches).await;
+    let exp_port = SerialInterface::new(config.exp_port_path)
+      .await
+      .map_err(|error| BootError::PortUnavailable {
+        port: config.exp_port_path.to_string(),
+        error,
+      })?;
+    log::info!("🥾 Opened EXP port at {}", config.exp_port_path);
+
+    MachineBuilder::boot_interfaces(config, io_port, exp_port, io_network, expansion_boards).await
+  }
+
+  /// Boot over the given transports rather than the serial ports named in `BootConfig`. This allows the machine to
+  /// run against anything that speaks the FAST protocol, e.g. an emulator connected through `tokio::io::duplex`.
+  pub async fn boot_with_transports(
+    config: BootConfig,
+    io_transport: impl Transport,
+    exp_transport: impl Transport,
+    io_network: IoNetwork,
+    expansion_boards: Vec<ExpansionBoardDefinition>,
+  ) -> Result<Self, BootError> {
+    MachineBuilder::check_definition(&config, &io_network, &expansion_boards)
+      .map_err(|problems| BootError::InvalidDefinition { problems })?;
+
+    let io_port = SerialInterface::from_transport(IO_NET_CHANNEL, io_transport)
+      .await
+      .map_err(|error| BootError::PortUnavailable {
+        port: IO_NET_CHANNEL.to_string(),
+        error,
+      })?;
+    let exp_port = SerialInterface::from_transport(EXP_CHANNEL, exp_transport)
+      .await
+      .map_err(|error| BootError::PortUnavailable {
+        port: EXP_CHANNEL.to_string(),
+        error,
+      })?;
+
+    MachineBuilder::boot_interfaces(config, io_port, exp_port, io_network, expansion_boards).await
+  }
+
+  /// Check the parts of the definition the `IoNetwork` can't check on its own, before any hardware is touched
+  fn check_definition(
+    config: &BootConfig,
+    io_network: &IoNetwork,
+    expansion_boards: &[ExpansionBoardDefinition],
+  ) -> Result<(), Vec<ValidationError>> {
+    let switches: Vec<&'static str> = io_network.switches.iter().map(|s| s.name).collect();
+    let problems = validate_machine(&switches, expansion_boards, &config.keyboard_mappings);
+    if problems.is_empty() {
+      Ok(())
+    } else {
+      Err(problems)
+    }
+  }
+
+  async fn boot_interfaces(
+    config: BootConfig,
+    mut io_port: SerialInterface,
+    mut exp_port: SerialInterface,
+    io_network: IoNetwork,
+    expansion_boards: Vec<ExpansionBoardDefinition>,
+  ) -> Result<Self, BootError> {
+    if let Some(path) = config.record_serial_to {
+      let recorder =
+        SerialRecorder::create(path).map_err(|error| BootError::RecordingUnavailable {
+          path: path.to_string(),
+          error,
+        })?;
+      io_port.record(recorder.clone(), IO_NET_CHANNEL);
+      exp_port.record(recorder, EXP_CHANNEL);
+      log::info!("🥾 Recording serial traffic to {}", path);
+    }
+
+    let mainboard = MachineBuilder::boot_mainboard(&mut io_port).await?;
+    let mut capabilities = vec![Capability::NodeInfo];
+    let switch_lookup: HashMap<&'static str, usize> = io_network
+      .switches
+      .iter()
+      .map(|switch| (switch.name, switch.id))
+      .collect();
+    let flipper_configs = io_network
+      .flippers
+      .iter()
+      .flat_map(|flipper| flipper.enabled_configs(&switch_lookup))
+      .map(|(_, config)| config);
+    let driver_configs = io_network.drivers.iter().filter_map(|d| d.config.clone());
+    for config in driver_configs.chain(flipper_configs) {
+      if config.mode() != 0 {
+        require(&mut capabilities, Capability::DriverMode(config.mode()));
+      }
+    }
+    for problem in mainboard.problems(HardwareTarget::Mainboard, &capabilities) {
+      if config.refuse_incompatible_firmware {
+        return Err(problem);
+      }
+      log::warn!("🥾 {}", problem);
+    }
+
+    MachineBuilder::configure_hardware(&mut io_port, config.platform.clone()).await?;
+    MachineBuilder::verify_watchdog(&mut io_port, config.watchdog_interval).await?;
+    MachineBuilder::configure_switches(&mut io_port, &io_network.switches).await?;
+
+    let io_inventory = MachineBuilder::verify_io_network(&mut io_port, &io_network.boards).await?;
+    let watchdog_blocked = config.refuse_watchdog_on_io_mismatch && !io_inventory.is_match();
 
     // Initialize switch context which Machine will use to maintain current state
-    let initial_switch_state = MachineBuilder::get_initial_switch_states(&mut io_port).await;
+    let initial_switch_state = MachineBuilder::get_initial_switch_states(&mut io_port).await?;
     let switches = SwitchContext::new(io_network.switches, initial_switch_state);
+    let mut keyboard_switch_map = HashMap::new();
+    for (key, switch_name) in &config.keyboard_mappings {
+      if let Some(switch) = switches.switch_by_name(switch_name) {
+        keyboard_switch_map.insert(*key, switch.id);
+      }
+    }
 
     // Configure drivers
-    MachineBuilder::configure_drivers(&mut io_port, &io_network.drivers).await;
+    MachineBuilder::configure_drivers(&mut io_port, &io_network.drivers).await?;
     let mut drivers = HashMap::new();
     for driver in io_network.drivers {
       drivers.insert(driver.name, driver);
     }
 
-    // open EXP port
-    let mut exp_port = SerialInterface::new(config.exp_port_path)
-      .await
-      .expect("Failed to open EXP port");
-    log::info!("🥾 Opened EXP port at {}", config.exp_port_path);
-
-    MachineBuilder::reset_expansion_boards(&mut exp_port, &expansion_boards).await;
-    MachineBuilder::configure_led_ports(&mut exp_port, &expansion_boards).await;
+    MachineBuilder::reset_expansion_boards(&mut exp_port, &expansion_boards).await?;
+    MachineBuilder::check_expansion_firmware(
+      &mut exp_port,
+      &expansion_boards,
+      config.refuse_incompatible_firmware,
+    )
+    .await?;
+    let expansion_inventory =
+      MachineBuilder::verify_expansion_network(&mut exp_port, &expansion_boards).await?;
+    MachineBuilder::configure_led_ports(&mut exp_port, &expansion_boards).await?;
+    MachineBuilder::configure_servos(&mut exp_port, &expansion_boards).await?;
+    MachineBuilder::home_steppers(&mut exp_port, &expansion_boards).await?;
 
-    Self {
+    Ok(Self {
       io_port,
       exp_port,
       switches,
       driver_lookup: drivers,
-      keyboard_switch_map: HashMap::new(),
+      keyboard_switch_map,
       virtual_switch_count: 0,
       config: MachineConfig::default(),
       expansion_boards,
       io_boards: io_network.boards,
       driver_groups: io_network.driver_groups,
-    }
+      led_groups: HashMap::new(),
+      hardware_rules: Vec::new(),
+      flippers: io_network.flippers,
+      platform: config.platform,
+      mainboard,
+      refuse_incompatible_firmware: config.refuse_incompatible_firmware,
+      io_inventory,
+      expansion_inventory,
+      watchdog_blocked,
+    })
   }
 
   /// wait for the mainboard to be ready to respond
-  async fn boot_mainboard(io_port: &mut SerialInterface) {
-    let _ = io_port
+  pub(crate) async fn boot_mainboard(
+    io_port: &mut SerialInterface,
+  ) -> Result<BoardFirmware, BootError> {
+    io_port
       .request_until_match(IdCommand::new(), Duration::from_millis(500), |response| {
-        if let IdResponse::Report {
-          processor,
-          product_number,
-          firmware_version,
-        } = response
-        {
-          log::info!(
-            "🥾 Connected to mainboard {} {} with firmware: {}",
-            processor,
-            product_number,
-            firmware_version
-          );
-          Some(true)
-        } else {
-          None
-        }
+        let firmware = BoardFirmware::from_id(response)?;
+        log::info!(
+          "🥾 Connected to mainboard {} {} with firmware: {}",
+          firmware.processor,
+          firmware.product_number,
+          firmware.version
+        );
+        Some(firmware)
       })
-      .await;
+      .await
+      .map_err(|e| BootError::request(HardwareTarget::Mainboard, e))
   }
 
-  async fn configure_hardware(io_port: &mut SerialInterface, platform: FastPlatform) {
+  /// Identify each declared expansion board and check its firmware supports what the machine uses
+  async fn check_expansion_firmware(
+    exp_port: &mut SerialInterface,
+    expansion_boards: &[ExpansionBoardDefinition],
+    refuse: bool,
+  ) -> Result<(), BootError> {
+    let mut addresses: Vec<u8> = expansion_boards.iter().map(|b| b.address).collect();
+    addresses.sort();
+    addresses.dedup();
+
+    for address in addresses {
+      let target = HardwareTarget::ExpansionBoard { address };
+      let response = exp_port
+        .request(&IdCommand::exp(address, None), Duration::from_millis(500))
+        .await
+        .map_err(|e| BootError::request(target.clone(), e))?;
+      let Some(firmware) = BoardFirmware::from_id(response) else {
+        continue;
+      };
+      log::info!(
+        "🥾 EXP board {:X} is {} {} with firmware: {}",
+        address,
+        firmware.processor,
+        firmware.product_number,
+        firmware.version
+      );
+
+      let mut capabilities = vec![Capability::IdentifyHardware];
+      if expansion_boards
+        .iter()
+        .any(|b| b.address == address && !b.led_ports.is_empty())
+      {
+        capabilities.push(Capability::LedPorts);
+      }
+      if expansion_boards
+        .iter()
+        .any(|b| b.address == address && !b.servos.is_empty())
+      {
+        capabilities.push(Capability::Servos);
+      }
+      if expansion_boards
+        .iter()
+        .any(|b| b.address == address && !b.steppers.is_empty())
+      {
+        capabilities.push(Capability::Steppers);
+      }
+      if expansion_boards
+        .iter()
+        .any(|b| b.address == address && !b.motors.is_empty())
+      {
+        capabilities.push(Capability::Motors);
+      }
+      for problem in firmware.problems(target.clone(), &capabilities) {
+        if refuse {
+          return Err(problem);
+        }
+        log::warn!("🥾 {}", problem);
+      }
+    }
+
+    Ok(())
+  }
+
+  pub(crate) async fn configure_hardware(
+    io_port: &mut SerialInterface,
+    platform: FastPlatform,
+  ) -> Result<(), BootError> {
     log::info!(
       "🥾 Configuring mainboard hardware as platform {:?}",
       platform
     );
-    let _ = io_port
-      .request(
-        &ConfigureHardwareCommand::new(platform as u16, Some(SwitchReporting::Verbose)),
-        Duration::from_millis(500),
-      )
-      .await;
+    request_processed(
+      io_port,
+      &ConfigureHardwareCommand::new(platform as u16, Some(SwitchReporting::Verbose)),
+      Duration::from_millis(500),
+    )
+    .await
+    .map_err(|e| BootError::request(HardwareTarget::Mainboard, e))
+  }
+
+  /// Enumerate the boards on the IO NET loop and compare them with the defined boards
+  async fn verify_io_network(
+    io_port: &mut SerialInterface,
+    boards: &[IoBoardDefinition],
+  ) -> Result<IoInventory, BootError> {
+    let mut nodes = Vec::new();
+
+    // look one past the defined boards so extra boards are reported too
+    for node in 0..=boards.len().min(u8::MAX as usize) as u8 {
+      match io_port
+        .request(&NodeInfoCommand::new(node), Duration::from_millis(500))
+        .await
+      {
+        Ok(NodeInfoResponse::Report {
+          name,
+          firmware_version,
+          driver_count,
+          switch_count,
+          ..
+        }) => nodes.push(IoNode {
+          node,
+          name,
+          firmware_version,
+          switch_count,
+          driver_count,
+        }),
+        Ok(NodeInfoResponse::Failed) => break,
+        Err(e) if e.error == FastResponseError::Disconnected => {
+          return Err(BootError::request(HardwareTarget::IoNode { node }, e));
+        }
+        Err(e) => {
+          log::warn!("🥾 IO NET node {} could not be identified: {}", node, e);
+          break;
+        }
+      }
+    }
+
+    let inventory = IoInventory::compare(boards, nodes);
+    if inventory.is_match() {
+      log::info!("🥾 {}", inventory);
+    } else {
+      log::error!("🥾 {}", inventory);
+    }
+    Ok(inventory)
+  }
+
+  /// Probe the breakouts at every declared and every FAST-assigned expansion address, and check the declared LED
+  /// ports fit them. Boards found at an undeclared address are reported as mismatches.
+  async fn verify_expansion_network(
+    exp_port: &mut SerialInterface,
+    expansion_boards: &[ExpansionBoardDefinition],
+  ) -> Result<ExpansionInventory, BootError> {
+    let mut declared: Vec<u8> = expansion_boards.iter().map(|b| b.address).collect();
+    declared.sort();
+    declared.dedup();
+
+    let mut breakouts = Vec::new();
+    for &address in &declared {
+      breakouts.extend(
+        MachineBuilder::probe_breakouts(exp_port, address, Duration::from_millis(500)).await?,
+      );
+    }
+
+    // also probe the rest of the addresses FAST assigns, so a stray or misaddressed board is reported at boot
+    // rather than silently ignored. The Neuron's own expansion processor is always there, so it only counts when
+    // it's declared.
+    let neutron = ExpansionBoardDefinition::neutron().address;
+    let mut undeclared: Vec<u8> = ExpansionBoardDefinition::known_addresses()
+      .into_iter()
+      .map(|(address, _)| address)
+      .filter(|address| *address != neutron && !declared.contains(address))
+      .collect();
+    undeclared.sort();
+    undeclared.dedup();
+    for address in undeclared {
+      breakouts.extend(
+        MachineBuilder::probe_breakouts(exp_port, address, Duration::from_millis(100)).await?,
+      );
+    }
+
+    let inventory = ExpansionInventory::compare(expansion_boards, breakouts);
+    if inventory.is_match() {
+      log::info!("🥾 {}", inventory);
+    } else {
+      log::warn!("🥾 {}", inventory);
+    }
+    Ok(inventory)
+  }
+
+  /// Identify the breakouts of the board at the given address, stopping at the first one which isn't active
+  async fn probe_breakouts(
+    exp_port: &mut SerialInterface,
+    address: u8,
+    timeout: Duration,
+  ) -> Result<Vec<ExpansionBreakout>, BootError> {
+    let mut breakouts = Vec::new();
+
+    for breakout in 0..MAX_BREAKOUTS {
+      match exp_port
+        .request(&IdentifyHardwareCommand::new(address, breakout), timeout)
+        .await
+      {
+        Ok(info) => match ExpansionBreakout::from_info(info) {
+          Some(found) => breakouts.push(found),
+          None => break,
+        },
+        Err(e) if e.error == FastResponseError::Disconnected => {
+          return Err(BootError::request(
+            HardwareTarget::ExpansionBoard { address },
+            e,
+          ));
+        }
+        Err(_) => break,
+      }
+    }
+
+    Ok(breakouts)
+  }
+
+  /// Scan every address FAST assigns to expansion boards and print a starter `ExpansionBoardDefinition` list for
+  /// what was found. Useful when writing the hardware definition for a new machine.
+  pub async fn discover_expansion_network(
+    config: BootConfig,
+  ) -> Result<Vec<ExpansionBreakout>, BootError> {
+    let exp_port = SerialInterface::new(config.exp_port_path)
+      .await
+      .map_err(|error| BootError::PortUnavailable {
+        port: config.exp_port_path.to_string(),
+        error,
+      })?;
+    MachineBuilder::discover_expansion_breakouts(exp_port).await
+  }
+
+  /// As `discover_expansion_network`, over the given transport rather than the EXP port named in `BootConfig`
+  pub async fn discover_expansion_network_with_transport(
+    exp_transport: impl Transport,
+  ) -> Result<Vec<ExpansionBreakout>, BootError> {
+    let exp_port = SerialInterface::from_transport(EXP_CHANNEL, exp_transport)
+      .await
+      .map_err(|error| BootError::PortUnavailable {
+        port: EXP_CHANNEL.to_string(),
+        error,
+      })?;
+    MachineBuilder::discover_expansion_breakouts(exp_port).await
+  }
+
+  async fn discover_expansion_breakouts(
+    mut exp_port: SerialInterface,
+  ) -> Result<Vec<ExpansionBreakout>, BootError> {
+    let mut breakouts = Vec::new();
+    for (address, _) in ExpansionBoardDefinition::known_addresses() {
+      breakouts.extend(
+        MachineBuilder::probe_breakouts(&mut exp_port, address, Duration::from_millis(100)).await?,
+      );
+    }
+
+    println!("{}", starter_definitions(&breakouts));
+    Ok(breakouts)
   }
 
   /// Read the hardware state of all switches at startup to initialize the switch context
-  async fn get_initial_switch_states(io_port: &mut SerialInterface) -> Vec<SwitchState> {
+  async fn get_initial_switch_states(
+    io_port: &mut SerialInterface,
+  ) -> Result<Vec<SwitchState>, BootError> {
     io_port
       .request_until_match(
         ReportSwitchesCommand::new(),
@@ -123,107 +493,136 @@ impl MachineBuilder {
         },
       )
       .await
+      .map_err(|e| BootError::request(HardwareTarget::Mainboard, e))
   }
 
-  /// Verify the watchdog is responsive. Sometimes the first few commands will fail.
-  async fn verify_watchdog(io_port: &mut SerialInterface) {
-    let _ = io_port.request_until_match(
-      WatchdogCommand::set(Duration::from_millis(1250)),
-      Duration::from_secs(1),
-      |resp| match resp {
-        WatchdogResponse::Processed => {
+  /// Verify the watchdog is responsive, so the machine never runs without one. Sometimes the first few commands will
+  /// fail.
+  async fn verify_watchdog(
+    io_port: &mut SerialInterface,
+    timeout: Duration,
+  ) -> Result<(), BootError> {
+    let cmd = WatchdogCommand::set(timeout);
+    let mut attempt = 1;
+    loop {
+      let error = match io_port.request_with_raw(&cmd, Duration::from_secs(1)).await {
+        Ok((WatchdogResponse::Processed, _)) => {
           log::info!("🥾 Watchdog is ready");
-          Some(true)
+          return Ok(());
         }
-        _ => None,
-      },
-    );
+        Ok((_, raw)) => RequestError::new(&cmd.to_string(), Some(raw), FastResponseError::Failed),
+        Err(e) => e,
+      };
+
+      if attempt == WATCHDOG_ATTEMPTS || error.error == FastResponseError::Disconnected {
+        return Err(BootError::request(HardwareTarget::Mainboard, error));
+      }
+      log::debug!("Watchdog not ready yet: {}", error);
+      attempt += 1;
+      tokio::time::sleep(Duration::from_secs(1)).await;
+    }
   }
 
-  async fn configure_switches(io_port: &mut SerialInterface, switches: &Vec<SwitchDefinition>) {
+  async fn configure_switches(
+    io_port: &mut SerialInterface,
+    switches: &Vec<SwitchDefinition>,
+  ) -> Result<(), BootError> {
     for switch in switches {
       if let Some(config) = &switch.config {
-        let reporting = if config.inverted {
-          SwitchReportingMode::ReportInverted
-        } else {
-          SwitchReportingMode::ReportNormal
-        };
-        log::info!("Configuring switch {} with {:?}", switch.name, config);
-        let _ = io_port
-          .request(
-            &ConfigureSwitchCommand::new(
-              switch.id,
-              reporting,
-              config.debounce_close,
-              config.debounce_open,
-            ),
-            Duration::from_millis(500),
-          )
-          .await;
+        MachineBuilder::configure_switch(io_port, switch.id, switch.name, config).await?;
       }
     }
+    Ok(())
+  }
+
+  pub(crate) async fn configure_switch(
+    io_port: &mut SerialInterface,
+    switch_id: usize,
+    switch_name: &'static str,
+    config: &SwitchConfig,
+  ) -> Result<(), BootError> {
+    log::info!("Configuring switch {} with {:?}", switch_name, config);
+    request_processed(
+      io_port,
+      &ConfigureSwitchCommand::new(
+        switch_id,
+        config.reporting_mode(),
+        config.debounce_close,
+        config.debounce_open,
+      ),
+      Duration::from_millis(500),
+    )
+    .await
+    .map_err(|e| {
+      BootError::request(
+        HardwareTarget::Switch {
+          name: switch_name,
+          id: switch_id,
+        },
+        e,
+      )
+    })
   }
 
-  async fn configure_drivers(io_port: &mut SerialInterface, drivers: &Vec<DriverDefinition>) {
+  pub(crate) async fn configure_drivers(
+    io_port: &mut SerialInterface,
+    drivers: &Vec<DriverDefinition>,
+  ) -> Result<(), BootError> {
     for driver in drivers {
       if let Some(config) = &driver.config {
         log::info!("Configuring driver {} with {:?}", driver.name, config);
-        match io_port
-          .request(
-            &ConfigureDriverCommand::new(&driver.id, config),
-            Duration::from_millis(500),
+        request_processed(
+          io_port,
+          &ConfigureDriverCommand::new(&driver.id, config),
+          Duration::from_millis(500),
+        )
+        .await
+        .map_err(|e| {
+          BootError::request(
+            HardwareTarget::Driver {
+              name: driver.name,
+              id: driver.id,
+            },
+            e,
           )
-          .await
-        {
-          Ok(ProcessedResponse::Processed) => {
-            log::debug!("Driver {} configured successfully", driver.name);
-          }
-          Ok(ProcessedResponse::Failed) => {
-            panic!("Driver {} configuration failed", driver.name);
-          }
-          Err(e) => {
-            panic!("Error configuring driver {}: {}", driver.name, e);
-          }
-        }
+        })?;
+        log::debug!("Driver {} configured successfully", driver.name);
       }
     }
+    Ok(())
   }
 
   pub async fn reset_expansion_boards(
     exp_port: &mut SerialInterface,
     expansion_boards: &Vec<ExpansionBoardDefinition>,
-  ) {
+  ) -> Result<(), BootError> {
     for board in expansion_boards {
       if board.breakout.is_none() {
         log::info!("Resetting expansion board at address {:X}", board.address);
-        match exp_port
-          .request(
-            &BoardResetCommand::new(board.address),
-            Duration::from_millis(2000),
+        request_processed(
+          exp_port,
+          &BoardResetCommand::new(board.address),
+          Duration::from_millis(2000),
+        )
+        .await
+        .map_err(|e| {
+          BootError::request(
+            HardwareTarget::ExpansionBoard {
+              address: board.address,
+            },
+            e,
           )
-          .await
-        {
-          Ok(ProcessedResponse::Processed) => {
-            log::debug!("Expansion board {:X} reset successfully", board.address);
-          }
-          Ok(ProcessedResponse::Failed) => {
-            panic!(
-              "Expansion board {:X} reset failed. Is this configured correctly?",
-              board.address
-            );
-          }
-          Err(e) => {
-            panic!("Error resetting expansion board {:X}: {}", board.address, e);
-          }
-        }
+        })?;
+        log::debug!("Expansion board {:X} reset successfully", board.address);
       }
     }
+    Ok(())
   }
 
-  async fn configure_led_ports(
+  pub(crate) async fn configure_led_ports(
     exp_port: &mut SerialInterface,
     expansion_boards: &Vec<ExpansionBoardDefinition>,
-  ) {
+  ) -> Result<(), BootError> {
     for board in expansion_boards {
       for led_port in &board.led_ports {
         let cmd = ConfigureLedPortCommand::new(
@@ -235,26 +634,92 @@ impl MachineBuilder {
           led_port.leds.len() as u8,
         );
         // configure port/block
-        let _ = exp_port.request(&cmd, Duration::from_millis(250)).await;
+        request_processed(exp_port, &cmd, Duration::from_millis(250))
+          .await
+          .map_err(|e| {
+            BootError::request(
+              HardwareTarget::LedPort {
+                address: board.address,
+                breakout: board.breakout,
+                port: led_port.port,
+              },
+              e,
+            )
+          })?;
       }
     }
+    Ok(())
   }
 
-  /// Map a keyboard key to a switch for emulated switch triggering
-  pub fn add_keyboard_mapping(mut self, key: KeyCode, switch_name: &'static str) -> Self {
-    let switch = self.switches.switch_by_name(switch_name).expect(&format!(
-      "Keyboard mapped switch '{}' not found.",
-      switch_name
-    ));
-    self.keyboard_switch_map.insert(key, switch.id);
-    self
+  /// Set the travel of each servo and park it at home
+  async fn configure_servos(
+    exp_port: &mut SerialInterface,
+    expansion_boards: &[ExpansionBoardDefinition],
+  ) -> Result<(), BootError> {
+    for board in expansion_boards {
+      for servo in &board.servos {
+        log::info!("Configuring servo {} with {:?}", servo.name, servo);
+        request_processed(
+          exp_port,
+          &ConfigureServoCommand::new(
+            board.address,
+            board.breakout,
+            servo.port,
+            servo.min,
+            servo.max,
+            servo.home,
+          ),
+          Duration::from_millis(500),
+        )
+        .await
+        .map_err(|e| {
+          BootError::request(
+            HardwareTarget::Servo {
+              name: servo.name,
+              address: board.address,
+            },
+            e,
+          )
+        })?;
+        exp_port.dispatch(&MoveServoCommand::new(
+          board.address,
+          board.breakout,
+          servo.port,
+          servo.home,
+          Duration::ZERO,
+        ));
+      }
+    }
+    Ok(())
   }
 
-  pub fn add_keyboard_mappings(mut self, mappings: Vec<(KeyCode, &'static str)>) -> Self {
-    for (key, switch_name) in mappings {
-      self = self.add_keyboard_mapping(key, switch_name);
+  /// Count stepper positions from where each stepper is at boot. Steppers with a home switch are found with
+  /// `cmds.stepper.home` once the machine is running.
+  async fn home_steppers(
+    exp_port: &mut SerialInterface,
+    expansion_boards: &[ExpansionBoardDefinition],
+  ) -> Result<(), BootError> {
+    for board in expansion_boards {
+      for stepper in &board.steppers {
+        log::info!("Homing stepper {} with {:?}", stepper.name, stepper);
+        request_processed(
+          exp_port,
+          &HomeStepperCommand::new(board.address, board.breakout, stepper.port),
+          Duration::from_millis(500),
+        )
+        .await
+        .map_err(|e| {
+          BootError::request(
+            HardwareTarget::Stepper {
+              name: stepper.name,
+              address: board.address,
+            },
+            e,
+          )
+        })?;
+      }
     }
-    self
+    Ok(())
   }
 
   /// Add a virtual switch that can be triggered by a keyboard key which is not backed by a hardware switch.
@@ -282,6 +747,99 @@ impl MachineBuilder {
     self
   }
 
+  /// Name a set of LEDs so systems can light them all with one declaration, e.g. GI or a bank of inserts. An LED
+  /// declared by its own name ignores declarations for its groups.
+  pub fn add_led_group<L: LedId>(mut self, name: impl LedId, leds: Vec<L>) -> Self {
+    let name = name.led_name();
+    let known_leds: Vec<&'static str> = self
+      .expansion_boards
+      .iter()
+      .flat_map(|board| &board.led_ports)
+      .flat_map(|led_port| led_port.leds.iter().copied())
+      .collect();
+    if known_leds.contains(&name) {
+      panic!("LED group '{}' has the same name as an LED.", name);
+    }
+    if self.led_groups.contains_key(name)
+      || led_port_groups(&self.expansion_boards).contains_key(name)
+    {
+      panic!("LED group '{}' is already defined.", name);
+    }
+
+    let leds: Vec<&'static str> = leds.iter().map(|led| led.led_name()).collect();
+    for led in &leds {
+      if !known_leds.contains(led) {
+        panic!("LED group '{}' LED '{}' not found.", name, led);
+      }
+    }
+
+    self.led_groups.insert(name, leds);
+    self
+  }
+
+  pub fn add_led_groups(mut self, groups: Vec<(&'static str, Vec<&'static str>)>) -> Self {
+    for (name, leds) in groups {
+      self = self.add_led_group(name, leds);
+    }
+    self
+  }
+
+  /// Let the hardware fire a driver when a switch changes, enabled only in some game phases. See `HardwareRule`.
+  pub fn add_hardware_rule(mut self, rule: HardwareRule) -> Self {
+    if !self.driver_lookup.contains_key(rule.driver) {
+      panic!("Hardware rule driver '{}' not found.", rule.driver);
+    }
+    if self.switches.switch_by_name(rule.switch).is_none() {
+      panic!("Hardware rule switch '{}' not found.", rule.switch);
+    }
+    for switch in rule.mode.switches() {
+      if self.switches.switch_by_name(switch).is_none() {
+        panic!(
+          "Hardware rule for driver '{}' uses switch '{}' which was not found.",
+          rule.driver, switch
+        );
+      }
+    }
+    if let Some(flipper) = self
+      .flippers
+      .iter()
+      .find(|f| f.drivers().contains(&rule.driver))
+    {
+      panic!(
+        "Driver '{}' is already used by flipper '{}'.",
+        rule.driver, flipper.name
+      );
+    }
+    if let Some(existing) = self.hardware_rules.iter().find(|r| r.driver == rule.driver) {
+      panic!(
+        "Driver '{}' already has a hardware rule for switch '{}'.",
+        rule.driver, existing.switch
+      );
+    }
+
+    let mode = rule.mode.to_config(&self.switches).mode();
+    if mode != 0
+      && let Some(problem) = self
+        .mainboard
+        .unsupported(HardwareTarget::Mainboard, Capability::DriverMode(mode))
+    {
+      if self.refuse_incompatible_firmware {
+        panic!("Hardware rule for driver '{}': {}", rule.driver, problem);
+      }
+      log::warn!("Hardware rule for driver '{}': {}", rule.driver, problem);
+    }
+
+    self.hardware_rules.push(rule);
+    self
+  }
+
+  pub fn add_hardware_rules(mut self, rules: Vec<HardwareRule>) -> Self {
+    for rule in rules {
+      self = self.add_hardware_rule(rule);
+    }
+    self
+  }
+
   pub fn add_config_item(mut self, key: &'static str, item: ConfigItem) -> Self {
     self.config.add_item(key, item);
     self
@@ -307,7 +865,84 @@ impl MachineBuilder {
       self.config,
       self.io_boards,
       self.expansion_boards,
-      self.driver_groups,
+      HardwareSetup {
+        driver_groups: self.driver_groups,
+        led_groups: self.led_groups,
+        hardware_rules: self.hardware_rules,
+        flippers: self.flippers,
+      },
+      self.platform,
+      self.watchdog_blocked,
     )
   }
+
+  /// The boards found on the IO NET loop at boot, and how they differ from the defined boards
+  pub fn io_inventory(&self) -> &IoInventory {
+    &self.io_inventory
+  }
+
+  /// The breakouts found on the declared expansion boards at boot, and how they differ from the definitions
+  pub fn expansion_inventory(&self) -> &ExpansionInventory {
+    &self.expansion_inventory
+  }
+}
+
+/// Send a command which the hardware answers with `P` or `F`, treating `F` as an error
+async fn request_processed<C: FastCommand<Response = ProcessedResponse>>(
+  port: &SerialInterface,
+  cmd: &C,
+  timeout: Duration,
+) -> Result<(), RequestError> {
+  match port.request_with_raw(cmd, timeout).await? {
+    (ProcessedResponse::Processed, _) => Ok(()),
+    (ProcessedResponse::Failed, raw) => Err(RequestError::new(
+      &cmd.to_string(),
+      Some(raw),
+      FastResponseError::Failed,
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::machine::expansion_inventory::ExpansionMismatch;
+  use fast_emulator::{EmulatedExpansionBoard, NeuronEmulator};
+
+  #[tokio::test]
+  async fn test_verify_expansion_network_reports_undeclared_boards() {
+    let (_emulator, _io_net, exp) = NeuronEmulator::new()
+      .with_expansion_board(EmulatedExpansionBoard::new(0x90, "FP-EXP-0061"))
+      .spawn();
+    let mut exp_port = SerialInterface::from_transport(EXP_CHANNEL, exp)
+      .await
+      .unwrap();
+
+    let declared = vec![ExpansionBoardDefinition::neutron()];
+    let inventory = MachineBuilder::verify_expansion_network(&mut exp_port, &declared)
+      .await
+      .unwrap();
+
+    assert_eq!(
+      inventory.mismatches,
+      vec![ExpansionMismatch::UndeclaredBreakout {
+        address: 0x90,
+        breakout: 0
+      }]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_verify_expansion_network_ignores_undeclared_neuron() {
+    let (_emulator, _io_net, exp) = NeuronEmulator::new().spawn();
+    let mut exp_port = SerialInterface::from_transport(EXP_CHANNEL, exp)
+      .await
+      .unwrap();
+
+    let inventory = MachineBuilder::verify_expansion_network(&mut exp_port, &[])
+      .await
+      .unwrap();
+
+    assert!(inventory.is_match());
+  }
 }
@@ -2,26 +2,55 @@ use crossterm::event::Event;
 
 use crate::machine::event::FrontboxEvent;
 use crate::prelude::*;
-use fast_protocol::EventResponse;
+use fast_protocol::{
+  EventResponse, MotorDirection, StepperStatusResponse, SwitchState, WatchdogResponse,
+};
 
 pub enum MachineCommand {
   // game management
   StartGame,
   EndGame,
+  ResumeGame,
   AddPlayer,
   AdvancePlayer,
 
   // hardware
   ConfigureDriver(&'static str, Box<dyn DriverMode + Send>),
+  /// Change a switch's stored configuration and send it to the hardware
+  ConfigureSwitch(&'static str, Box<dyn FnOnce(&mut SwitchConfig) + Send>),
+  WatchSwitchHeld(&'static str, Duration),
+  UnwatchSwitchHeld(&'static str, Duration),
   TriggerDriver(&'static str, DriverTriggerControlMode, Option<Duration>),
   TriggerDriverGroup(&'static str, DriverTriggerControlMode, Option<Duration>),
   HardwareEvent(EventResponse),
+  SwitchReport(Vec<SwitchState>),
   Key(Event),
   ResetExpansionNetwork,
+  MoveServo(&'static str, u8, Duration),
+  HomeServo(&'static str),
+  /// Configure the servos of the expansion board at the given address and park them at home, e.g. after a reset
+  ParkServos(u8),
+  RehomeSteppers(u8),
+  HomeStepper(&'static str),
+  MoveStepperTo(&'static str, &'static str),
+  MoveStepperBy(&'static str, i32),
+  RotateStepper(&'static str, f32),
+  StopStepper(&'static str),
+  RunMotor(&'static str, MotorDirection, u8, Duration),
+  RunMotorTo(&'static str, &'static str, MotorDirection, u8),
+  StopMotor(&'static str),
+  StopMotors,
+  EnableFlippers,
+  DisableFlippers,
+  /// Response to polling a moving stepper, for the numbered move. `None` if the board didn't respond.
+  StepperStatus(&'static str, u64, Option<StepperStatusResponse>),
 
   // timers
   SystemTick,
   WatchdogTick,
+  /// What the hardware reported before a watchdog refresh (`None` if it didn't respond), and whether the refresh
+  /// was accepted
+  WatchdogStatus(Option<WatchdogResponse>, bool),
 
   // other
   StateTransition(Box<dyn FnOnce(&mut States) + Send>),
@@ -36,9 +65,17 @@ impl std::fmt::Debug for MachineCommand {
       Self::EmitEvent(event) => write!(f, "EmitEvent({:?})", event),
       Self::StartGame => write!(f, "StartGame"),
       Self::EndGame => write!(f, "EndGame"),
+      Self::ResumeGame => write!(f, "ResumeGame"),
       Self::AddPlayer => write!(f, "AddPlayer"),
       Self::AdvancePlayer => write!(f, "AdvancePlayer"),
       Self::ConfigureDriver(name, _mode) => write!(f, "ConfigureDriver({:?}, ...)", name),
+      Self::ConfigureSwitch(name, _f) => write!(f, "ConfigureSwitch({:?}, ...)", name),
+      Self::WatchSwitchHeld(name, duration) => {
+        write!(f, "WatchSwitchHeld({:?}, {:?})", name, duration)
+      }
+      Self::UnwatchSwitchHeld(name, duration) => {
+        write!(f, "UnwatchSwitchHeld({:?}, {:?})", name, duration)
+      }
       Self::TriggerDriver(name, mode, delay) => {
         write!(f, "TriggerDriver({:?}, {:?}, {:?})", name, mode, delay)
       }
@@ -48,10 +85,46 @@ impl std::fmt::Debug for MachineCommand {
       Self::SetConfigValue(key, value) => write!(f, "SetConfigValue({}, {:?})", key, value),
       Self::SystemTick => write!(f, "SystemTick"),
       Self::WatchdogTick => write!(f, "WatchdogTick"),
+      Self::WatchdogStatus(remaining, refreshed) => {
+        write!(f, "WatchdogStatus({:?}, {})", remaining, refreshed)
+      }
       Self::HardwareEvent(event) => write!(f, "HardwareEvent({:?})", event),
+      Self::SwitchReport(switches) => write!(f, "SwitchReport({:?})", switches),
       Self::Key(key_event) => write!(f, "Key({:?})", key_event),
       Self::Shutdown => write!(f, "Shutdown"),
       Self::ResetExpansionNetwork => write!(f, "ResetExpansionNetwork"),
+      Self::MoveServo(name, position, time) => {
+        write!(f, "MoveServo({:?}, {}, {:?})", name, position, time)
+      }
+      Self::HomeServo(name) => write!(f, "HomeServo({:?})", name),
+      Self::ParkServos(address) => write!(f, "ParkServos({:X})", address),
+      Self::RehomeSteppers(address) => write!(f, "RehomeSteppers({:X})", address),
+      Self::HomeStepper(name) => write!(f, "HomeStepper({:?})", name),
+      Self::MoveStepperTo(name, position) => {
+        write!(f, "MoveStepperTo({:?}, {:?})", name, position)
+      }
+      Self::MoveStepperBy(name, steps) => write!(f, "MoveStepperBy({:?}, {})", name, steps),
+      Self::RotateStepper(name, revolutions) => {
+        write!(f, "RotateStepper({:?}, {})", name, revolutions)
+      }
+      Self::StopStepper(name) => write!(f, "StopStepper({:?})", name),
+      Self::RunMotor(name, direction, speed, time) => write!(
+        f,
+        "RunMotor({:?}, {:?}, {}, {:?})",
+        name, direction, speed, time
+      ),
+      Self::RunMotorTo(name, position, direction, speed) => write!(
+        f,
+        "RunMotorTo({:?}, {:?}, {:?}, {})",
+        name, position, direction, speed
+      ),
+      Self::StopMotor(name) => write!(f, "StopMotor({:?})", name),
+      Self::StopMotors => write!(f, "StopMotors"),
+      Self::EnableFlippers => write!(f, "EnableFlippers"),
+      Self::DisableFlippers => write!(f, "DisableFlippers"),
+      Self::StepperStatus(name, move_id, status) => {
+        write!(f, "StepperStatus({:?}, {}, {:?})", name, move_id, status)
+      }
       Self::StateTransition(_) => write!(f, "StateTransition(...)"),
     }
   }
@@ -83,6 +83,7 @@ impl MachineConfig {
 
 pub mod default_config {
   pub const WATCHDOG_TICK: &str = "watchdog.tick_ms";
+  pub const WATCHDOG_TIMEOUT: &str = "watchdog.timeout_ms";
   pub const SYSTEM_TIMER_TICK: &str = "system.timer_tick_ms";
   pub const LED_RENDERER_TICK: &str = "led.renderer_tick_ms";
 }
@@ -103,6 +104,18 @@ impl Default for MachineConfig {
       },
     );
 
+    config.add_item(
+      default_config::WATCHDOG_TIMEOUT,
+      ConfigItem::Integer {
+        current: 1250,
+        min: 100,
+        max: 10000,
+        default: 1250,
+        name: "Watchdog Timeout (ms)",
+        description: "How long the hardware keeps high voltage on after each watchdog refresh. The difference from the watchdog tick is the margin for a late refresh.",
+      },
+    );
+
     config.add_item(
       default_config::SYSTEM_TIMER_TICK,
       ConfigItem::Integer {
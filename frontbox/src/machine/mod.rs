@@ -1,14 +1,21 @@
+pub mod boot_error;
 pub mod config_value;
 pub mod context;
 pub mod event;
+pub mod expansion_inventory;
 mod fast_codec;
+pub mod firmware_check;
+pub mod io_inventory;
 pub mod key_reader;
 pub mod machine;
 pub mod machine_builder;
 pub mod machine_command;
 pub mod machine_config;
+mod outbound_queue;
 pub mod plugin;
 pub mod serial_interface;
+pub mod serial_recorder;
 pub mod store;
 pub mod switch_context;
+pub mod transport;
 pub mod watchdog;
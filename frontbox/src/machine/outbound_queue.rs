@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Most LEDs set by a single coalesced `RS:` command
+const MAX_LEDS_PER_COMMAND: usize = 24;
+
+/// How long a burst the link may send at full speed, as a fraction of a second
+const BURST_FRACTION: u32 = 10;
+
+/// Share of the burst which LED traffic leaves free, so that a busy light show never fills the link ahead of
+/// higher priority commands
+const LED_HEADROOM_FRACTION: f64 = 0.25;
+
+/// Outbound commands are sent highest priority first. Commands within the same class keep their order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CommandPriority {
+  /// Watchdog refreshes
+  Safety,
+  /// Driver and switch commands and everything not otherwise classified. These share one queue because they depend on
+  /// each other's order, e.g. a `DL:` driver rule on a switch must follow the `SL:` which configures that switch.
+  Config,
+  /// LED updates, which are throttled and coalesced when the link is saturated
+  Led,
+}
+
+impl CommandPriority {
+  pub(crate) fn of(cmd: &str) -> Self {
+    match cmd.get(..2).map(|prefix| prefix.to_uppercase()).as_deref() {
+      Some("WD") => CommandPriority::Safety,
+      Some("RS") => CommandPriority::Led,
+      _ => CommandPriority::Config,
+    }
+  }
+}
+
+pub(crate) enum NextCommand {
+  Send(String),
+  /// Only throttled commands are queued, try again at the given time
+  WaitUntil(Instant),
+  Empty,
+}
+
+/// Schedules the commands waiting to be written to a port
+pub(crate) struct OutboundQueue {
+  safety: VecDeque<String>,
+  config: VecDeque<String>,
+  /// Pending LED colors by address, e.g. `48` or `480`, in the order the address was first queued
+  leds: Vec<(String, Vec<String>)>,
+  bytes_per_second: f64,
+  burst: f64,
+  available: f64,
+  refilled_at: Instant,
+}
+
+impl OutboundQueue {
+  pub(crate) fn new(bytes_per_second: u32) -> Self {
+    let burst = (bytes_per_second / BURST_FRACTION) as f64;
+    Self {
+      safety: VecDeque::new(),
+      config: VecDeque::new(),
+      leds: Vec::new(),
+      bytes_per_second: bytes_per_second as f64,
+      burst,
+      available: burst,
+      refilled_at: Instant::now(),
+    }
+  }
+
+  pub(crate) fn push(&mut self, cmd: String) {
+    match CommandPriority::of(&cmd) {
+      CommandPriority::Safety => self.safety.push_back(cmd),
+      CommandPriority::Config => {
+        if let Some(board) = reset_board(&cmd) {
+          // colors queued before a board reset would be wiped by it anyway
+          self
+            .leds
+            .retain(|(address, _)| !address.starts_with(&board));
+        }
+        self.config.push_back(cmd)
+      }
+      CommandPriority::Led => self.push_leds(cmd),
+    }
+  }
+
+  /// Merge the colors into any update already waiting for the same address. Later colors for an LED replace
+  /// earlier ones.
+  fn push_leds(&mut self, cmd: String) {
+    let Some((address, colors)) = parse_led_command(&cmd) else {
+      // not something that can be coalesced, send it as is
+      self.config.push_back(cmd);
+      return;
+    };
+
+    let pending = match self.leds.iter_mut().find(|(a, _)| *a == address) {
+      Some((_, pending)) => pending,
+      None => {
+        self.leds.push((address, Vec::new()));
+        &mut self.leds.last_mut().unwrap().1
+      }
+    };
+
+    for color in colors {
+      let index = led_index(&color);
+      match pending.iter_mut().find(|c| led_index(c) == index) {
+        Some(existing) => *existing = color,
+        None => pending.push(color),
+      }
+    }
+  }
+
+  pub(crate) fn pop(&mut self, now: Instant) -> NextCommand {
+    self.refill(now);
+
+    let next = self.safety.pop_front().or_else(|| self.config.pop_front());
+    if let Some(cmd) = next {
+      // higher priority commands are never held back, but still count against the link
+      self.available -= cmd.len() as f64;
+      return NextCommand::Send(cmd);
+    }
+
+    let Some((address, colors)) = self.leds.first() else {
+      return NextCommand::Empty;
+    };
+    let count = colors.len().min(MAX_LEDS_PER_COMMAND);
+    let cmd = format!("RS@{}:{}\r", address, colors[..count].join(","));
+
+    let required = cmd.len() as f64 + self.burst * LED_HEADROOM_FRACTION;
+    if self.available < required {
+      let wait = (required - self.available) / self.bytes_per_second;
+      return NextCommand::WaitUntil(now + Duration::from_secs_f64(wait));
+    }
+
+    let (_, colors) = &mut self.leds[0];
+    colors.drain(..count);
+    if colors.is_empty() {
+      self.leds.remove(0);
+    }
+
+    self.available -= cmd.len() as f64;
+    NextCommand::Send(cmd)
+  }
+
+  fn refill(&mut self, now: Instant) {
+    let elapsed = now.saturating_duration_since(self.refilled_at);
+    self.available =
+      (self.available + elapsed.as_secs_f64() * self.bytes_per_second).min(self.burst);
+    self.refilled_at = now;
+  }
+}
+
+/// Split `RS@480:0FF0000,100FF00\r` into its address and individual LED colors
+fn parse_led_command(cmd: &str) -> Option<(String, Vec<String>)> {
+  let (header, payload) = cmd.trim_end_matches('\r').split_once(':')?;
+  let (_, address) = header.split_once('@')?;
+  let colors = payload
+    .split(',')
+    .filter(|c| c.len() > 6)
+    .map(|c| c.to_string())
+    .collect();
+  Some((address.to_string(), colors))
+}
+
+/// LED index of a color entry, e.g. `1A` for `1A00FF00`
+fn led_index(color: &str) -> &str {
+  &color[..color.len() - 6]
+}
+
+/// Board address of a `BR@48:` command
+fn reset_board(cmd: &str) -> Option<String> {
+  let (header, _) = cmd.split_once(':')?;
+  let (prefix, address) = header.split_once('@')?;
+  prefix
+    .eq_ignore_ascii_case("BR")
+    .then(|| address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sent(queue: &mut OutboundQueue, now: Instant) -> Vec<String> {
+    let mut sent = Vec::new();
+    while let NextCommand::Send(cmd) = queue.pop(now) {
+      sent.push(cmd);
+    }
+    sent
+  }
+
+  #[test]
+  fn test_priority_order() {
+    let mut queue = OutboundQueue::new(100_000);
+    queue.push("RS@48:0FF0000\r".to_string());
+    queue.push("SL:5,1,2,3\r".to_string());
+    queue.push("DL:0,81,5,10,1E,FF,0,0,0\r".to_string());
+    queue.push("TL:0,1\r".to_string());
+    queue.push("WD:3E8\r".to_string());
+
+    assert_eq!(
+      sent(&mut queue, Instant::now()),
+      vec![
+        "WD:3E8\r",
+        // the driver rule on switch 5 still follows the switch's configuration
+        "SL:5,1,2,3\r",
+        "DL:0,81,5,10,1E,FF,0,0,0\r",
+        "TL:0,1\r",
+        "RS@48:0FF0000\r",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_led_coalescing() {
+    let mut queue = OutboundQueue::new(100_000);
+    queue.push("RS@48:0FF0000,100FF00\r".to_string());
+    queue.push("RS@49:00000FF\r".to_string());
+    queue.push("RS@48:1FFFFFF,20000FF\r".to_string());
+
+    assert_eq!(
+      sent(&mut queue, Instant::now()),
+      vec!["RS@48:0FF0000,1FFFFFF,20000FF\r", "RS@49:00000FF\r"]
+    );
+  }
+
+  #[test]
+  fn test_led_command_length_limit() {
+    let mut queue = OutboundQueue::new(100_000);
+    let colors: Vec<_> = (0..30).map(|i| format!("{:X}FF0000", i)).collect();
+    queue.push(format!("RS@48:{}\r", colors.join(",")));
+
+    let sent = sent(&mut queue, Instant::now());
+    assert_eq!(
+      sent,
+      vec![
+        format!("RS@48:{}\r", colors[..MAX_LEDS_PER_COMMAND].join(",")),
+        format!("RS@48:{}\r", colors[MAX_LEDS_PER_COMMAND..].join(",")),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_board_reset_drops_queued_leds() {
+    let mut queue = OutboundQueue::new(100_000);
+    queue.push("RS@480:0FF0000\r".to_string());
+    queue.push("RS@49:0FF0000\r".to_string());
+    queue.push("BR@48:\r".to_string());
+    queue.push("RS@481:0FF0000\r".to_string());
+
+    assert_eq!(
+      sent(&mut queue, Instant::now()),
+      vec!["BR@48:\r", "RS@49:0FF0000\r", "RS@481:0FF0000\r"]
+    );
+  }
+
+  #[test]
+  fn test_led_rate_limit() {
+    // a 100 byte burst, of which LEDs may use 75
+    let mut queue = OutboundQueue::new(1_000);
+    let start = Instant::now();
+    for address in 0..10 {
+      queue.push(format!("RS@4{}:0FF0000\r", address));
+    }
+
+    let burst = sent(&mut queue, start);
+    assert_eq!(burst.len(), 5);
+    let NextCommand::WaitUntil(deadline) = queue.pop(start) else {
+      panic!("LEDs should be held back once the burst is used up");
+    };
+    assert!(deadline > start);
+
+    // higher priority commands still go out straight away
+    queue.push("TL:0,1\r".to_string());
+    assert!(matches!(queue.pop(start), NextCommand::Send(cmd) if cmd == "TL:0,1\r"));
+
+    // the wait is long enough for the next LED command
+    let deadline = match queue.pop(start) {
+      NextCommand::WaitUntil(deadline) => deadline,
+      _ => panic!("LEDs should still be held back"),
+    };
+    assert!(matches!(queue.pop(deadline), NextCommand::Send(_)));
+    assert_eq!(sent(&mut queue, start + Duration::from_secs(1)).len(), 4);
+  }
+}
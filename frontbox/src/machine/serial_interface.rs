@@ -1,36 +1,127 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use futures_util::StreamExt;
 use tokio::io::{AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tokio_serial::{DataBits, FlowControl, Parity, SerialStream, StopBits};
 use tokio_util::codec::FramedRead;
 
 use crate::machine::fast_codec::FastRawCodec;
+use crate::machine::outbound_queue::{NextCommand, OutboundQueue};
+use crate::machine::serial_recorder::{Direction, SerialRecorder};
+use crate::machine::transport::Transport;
 use fast_protocol::FastCommand;
 use fast_protocol::RawResponse;
-use fast_protocol::{EventResponse, FastResponseError};
+use fast_protocol::{EventResponse, FastResponseError, RequestError};
 
 const BAUD_RATE: u32 = 921_600;
+// 8N1 framing sends 10 bits per byte
+const LINK_BYTES_PER_SECOND: u32 = BAUD_RATE / 10;
 
+static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+type FramedReader = FramedRead<ReadHalf<Box<dyn Transport>>, FastRawCodec>;
+type Recorder = Arc<Mutex<Option<(SerialRecorder, &'static str)>>>;
+
+/// Requests awaiting a response, queued by response prefix in the order they were sent
+type InFlightRequests = Arc<Mutex<HashMap<String, VecDeque<InFlight>>>>;
+
+struct InFlight {
+  id: u64,
+  address: Option<String>,
+  sender: oneshot::Sender<RawResponse>,
+}
+
+/// Removes a request from the in-flight queue if it is dropped before a response arrives, e.g. after timing out, so
+/// that a later response isn't handed to a request nobody is waiting on
+struct InFlightGuard {
+  id: u64,
+  prefix: String,
+  in_flight: InFlightRequests,
+}
+
+impl Drop for InFlightGuard {
+  fn drop(&mut self) {
+    let mut in_flight = self.in_flight.lock().unwrap();
+    if let Some(queue) = in_flight.get_mut(&self.prefix) {
+      queue.retain(|request| request.id != self.id);
+    }
+  }
+}
+
+/// Reader and writer tasks for the current connection, replaced on reconnect
+struct Link {
+  outbound: mpsc::UnboundedSender<String>,
+  events: mpsc::UnboundedReceiver<RawResponse>,
+  connected: Arc<AtomicBool>,
+  reader_task: JoinHandle<()>,
+}
+
+impl Drop for Link {
+  fn drop(&mut self) {
+    self.reader_task.abort();
+  }
+}
+
+/// A FAST serial port. Reading and writing happen on background tasks, so any number of requests can be in flight at
+/// once. Responses are matched back to their request by prefix and, where the response has one, address. Anything
+/// which doesn't answer a request is queued as an event for `read_event`. Outgoing commands are written by priority,
+/// with LED updates throttled to what the link can carry (see `OutboundQueue`).
 pub struct SerialInterface {
   port_name: String,
-  reader: FramedRead<ReadHalf<SerialStream>, FastRawCodec>,
-  writer: WriteHalf<SerialStream>,
-  event_queue: VecDeque<RawResponse>,
+  port_path: Option<String>,
+  link: Link,
+  in_flight: InFlightRequests,
+  recorder: Recorder,
 }
 
 impl SerialInterface {
   pub async fn new(port_path: &str) -> tokio_serial::Result<Self> {
+    let port = Self::open_serial_port(port_path)?;
+    let mut interface = Self::from_transport(port_path, port).await?;
+    interface.port_path = Some(port_path.to_string());
+    Ok(interface)
+  }
+
+  fn open_serial_port(port_path: &str) -> tokio_serial::Result<SerialStream> {
     let port = tokio_serial::new(port_path, BAUD_RATE)
       .data_bits(DataBits::Eight)
       .parity(Parity::None)
       .stop_bits(StopBits::One)
       .flow_control(FlowControl::None);
 
-    let port = SerialStream::open(&port)?;
+    SerialStream::open(&port)
+  }
+
+  /// Speak the FAST protocol over an arbitrary transport instead of a serial device, e.g. an in-memory duplex
+  /// pipe or a TCP socket. `name` is only used for logging.
+  pub async fn from_transport(name: &str, transport: impl Transport) -> tokio_serial::Result<Self> {
+    let in_flight: InFlightRequests = Arc::new(Mutex::new(HashMap::new()));
+    let recorder: Recorder = Arc::new(Mutex::new(None));
+    let link = Self::connect(name, Box::new(transport), &in_flight, &recorder).await?;
+
+    Ok(SerialInterface {
+      port_name: name.to_string(),
+      port_path: None,
+      link,
+      in_flight,
+      recorder,
+    })
+  }
 
-    let (reader, mut writer) = tokio::io::split(port);
+  async fn connect(
+    name: &str,
+    transport: Box<dyn Transport>,
+    in_flight: &InFlightRequests,
+    recorder: &Recorder,
+  ) -> tokio_serial::Result<Link> {
+    let (reader, mut writer) = tokio::io::split(transport);
 
     // before this port starts reading, send a bunch of carriage returns to clear out any junk in the buffer.
     // https://fastpinball.com/programming/framework/exp/#clear-out-the-serial-buffer
@@ -40,7 +131,7 @@ impl SerialInterface {
 
     // poll reader until there is no unexpected messages
     // this also clears out anything that was from a prior run
-    log::trace!("Draining serial buffer on {} before continuing", port_path);
+    log::trace!("Draining serial buffer on {} before continuing", name);
     let drain_timeout = Duration::from_millis(300); // Adjust as needed
     loop {
       match tokio::time::timeout(drain_timeout, framed_reader.next()).await {
@@ -49,116 +140,205 @@ impl SerialInterface {
       }
     }
 
-    Ok(SerialInterface {
-      port_name: port_path.to_string(),
-      reader: framed_reader,
+    let connected = Arc::new(AtomicBool::new(true));
+    let (outbound_sender, outbound_receiver) = mpsc::unbounded_channel();
+    let (events_sender, events_receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(write_port(
+      name.to_string(),
       writer,
-      event_queue: VecDeque::new(),
+      outbound_receiver,
+      connected.clone(),
+    ));
+    let reader_task = tokio::spawn(read_port(
+      name.to_string(),
+      framed_reader,
+      in_flight.clone(),
+      events_sender,
+      connected.clone(),
+      recorder.clone(),
+    ));
+
+    Ok(Link {
+      outbound: outbound_sender,
+      events: events_receiver,
+      connected,
+      reader_task,
     })
   }
 
-  pub async fn read_event(&mut self) -> Option<EventResponse> {
-    match self.read().await {
-      Some(Ok(raw)) => EventResponse::parse(raw).ok(),
-      _ => None,
-    }
+  /// False once reading or writing has failed, e.g. the USB cable was unplugged
+  pub fn is_connected(&self) -> bool {
+    self.link.connected.load(Ordering::Relaxed)
   }
 
-  async fn read(&mut self) -> Option<tokio_serial::Result<RawResponse>> {
-    let resp = {
-      // first drain any queued events
-      // this can happen when we read a message that isn't a response to a command, but is instead an event (like a switch change)
-      if let Some(event) = self.event_queue.pop_front() {
-        return Some(Ok(event));
-      }
-
-      // otherwise read from the serial port
-      self.reader.next().await.map(|result| {
-        result.map_err(|e| {
-          tokio_serial::Error::new(tokio_serial::ErrorKind::Io(e.kind()), e.to_string())
-        })
-      })
+  /// Reopen the serial port after the connection was lost. Ports created from a transport cannot be reopened.
+  pub async fn reconnect(&mut self) -> tokio_serial::Result<()> {
+    let Some(port_path) = self.port_path.clone() else {
+      return Err(tokio_serial::Error::new(
+        tokio_serial::ErrorKind::NoDevice,
+        format!(
+          "{} is not a serial port and cannot be reopened",
+          self.port_name
+        ),
+      ));
     };
 
-    match &resp {
-      Some(Ok(raw)) if raw.prefix == "WD" => {
-        log::trace!("👾 -> 🖥️ : {}:{}", raw.prefix, raw.payload)
-      }
-      Some(Ok(raw)) => {
-        log::debug!("👾 -> 🖥️ : {}:{}", raw.prefix, raw.payload)
-      }
-      _ => {}
-    }
+    let port = Self::open_serial_port(&port_path)?;
+    self.link = Self::connect(&port_path, Box::new(port), &self.in_flight, &self.recorder).await?;
+    log::info!("Reconnected to {}", self.port_name);
+    Ok(())
+  }
 
-    resp
+  /// Write all traffic on this port to the recorder from now on, labeled with `channel`
+  pub fn record(&mut self, recorder: SerialRecorder, channel: &'static str) {
+    *self.recorder.lock().unwrap() = Some((recorder, channel));
+  }
+
+  /// Wait for the next message from the hardware which was not a response to a request
+  pub async fn read_event(&mut self) -> Option<EventResponse> {
+    let raw = self.link.events.recv().await?;
+    EventResponse::parse(raw).ok()
   }
 
   // Send off a command without concern for a response
-  async fn send(&mut self, cmd: &str) {
+  fn send(&self, cmd: &str) {
+    if !self.is_connected() {
+      log::debug!("Not connected to {}, dropping {}", self.port_name, cmd);
+      return;
+    }
+
     if cmd.starts_with("WD:") {
       log::trace!("🖥️ -> 👾 : {}", cmd);
     } else {
       log::debug!("🖥️ -> 👾 : {}", cmd);
     }
 
-    match self.writer.write_all(cmd.as_bytes()).await {
-      Ok(_) => (),
-      Err(e) => {
-        log::error!("Failed to send on {}: {:?}", self.port_name, e);
-      }
+    if let Some((recorder, channel)) = self.recorder.lock().unwrap().as_ref() {
+      recorder.record(channel, Direction::Outbound, cmd);
     }
+
+    self.link.outbound.send(cmd.to_string()).ok();
   }
 
-  pub async fn dispatch<C: FastCommand>(&mut self, cmd: &C) {
-    self.send(&cmd.to_string()).await
+  /// Send a command without waiting on a response. Sent in order with any other commands of the same priority.
+  pub fn dispatch<C: FastCommand>(&self, cmd: &C) {
+    self.send(&cmd.to_string())
   }
 
   /// Send a command and wait for a response to that command
   pub async fn request<C: FastCommand>(
-    &mut self,
+    &self,
     cmd: &C,
     timeout: Duration,
-  ) -> Result<C::Response, FastResponseError> {
-    self.dispatch(cmd).await;
-
-    tokio::time::timeout(timeout, async {
-      loop {
-        match self.read().await {
-          Some(Ok(response)) => {
-            if response.prefix.to_lowercase() == C::prefix() {
-              return cmd.parse(response);
-            } else {
-              // If the response doesn't match the prefix, it's likely an event that should be queued for reading by a different process
-              self.event_queue.push_back(response);
-            }
-          }
-          Some(Err(e)) => {
-            log::error!("Error reading response: {:?}", e);
-            return Err(FastResponseError::UnknownResponse); // ???
-          }
-          None => {
-            log::error!("Serial stream ended unexpectedly");
-            return Err(FastResponseError::UnknownResponse);
-          }
-        }
+  ) -> Result<C::Response, RequestError> {
+    self
+      .request_with_raw(cmd, timeout)
+      .await
+      .map(|(response, _)| response)
+  }
+
+  /// Like `request`, also returning the response line as received, e.g. to report a `BR:F` in full
+  pub async fn request_with_raw<C: FastCommand>(
+    &self,
+    cmd: &C,
+    timeout: Duration,
+  ) -> Result<(C::Response, RawResponse), RequestError> {
+    let cmd_string = cmd.to_string();
+    let raw = self.send_tracked::<C>(cmd_string.clone(), timeout).await?;
+    let response = parse_response(cmd, &cmd_string, raw.clone())?;
+    Ok((response, raw))
+  }
+
+  /// Send a command without waiting on the response. The returned future resolves to the response and does not
+  /// borrow the interface, so it can be spawned or awaited later while other requests are sent.
+  pub fn start_request<C>(
+    &self,
+    cmd: C,
+    timeout: Duration,
+  ) -> impl Future<Output = Result<C::Response, RequestError>> + Send + 'static
+  where
+    C: FastCommand + Send + Sync + 'static,
+    C::Response: Send,
+  {
+    let cmd_string = cmd.to_string();
+    let response = self.send_tracked::<C>(cmd_string.clone(), timeout);
+    async move {
+      let response = response.await?;
+      parse_response(&cmd, &cmd_string, response)
+    }
+  }
+
+  /// Register the command as in flight, then send it
+  fn send_tracked<C: FastCommand>(
+    &self,
+    cmd: String,
+    timeout: Duration,
+  ) -> impl Future<Output = Result<RawResponse, RequestError>> + Send + 'static {
+    let connected = self.is_connected();
+    let prefix = C::prefix().to_uppercase();
+    let (sender, receiver) = oneshot::channel();
+    let guard = InFlightGuard {
+      id: REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+      prefix: prefix.clone(),
+      in_flight: self.in_flight.clone(),
+    };
+
+    if connected {
+      self
+        .in_flight
+        .lock()
+        .unwrap()
+        .entry(prefix)
+        .or_default()
+        .push_back(InFlight {
+          id: guard.id,
+          address: command_address(&cmd),
+          sender,
+        });
+      self.send(&cmd);
+    }
+
+    async move {
+      if !connected {
+        return Err(RequestError::new(
+          &cmd,
+          None,
+          FastResponseError::Disconnected,
+        ));
       }
-    })
-    .await
-    .unwrap_or_else(|_| Err(FastResponseError::Timeout))
+
+      let response = tokio::time::timeout(timeout, receiver).await;
+      drop(guard);
+      match response {
+        Ok(Ok(response)) => Ok(response),
+        // the connection was lost while waiting
+        Ok(Err(_)) => Err(RequestError::new(
+          &cmd,
+          None,
+          FastResponseError::Disconnected,
+        )),
+        Err(_) => Err(RequestError::new(&cmd, None, FastResponseError::Timeout)),
+      }
+    }
   }
 
-  /// Keep sending the command until a response comes in
+  /// Keep sending the command until a response comes in. Gives up if the connection is lost.
   pub async fn request_until_match<C: FastCommand, R>(
-    &mut self,
+    &self,
     cmd: C,
     timeout: Duration,
     f: fn(C::Response) -> Option<R>,
-  ) -> R {
+  ) -> Result<R, RequestError> {
     loop {
-      if let Ok(response) = self.request(&cmd, timeout).await {
-        if let Some(result) = f(response) {
-          return result;
+      match self.request(&cmd, timeout).await {
+        Ok(response) => {
+          if let Some(result) = f(response) {
+            return Ok(result);
+          }
         }
+        Err(e) if e.error == FastResponseError::Disconnected => return Err(e),
+        Err(_) => {}
       }
 
       // sleep if a match wasn't found
@@ -166,3 +346,260 @@ impl SerialInterface {
     }
   }
 }
+
+fn parse_response<C: FastCommand>(
+  cmd: &C,
+  cmd_string: &str,
+  response: RawResponse,
+) -> Result<C::Response, RequestError> {
+  cmd
+    .parse(response.clone())
+    .map_err(|e| RequestError::new(cmd_string, Some(response), e))
+}
+
+/// Address a command was sent to, e.g. `48` for `BR@48:`
+fn command_address(cmd: &str) -> Option<String> {
+  let header = cmd.split(':').next()?;
+  header
+    .split_once('@')
+    .map(|(_, address)| address.to_string())
+}
+
+/// Hand a response to the oldest request waiting on its prefix. Responses which carry an address only answer a request
+/// sent to that address.
+fn take_in_flight(in_flight: &InFlightRequests, response: &RawResponse) -> Option<InFlight> {
+  let mut in_flight = in_flight.lock().unwrap();
+  let queue = in_flight.get_mut(&response.prefix.to_uppercase())?;
+  let index = match &response.address {
+    Some(address) => queue.iter().position(|request| {
+      request
+        .address
+        .as_ref()
+        .is_some_and(|a| a.eq_ignore_ascii_case(address))
+    })?,
+    None => 0,
+  };
+  queue.remove(index)
+}
+
+async fn read_port(
+  port_name: String,
+  mut reader: FramedReader,
+  in_flight: InFlightRequests,
+  events: mpsc::UnboundedSender<RawResponse>,
+  connected: Arc<AtomicBool>,
+  recorder: Recorder,
+) {
+  loop {
+    let raw = match reader.next().await {
+      Some(Ok(Ok(raw))) => raw,
+      Some(Ok(Err(line))) => {
+        log::warn!("Ignoring malformed message on {}: {:?}", port_name, line.0);
+        continue;
+      }
+      Some(Err(e)) => {
+        log::error!("Failed to read from {}: {:?}", port_name, e);
+        break;
+      }
+      None => break,
+    };
+
+    if let Some((recorder, channel)) = recorder.lock().unwrap().as_ref() {
+      recorder.record(channel, Direction::Inbound, &raw.to_string());
+    }
+
+    if raw.prefix == "WD" {
+      log::trace!("👾 -> 🖥️ : {}:{}", raw.prefix, raw.payload)
+    } else {
+      log::debug!("👾 -> 🖥️ : {}:{}", raw.prefix, raw.payload)
+    }
+
+    match take_in_flight(&in_flight, &raw) {
+      Some(request) => {
+        request.sender.send(raw).ok();
+      }
+      None => {
+        // Not a response to a request, it's likely an event (like a switch change)
+        events.send(raw).ok();
+      }
+    }
+  }
+
+  connection_lost(&port_name, &connected);
+  // fail everything still waiting on this connection
+  in_flight.lock().unwrap().clear();
+}
+
+async fn write_port(
+  port_name: String,
+  mut writer: WriteHalf<Box<dyn Transport>>,
+  mut outbound: mpsc::UnboundedReceiver<String>,
+  connected: Arc<AtomicBool>,
+) {
+  let mut queue = OutboundQueue::new(LINK_BYTES_PER_SECOND);
+
+  loop {
+    // queue everything sent so far before choosing what goes next
+    while let Ok(cmd) = outbound.try_recv() {
+      queue.push(cmd);
+    }
+
+    let cmd = match queue.pop(Instant::now()) {
+      NextCommand::Send(cmd) => cmd,
+      NextCommand::WaitUntil(deadline) => {
+        tokio::select! {
+          _ = tokio::time::sleep_until(deadline) => {}
+          cmd = outbound.recv() => match cmd {
+            Some(cmd) => queue.push(cmd),
+            None => return,
+          },
+        }
+        continue;
+      }
+      NextCommand::Empty => match outbound.recv().await {
+        Some(cmd) => {
+          queue.push(cmd);
+          continue;
+        }
+        None => return,
+      },
+    };
+
+    if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+      log::error!("Failed to send on {}: {:?}", port_name, e);
+      connection_lost(&port_name, &connected);
+      return;
+    }
+  }
+}
+
+fn connection_lost(port_name: &str, connected: &AtomicBool) {
+  if connected.swap(false, Ordering::Relaxed) {
+    log::error!("Lost connection to {}", port_name);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use fast_protocol::{BoardResetCommand, ConfigureDriverCommand, DriverConfig, ProcessedResponse};
+  use tokio::io::{AsyncReadExt, DuplexStream};
+
+  async fn connect() -> (SerialInterface, DuplexStream) {
+    let (client, server) = tokio::io::duplex(4096);
+    let port = SerialInterface::from_transport("test", client)
+      .await
+      .unwrap();
+    (port, server)
+  }
+
+  /// Next command the hardware side receives, skipping the carriage returns sent on connect
+  async fn next_command(hardware: &mut DuplexStream) -> String {
+    let mut line = Vec::new();
+    loop {
+      match hardware.read_u8().await.unwrap() {
+        b'\r' if line.is_empty() => {}
+        b'\r' => return String::from_utf8(line).unwrap(),
+        b => line.push(b),
+      }
+    }
+  }
+
+  fn in_flight_count(port: &SerialInterface, prefix: &str) -> usize {
+    port
+      .in_flight
+      .lock()
+      .unwrap()
+      .get(prefix)
+      .map_or(0, |queue| queue.len())
+  }
+
+  #[tokio::test]
+  async fn test_interleaved_responses() {
+    let (port, mut hardware) = connect().await;
+    let timeout = Duration::from_secs(1);
+    let reset_48 = port.start_request(BoardResetCommand::new(0x48), timeout);
+    let reset_49 = port.start_request(BoardResetCommand::new(0x49), timeout);
+    let driver = port.start_request(
+      ConfigureDriverCommand::new(&0, &DriverConfig::Disabled),
+      timeout,
+    );
+
+    let mut sent = Vec::new();
+    for _ in 0..3 {
+      sent.push(next_command(&mut hardware).await);
+    }
+    sent.sort();
+    assert_eq!(sent, vec!["BR@48:", "BR@49:", "DL:0,,,0"]);
+
+    // answered out of order, each response goes to the request for its address
+    hardware
+      .write_all(b"BR@49:P\rDL:P\rBR@48:F\r")
+      .await
+      .unwrap();
+    let (reset_48, reset_49, driver) = tokio::join!(reset_48, reset_49, driver);
+    assert_eq!(
+      reset_48.unwrap_err().response.unwrap().payload,
+      "F".to_string()
+    );
+    assert_eq!(reset_49.unwrap(), ProcessedResponse::Processed);
+    assert_eq!(driver.unwrap(), ProcessedResponse::Processed);
+    assert_eq!(in_flight_count(&port, "BR"), 0);
+  }
+
+  #[tokio::test]
+  async fn test_late_response_after_timeout() {
+    let (mut port, mut hardware) = connect().await;
+    let error = port
+      .request(&BoardResetCommand::new(0x48), Duration::from_millis(50))
+      .await
+      .unwrap_err();
+    assert_eq!(error.error, FastResponseError::Timeout);
+    assert_eq!(in_flight_count(&port, "BR"), 0);
+
+    // the late answer isn't handed to the next request
+    next_command(&mut hardware).await;
+    hardware.write_all(b"BR@48:F\r").await.unwrap();
+    let late = tokio::time::timeout(Duration::from_secs(1), port.link.events.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(late.payload, "F");
+
+    let retry = port.start_request(BoardResetCommand::new(0x48), Duration::from_secs(1));
+    next_command(&mut hardware).await;
+    hardware.write_all(b"BR@48:P\r").await.unwrap();
+    assert_eq!(retry.await.unwrap(), ProcessedResponse::Processed);
+  }
+
+  #[tokio::test]
+  async fn test_dropped_request() {
+    let (mut port, mut hardware) = connect().await;
+    let request = port.start_request(BoardResetCommand::new(0x48), Duration::from_secs(1));
+    assert_eq!(in_flight_count(&port, "BR"), 1);
+    drop(request);
+    assert_eq!(in_flight_count(&port, "BR"), 0);
+
+    next_command(&mut hardware).await;
+    hardware.write_all(b"BR@48:P\r").await.unwrap();
+    let unclaimed = tokio::time::timeout(Duration::from_secs(1), port.link.events.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(unclaimed.prefix, "BR");
+  }
+
+  #[tokio::test]
+  async fn test_malformed_lines_keep_the_connection() {
+    let (port, mut hardware) = connect().await;
+    let request = port.start_request(BoardResetCommand::new(0x48), Duration::from_secs(1));
+    next_command(&mut hardware).await;
+
+    hardware
+      .write_all(b"\xff\xfe\rnoise\rBR@48:P\r")
+      .await
+      .unwrap();
+    assert_eq!(request.await.unwrap(), ProcessedResponse::Processed);
+    assert!(port.is_connected());
+  }
+}
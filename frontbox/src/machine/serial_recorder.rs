@@ -0,0 +1,333 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::time::Instant;
+
+pub(crate) const IO_NET_CHANNEL: &str = "IO NET";
+pub(crate) const EXP_CHANNEL: &str = "EXP";
+
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+  /// Sent from the hardware to the host
+  Inbound,
+  /// Sent from the host to the hardware
+  Outbound,
+}
+
+impl Direction {
+  fn symbol(&self) -> &'static str {
+    match self {
+      Direction::Inbound => "<",
+      Direction::Outbound => ">",
+    }
+  }
+}
+
+/// Writes all serial traffic to a log file, one tab separated message per line: the seconds since the recording was
+/// created, the channel (`IO NET` or `EXP`), the direction (`<` from the hardware, `>` to the hardware) and the
+/// message itself. Clones write to the same file so both ports can share a single recording.
+///
+/// The file is written on a thread of its own, so a slow disk never holds up the serial ports. Everything recorded is
+/// on disk once the last clone is dropped.
+#[derive(Clone)]
+pub struct SerialRecorder {
+  started: Instant,
+  writer: Arc<RecordingWriter>,
+}
+
+impl SerialRecorder {
+  pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+    let file = File::create(path)?;
+    let (sender, receiver) = mpsc::channel();
+    let thread = std::thread::Builder::new()
+      .name("serial recorder".to_string())
+      .spawn(move || write_recording(BufWriter::new(file), receiver))?;
+
+    Ok(Self {
+      started: Instant::now(),
+      writer: Arc::new(RecordingWriter {
+        sender: Some(sender),
+        thread: Some(thread),
+      }),
+    })
+  }
+
+  pub fn record(&self, channel: &str, direction: Direction, line: &str) {
+    let elapsed = self.started.elapsed().as_secs_f64();
+    let line = format!(
+      "{:.6}\t{}\t{}\t{}",
+      elapsed,
+      channel,
+      direction.symbol(),
+      line.trim_end_matches('\r')
+    );
+
+    if let Some(sender) = &self.writer.sender
+      && sender.send(line).is_err()
+    {
+      log::error!("Serial recording stopped, the writer has failed");
+    }
+  }
+}
+
+struct RecordingWriter {
+  sender: Option<Sender<String>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for RecordingWriter {
+  fn drop(&mut self) {
+    // closing the channel lets the thread write what's left and stop
+    self.sender.take();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn write_recording(mut writer: BufWriter<File>, lines: Receiver<String>) {
+  while let Ok(line) = lines.recv() {
+    let mut result = writeln!(writer, "{}", line);
+    for line in lines.try_iter() {
+      result = result.and_then(|_| writeln!(writer, "{}", line));
+    }
+    // flush once the backlog is written so the recording survives a crash, which is usually when it's needed most
+    if let Err(e) = result.and_then(|_| writer.flush()) {
+      log::error!("Failed to write serial recording: {:?}", e);
+      return;
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedMessage {
+  pub at: Duration,
+  pub channel: String,
+  pub direction: Direction,
+  pub line: String,
+}
+
+/// A session written by `SerialRecorder`, which can be fed back into a machine as if it came from the hardware.
+///
+/// Usage:
+/// ```ignore
+/// let recording = SerialRecording::load("session.log")?;
+/// let machine = MachineBuilder::boot_with_transports(
+///   BootConfig::default(),
+///   recording.io_net(),
+///   recording.exp(),
+///   io_network,
+///   expansion_boards,
+/// )
+/// .await?
+/// .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SerialRecording {
+  messages: Vec<RecordedMessage>,
+}
+
+impl SerialRecording {
+  pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut messages = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      match Self::parse_line(&line) {
+        Some(message) => messages.push(message),
+        None => log::warn!("Skipping malformed recording line {}: {}", i + 1, line),
+      }
+    }
+
+    Ok(Self { messages })
+  }
+
+  fn parse_line(line: &str) -> Option<RecordedMessage> {
+    let mut parts = line.splitn(4, '\t');
+    let at = parts.next()?.parse::<f64>().ok()?;
+    let channel = parts.next()?.to_string();
+    let direction = match parts.next()? {
+      "<" => Direction::Inbound,
+      ">" => Direction::Outbound,
+      _ => return None,
+    };
+    let line = parts.next()?.to_string();
+
+    Some(RecordedMessage {
+      at: Duration::from_secs_f64(at),
+      channel,
+      direction,
+      line,
+    })
+  }
+
+  pub fn messages(&self) -> &[RecordedMessage] {
+    &self.messages
+  }
+
+  /// Transport which replays the recorded IO NET traffic
+  pub fn io_net(&self) -> DuplexStream {
+    self.replay(IO_NET_CHANNEL)
+  }
+
+  /// Transport which replays the recorded EXP traffic
+  pub fn exp(&self) -> DuplexStream {
+    self.replay(EXP_CHANNEL)
+  }
+
+  /// Transport which plays back everything the hardware sent on the given channel with the original timing. Anything
+  /// written to the transport is discarded. Must be called from within a tokio runtime.
+  pub fn replay(&self, channel: &str) -> DuplexStream {
+    let messages: Vec<RecordedMessage> = self
+      .messages
+      .iter()
+      .filter(|m| m.channel == channel)
+      .cloned()
+      .collect();
+    let (client, server) = tokio::io::duplex(PIPE_CAPACITY);
+    tokio::spawn(play(channel.to_string(), server, messages));
+    client
+  }
+}
+
+async fn play(channel: String, stream: DuplexStream, messages: Vec<RecordedMessage>) {
+  let (mut reader, mut writer) = tokio::io::split(stream);
+
+  // The recording starts with the first command sent by the host. Playback is anchored to the first command written
+  // to the transport so that the serial buffer drain performed on connect isn't counted against the recorded timing.
+  let first_command_at = messages
+    .iter()
+    .find(|m| m.direction == Direction::Outbound)
+    .map_or(Duration::ZERO, |m| m.at);
+  let mut buffer = [0u8; 1024];
+  let anchor = loop {
+    match reader.read(&mut buffer).await {
+      Ok(0) | Err(_) => return,
+      Ok(n) if buffer[..n].iter().any(|&b| b != b'\r') => break Instant::now(),
+      Ok(_) => continue,
+    }
+  };
+
+  log::info!(
+    "Replaying {} recorded messages on {}",
+    messages.len(),
+    channel
+  );
+  let inbound = messages
+    .into_iter()
+    .filter(|m| m.direction == Direction::Inbound);
+
+  for message in inbound {
+    let deadline = anchor + message.at.saturating_sub(first_command_at);
+
+    // keep consuming whatever the host sends so it never blocks on a full pipe
+    loop {
+      tokio::select! {
+        _ = tokio::time::sleep_until(deadline) => break,
+        read = reader.read(&mut buffer) => match read {
+          Ok(0) | Err(_) => return,
+          Ok(_) => {}
+        },
+      }
+    }
+
+    let line = format!("{}\r", message.line);
+    if writer.write_all(line.as_bytes()).await.is_err() {
+      return;
+    }
+  }
+
+  log::info!("Replay of {} finished", channel);
+
+  // hold the transport open after the recording ends, as the hardware would
+  loop {
+    match reader.read(&mut buffer).await {
+      Ok(0) | Err(_) => return,
+      Ok(_) => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_record_load_replay() {
+    let path = std::env::temp_dir().join("frontbox_serial_recorder_test.log");
+    let recorder = SerialRecorder::create(&path).unwrap();
+    let exp_recorder = recorder.clone();
+    recorder.record(IO_NET_CHANNEL, Direction::Outbound, "ID:\r");
+    exp_recorder.record(
+      EXP_CHANNEL,
+      Direction::Inbound,
+      "ID@48:EXP FP-EXP-0061 0.10\r",
+    );
+    recorder.record(
+      IO_NET_CHANNEL,
+      Direction::Inbound,
+      "ID:NET FP-CPU-2000 02.10\r",
+    );
+    recorder.record(IO_NET_CHANNEL, Direction::Inbound, "SA:P\r");
+    drop(recorder);
+    drop(exp_recorder);
+
+    // a partly written line, as left by a crash
+    let mut file = std::fs::OpenOptions::new()
+      .append(true)
+      .open(&path)
+      .unwrap();
+    write!(file, "0.5\tIO NET").unwrap();
+    drop(file);
+
+    let recording = SerialRecording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<_> = recording
+      .messages()
+      .iter()
+      .map(|m| (m.channel.as_str(), m.direction, m.line.as_str()))
+      .collect();
+    assert_eq!(
+      lines,
+      vec![
+        (IO_NET_CHANNEL, Direction::Outbound, "ID:"),
+        (
+          EXP_CHANNEL,
+          Direction::Inbound,
+          "ID@48:EXP FP-EXP-0061 0.10"
+        ),
+        (
+          IO_NET_CHANNEL,
+          Direction::Inbound,
+          "ID:NET FP-CPU-2000 02.10"
+        ),
+        (IO_NET_CHANNEL, Direction::Inbound, "SA:P"),
+      ]
+    );
+
+    // only the hardware's side of the IO NET channel comes back, once the host sends something
+    let mut transport = recording.io_net();
+    transport.write_all(b"ID:\r").await.unwrap();
+    let expected = "ID:NET FP-CPU-2000 02.10\rSA:P\r";
+    let mut replayed = vec![0u8; expected.len()];
+    tokio::time::timeout(Duration::from_secs(2), transport.read_exact(&mut replayed))
+      .await
+      .unwrap()
+      .unwrap();
+    assert_eq!(String::from_utf8(replayed).unwrap(), expected);
+  }
+}
@@ -1,14 +1,18 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::SwitchDefinition;
 use crate::prelude::*;
 use fast_protocol::SwitchState;
+use tokio::time::Instant;
 
 #[derive(Debug)]
 pub struct SwitchContext {
   by_id: HashMap<usize, Switch>,
   by_name: HashMap<&'static str, Switch>,
   is_closed: HashMap<usize, bool>,
+  /// When each switch last opened or closed
+  changed_at: HashMap<usize, Instant>,
   configs: HashMap<usize, SwitchConfig>,
 }
 
@@ -17,6 +21,7 @@ impl SwitchContext {
     let mut by_id = HashMap::new();
     let mut by_name = HashMap::new();
     let mut is_closed = HashMap::new();
+    let mut changed_at = HashMap::new();
     let mut configs = HashMap::new();
 
     for spec in switch_specs {
@@ -42,12 +47,14 @@ impl SwitchContext {
 
       // Actual state is populated below from initial report
       is_closed.insert(spec.id, false);
+      changed_at.insert(spec.id, Instant::now());
     }
 
     let mut context = Self {
       by_id,
       by_name,
       is_closed,
+      changed_at,
       configs,
     };
 
@@ -78,6 +85,47 @@ impl SwitchContext {
       .and_then(|switch| self.is_open(switch.id))
   }
 
+  /// How long the switch has been closed, zero if it's open
+  pub fn closed_for(&self, switch_id: usize) -> Option<Duration> {
+    let closed = self.is_closed(switch_id)?;
+    Some(if closed {
+      self.since_change(switch_id)
+    } else {
+      Duration::ZERO
+    })
+  }
+
+  /// How long the switch has been open, zero if it's closed
+  pub fn open_for(&self, switch_id: usize) -> Option<Duration> {
+    let closed = self.is_closed(switch_id)?;
+    Some(if closed {
+      Duration::ZERO
+    } else {
+      self.since_change(switch_id)
+    })
+  }
+
+  pub fn closed_for_by_name(&self, switch_name: &'static str) -> Option<Duration> {
+    self
+      .by_name
+      .get(switch_name)
+      .and_then(|switch| self.closed_for(switch.id))
+  }
+
+  pub fn open_for_by_name(&self, switch_name: &'static str) -> Option<Duration> {
+    self
+      .by_name
+      .get(switch_name)
+      .and_then(|switch| self.open_for(switch.id))
+  }
+
+  fn since_change(&self, switch_id: usize) -> Duration {
+    self
+      .changed_at
+      .get(&switch_id)
+      .map_or(Duration::ZERO, |at| at.elapsed())
+  }
+
   pub fn switch_by_id(&self, switch_id: &usize) -> Option<&Switch> {
     self.by_id.get(&switch_id)
   }
@@ -86,6 +134,24 @@ impl SwitchContext {
     self.by_name.get(switch_name)
   }
 
+  /// Used internally to reapply the switch configuration to the hardware
+  pub(crate) fn configs(&self) -> impl Iterator<Item = (&Switch, &SwitchConfig)> {
+    self
+      .configs
+      .iter()
+      .filter_map(|(id, config)| self.by_id.get(id).map(|switch| (switch, config)))
+  }
+
+  /// The switch's current configuration, or the default if it was never configured
+  pub fn config(&self, switch_id: usize) -> SwitchConfig {
+    self.configs.get(&switch_id).cloned().unwrap_or_default()
+  }
+
+  /// Used internally to keep the stored configuration in step with the hardware after a reconfiguration
+  pub(crate) fn set_config(&mut self, switch_id: usize, config: SwitchConfig) {
+    self.configs.insert(switch_id, config);
+  }
+
   /// Used internally to define an additional virtual (non-hardware backed) switch
   pub(crate) fn add_virtual_switch(&mut self, switch_name: &'static str, id: usize) {
     let switch = Switch {
@@ -95,12 +161,16 @@ impl SwitchContext {
     self.by_id.insert(id, switch.clone());
     self.by_name.insert(switch_name, switch);
     self.is_closed.insert(id, false);
+    self.changed_at.insert(id, Instant::now());
   }
 
   /// Used internally to update switch state via switch events
   pub(crate) fn update_switch_state(&mut self, switch_id: usize, state: SwitchState) {
     let is_closed = matches!(state, SwitchState::Closed);
-    self.is_closed.insert(switch_id, is_closed);
+    // reports repeat the state of switches which haven't changed
+    if self.is_closed.insert(switch_id, is_closed) != Some(is_closed) {
+      self.changed_at.insert(switch_id, Instant::now());
+    }
   }
 
   /// Used internally to update all switch states based on a switch report
@@ -0,0 +1,8 @@
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A bidirectional byte stream that the FAST serial protocol can be spoken over. This is implemented for anything
+/// that is both `AsyncRead` and `AsyncWrite`, e.g. a `tokio_serial::SerialStream`, a `tokio::io::DuplexStream`,
+/// a PTY or a `tokio::net::TcpStream`.
+pub trait Transport: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Transport for T {}
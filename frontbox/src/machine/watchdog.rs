@@ -1,12 +1,17 @@
 use std::time::Duration;
 
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 
 use crate::machine::machine_command::MachineCommand;
 
 pub struct Watchdog {
   machine_to_watchdog_sender: mpsc::UnboundedSender<MachineToWatchdog>,
   enabled: bool,
+  /// Refreshes which failed or went unanswered since the last one the hardware accepted
+  missed_refreshes: u32,
+  last_refresh: Instant,
+  expired: bool,
 }
 
 impl Watchdog {
@@ -35,6 +40,9 @@ impl Watchdog {
     Self {
       machine_to_watchdog_sender: enablement_sender,
       enabled: false,
+      missed_refreshes: 0,
+      last_refresh: Instant::now(),
+      expired: false,
     }
   }
 
@@ -44,6 +52,8 @@ impl Watchdog {
       .send(MachineToWatchdog::Enable)
       .ok();
     self.enabled = true;
+    self.missed_refreshes = 0;
+    self.last_refresh = Instant::now();
   }
 
   pub fn disable(&mut self) {
@@ -53,6 +63,38 @@ impl Watchdog {
       .ok();
     self.enabled = false;
   }
+
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+
+  /// Whether the hardware watchdog ran out, and high voltage is off, since the last accepted refresh
+  pub fn is_expired(&self) -> bool {
+    self.expired
+  }
+
+  pub fn missed_refreshes(&self) -> u32 {
+    self.missed_refreshes
+  }
+
+  /// The hardware accepted a refresh. Returns true if the watchdog had expired and is running again.
+  pub fn refreshed(&mut self) -> bool {
+    self.missed_refreshes = 0;
+    self.last_refresh = Instant::now();
+    std::mem::replace(&mut self.expired, false)
+  }
+
+  /// A refresh failed or went unanswered. Returns true if the watchdog has now gone longer than `timeout` without a
+  /// refresh, so the hardware will have turned off high voltage.
+  pub fn missed(&mut self, timeout: Duration) -> bool {
+    self.missed_refreshes += 1;
+    !self.expired && self.last_refresh.elapsed() >= timeout && self.expire()
+  }
+
+  /// The hardware reported that the watchdog expired. Returns true if it wasn't already known to be expired.
+  pub fn expire(&mut self) -> bool {
+    !std::mem::replace(&mut self.expired, true)
+  }
 }
 
 pub enum MachineToWatchdog {
@@ -6,8 +6,10 @@ pub struct FreePlay {
 }
 
 impl FreePlay {
-  pub fn new(start_button_id: &'static str) -> Box<Self> {
-    Box::new(Self { start_button_id })
+  pub fn new(start_button: impl SwitchId) -> Box<Self> {
+    Box::new(Self {
+      start_button_id: start_button.switch_name(),
+    })
   }
 }
 
@@ -1,3 +1,4 @@
 pub mod free_play;
 pub mod game_points;
 pub mod player_system;
+pub mod switch_sequence;
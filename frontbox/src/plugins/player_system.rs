@@ -62,6 +62,7 @@ impl PlayerSystem {
       .collect();
     self.player_scenes.push(copy);
     self.player_stores.push(Store::new());
+    self.player_states.push(States::new());
   }
 
   fn iterate_current_systems(
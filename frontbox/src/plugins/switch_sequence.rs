@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use tokio::time::Instant;
+
+use crate::prelude::*;
+
+/// Recognizes switches closing in order within a time window, e.g. an orbit or a ramp combo, and emits an event when
+/// the sequence completes:
+///
+/// ```ignore
+/// SwitchSequence::new(
+///   vec![switches::LEFT_ORBIT, switches::RIGHT_ORBIT],
+///   Duration::from_secs(2),
+///   || LeftOrbitMade::new(),
+/// )
+/// .ignoring(vec![switches::SPINNER])
+/// ```
+///
+/// Any other switch closing part way through starts the sequence over, unless it's ignored. Progress is kept in the
+/// system itself, so a sequence added to a `PlayerSystem` scene is tracked separately for each player.
+#[derive(Clone)]
+pub struct SwitchSequence {
+  switches: Vec<&'static str>,
+  within: Duration,
+  step_timeout: Option<Duration>,
+  ignored: Vec<&'static str>,
+  event: Arc<dyn Fn() -> Box<dyn FrontboxEvent> + Send + Sync>,
+  /// Number of switches matched so far
+  progress: usize,
+  started_at: Option<Instant>,
+  last_step_at: Option<Instant>,
+}
+
+impl SwitchSequence {
+  /// Emit the event from `event` when every switch closes in order, with no more than `within` between the first
+  /// and the last
+  pub fn new<S: SwitchId>(
+    switches: Vec<S>,
+    within: Duration,
+    event: impl Fn() -> Box<dyn FrontboxEvent> + Send + Sync + 'static,
+  ) -> Box<Self> {
+    if switches.is_empty() {
+      panic!("Switch sequence needs at least one switch");
+    }
+
+    Box::new(Self {
+      switches: switches.iter().map(|switch| switch.switch_name()).collect(),
+      within,
+      step_timeout: None,
+      ignored: Vec::new(),
+      event: Arc::new(event),
+      progress: 0,
+      started_at: None,
+      last_step_at: None,
+    })
+  }
+
+  /// Switches which can close part way through without starting the sequence over, e.g. a spinner on a ramp
+  pub fn ignoring<S: SwitchId>(mut self: Box<Self>, switches: Vec<S>) -> Box<Self> {
+    self.ignored = switches.iter().map(|switch| switch.switch_name()).collect();
+    self
+  }
+
+  /// Also limit the time between one switch in the sequence and the next
+  pub fn with_step_timeout(mut self: Box<Self>, timeout: Duration) -> Box<Self> {
+    self.step_timeout = Some(timeout);
+    self
+  }
+
+  fn reset(&mut self) {
+    self.progress = 0;
+    self.started_at = None;
+    self.last_step_at = None;
+  }
+
+  fn is_timed_out(&self, now: Instant) -> bool {
+    let past_window = self
+      .started_at
+      .is_some_and(|started_at| now - started_at > self.within);
+    let past_step = match (self.step_timeout, self.last_step_at) {
+      (Some(timeout), Some(last_step_at)) => now - last_step_at > timeout,
+      _ => false,
+    };
+    past_window || past_step
+  }
+
+  fn on_switch_closed(&mut self, switch_name: &'static str, cmds: &mut Commands) {
+    let now = Instant::now();
+    if self.progress > 0 && self.is_timed_out(now) {
+      self.reset();
+    }
+
+    if self.switches[self.progress] == switch_name {
+      if self.progress == 0 {
+        self.started_at = Some(now);
+      }
+      self.progress += 1;
+      self.last_step_at = Some(now);
+    } else if self.progress > 0 && self.switches[self.progress - 1] == switch_name {
+      // the switch just matched closed again, e.g. a rollover hit twice
+      return;
+    } else if self.switches[0] == switch_name {
+      self.reset();
+      self.progress = 1;
+      self.started_at = Some(now);
+      self.last_step_at = Some(now);
+    } else if self.progress > 0 && !self.ignored.contains(&switch_name) {
+      self.reset();
+    }
+
+    if self.progress == self.switches.len() {
+      self.reset();
+      cmds.emit((self.event)());
+    }
+  }
+}
+
+impl CloneableSystem for SwitchSequence {
+  fn on_event(&mut self, event: &dyn FrontboxEvent, _ctx: &Context, cmds: &mut Commands) {
+    handle_event!(event, {
+      SwitchClosed => |e| { self.on_switch_closed(e.switch.name, cmds); }
+    });
+  }
+}
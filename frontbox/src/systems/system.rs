@@ -43,7 +43,36 @@ pub trait CloneableSystem: DynClone + Send + Sync {
 
 dyn_clone::clone_trait_object!(CloneableSystem);
 
-impl<T: CloneableSystem> System for T {}
+// forward every hook, otherwise `System`'s empty defaults would run instead of the system's own
+impl<T: CloneableSystem> System for T {
+  fn on_startup(&mut self, ctx: &Context, cmds: &mut Commands) {
+    CloneableSystem::on_startup(self, ctx, cmds);
+  }
+
+  fn on_shutdown(&mut self, ctx: &Context, cmds: &mut Commands) {
+    CloneableSystem::on_shutdown(self, ctx, cmds);
+  }
+
+  fn on_timer(&mut self, timer_name: &'static str, ctx: &Context, cmds: &mut Commands) {
+    CloneableSystem::on_timer(self, timer_name, ctx, cmds);
+  }
+
+  fn on_tick(&mut self, delta: Duration, ctx: &Context, cmds: &mut Commands) {
+    CloneableSystem::on_tick(self, delta, ctx, cmds);
+  }
+
+  fn on_event(&mut self, event: &dyn FrontboxEvent, ctx: &Context, cmds: &mut Commands) {
+    CloneableSystem::on_event(self, event, ctx, cmds);
+  }
+
+  fn is_active(&self, ctx: &Context) -> bool {
+    CloneableSystem::is_active(self, ctx)
+  }
+
+  fn leds(&mut self, delta_time: Duration, ctx: &Context) -> HashMap<&'static str, LedState> {
+    CloneableSystem::leds(self, delta_time, ctx)
+  }
+}
 
 impl System for Box<dyn CloneableSystem> {
   fn on_startup(&mut self, ctx: &Context, cmds: &mut Commands) {
@@ -74,3 +103,75 @@ impl System for Box<dyn CloneableSystem> {
     self.as_mut().leds(delta_time, ctx)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::sync::mpsc;
+
+  /// Records which hooks ran
+  #[derive(Clone, Default)]
+  struct Recorder {
+    calls: Vec<&'static str>,
+  }
+
+  impl CloneableSystem for Recorder {
+    fn on_startup(&mut self, _ctx: &Context, _cmds: &mut Commands) {
+      self.calls.push("startup");
+    }
+
+    fn on_shutdown(&mut self, _ctx: &Context, _cmds: &mut Commands) {
+      self.calls.push("shutdown");
+    }
+
+    fn on_timer(&mut self, timer_name: &'static str, _ctx: &Context, _cmds: &mut Commands) {
+      self.calls.push(timer_name);
+    }
+
+    fn on_tick(&mut self, _delta: Duration, _ctx: &Context, _cmds: &mut Commands) {
+      self.calls.push("tick");
+    }
+
+    fn on_event(&mut self, _event: &dyn FrontboxEvent, _ctx: &Context, _cmds: &mut Commands) {
+      self.calls.push("event");
+    }
+
+    fn is_active(&self, _ctx: &Context) -> bool {
+      false
+    }
+
+    fn leds(&mut self, _delta_time: Duration, _ctx: &Context) -> HashMap<&'static str, LedState> {
+      HashMap::from([("lamp", LedState::Off)])
+    }
+  }
+
+  #[test]
+  fn test_cloneable_system_hooks_are_forwarded() {
+    let config = MachineConfig::new();
+    let states = States::new();
+    let store = Store::new();
+    let switches = SwitchContext::new(Vec::new(), Vec::new());
+    let ctx = Context::new(&config, &None, &states, &store, &switches);
+    let mut cmds = Commands::new(
+      mpsc::unbounded_channel().0,
+      mpsc::unbounded_channel().0,
+      mpsc::unbounded_channel().0,
+      0,
+    );
+
+    let mut recorder = Recorder::default();
+    let system: &mut dyn System = &mut recorder;
+    system.on_startup(&ctx, &mut cmds);
+    system.on_timer("timer", &ctx, &mut cmds);
+    system.on_tick(Duration::from_millis(10), &ctx, &mut cmds);
+    system.on_event(HardwareReconnected::new().as_ref(), &ctx, &mut cmds);
+    system.on_shutdown(&ctx, &mut cmds);
+    assert!(!system.is_active(&ctx));
+    assert!(system.leds(Duration::ZERO, &ctx).contains_key("lamp"));
+
+    assert_eq!(
+      recorder.calls,
+      vec!["startup", "timer", "tick", "event", "shutdown"]
+    );
+  }
+}